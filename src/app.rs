@@ -1,31 +1,174 @@
 use std::{
 	error,
+	ops::Range,
 	vec,
 };
 
 use rand::{
 	prelude::SliceRandom,
 	Rng,
+	SeedableRng,
+};
+use rand_chacha::ChaCha8Rng;
+use ratatui::{
+	layout::Rect,
+	style::{
+		Color,
+		Style,
+	},
+	text::{
+		Line,
+		Span,
+	},
 };
-use ratatui::layout::Rect;
 
-use crate::tetromino::{
-	Tetromino,
-	TETROMINO_SHAPES,
+use crate::{
+	config::Config,
+	daily::{
+		self,
+		DailyChallenge,
+		DailyResult,
+	},
+	finesse::{
+		FinesseStats,
+		FinesseTrainer,
+		Input,
+	},
+	history::History,
+	leaderboard::Leaderboard,
+	master::{
+		self,
+		MasterState,
+		MAX_LEVEL,
+	},
+	mode::GameMode,
+	opener::{
+		OpenerState,
+		OPENERS,
+	},
+	pc::PcTrainer,
+	puzzle::{
+		self,
+		Goal,
+		Puzzle,
+		PuzzleState,
+		PuzzleStatus,
+	},
+	records,
+	replay::Replay,
+	screen::Screen,
+	settings::SettingsMenu,
+	stats::GameStats,
+	tetromino::{
+		Tetromino,
+		TetrominoKind,
+		TETROMINO_SHAPES,
+	},
+	viewer::ReplayViewer,
 };
 
 /// Application result type.
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 
+/// Milliseconds between two ticks of the game loop.
+pub const TICK_RATE: u64 = 30;
+
+/// Hidden cells around the board that let the 4x4 tetromino grid overhang it.
+pub const PLAYFIELD_PADDING: usize = 4;
+/// Columns of the standard board.
+pub const BOARD_WIDTH: usize = 10;
+/// Visible rows of the standard board.
+pub const BOARD_HEIGHT: usize = 22;
+
 //----------[ Structs ]----------//
 /// Application.
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct PlayFieldCell {
 	pub falling: bool,
 	pub landed: bool,
+	/// Tick at which the cell landed.
+	pub locked_at: u32,
+	/// Tetromino the landed cell belongs to, `None` for garbage.
+	pub kind: Option<TetrominoKind>,
 }
 
-#[derive(Debug)]
+impl PlayFieldCell {
+	/// Returns a landed cell of the given tetromino type, or garbage.
+	pub fn filled(kind: Option<TetrominoKind>) -> Self {
+		Self {
+			landed: true,
+			kind,
+			..Self::default()
+		}
+	}
+
+	/// Parses a cell written by [`PlayFieldCell::to_char`].
+	pub fn from_char(cell: char) -> Option<Self> {
+		match cell {
+			'.' => Some(Self::default()),
+			'X' | 'x' => Some(Self::filled(None)),
+			_ => TetrominoKind::from_letter(cell).map(|kind| Self::filled(Some(kind))),
+		}
+	}
+
+	/// Returns `.` for an empty cell, `X` for garbage or the letter of the tetromino.
+	pub fn to_char(&self) -> char {
+		match (self.landed, self.kind) {
+			(false, _) => '.',
+			(true, None) => 'X',
+			(true, Some(kind)) => kind.letter(),
+		}
+	}
+
+	/// Returns the color the landed cell is drawn with.
+	pub fn color(&self) -> Color {
+		self.kind.map_or(Color::Gray, |kind| kind.color())
+	}
+}
+
+/// Where the current tetromino is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Phase {
+	/// A tetromino is falling and can be controlled.
+	#[default]
+	Falling,
+	/// Cleared lines are shown for the given number of ticks before collapsing.
+	LineClear(u32),
+	/// The next tetromino spawns after the given number of ticks.
+	Are(u32),
+}
+
+/// A shift key held down, as far as the terminal reports key releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeldShift {
+	/// Left (-1) or right (1).
+	pub direction: i32,
+	/// Tick at which the key was pressed.
+	pub pressed_at: u32,
+	/// Tick at which the key last shifted the tetromino.
+	pub shifted_at: u32,
+	/// Has the key been held for longer than DAS?
+	pub charged: bool,
+}
+
+/// What happened when a tetromino locked.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LockResult {
+	/// Type of the locked tetromino.
+	pub tetromino: Option<TetrominoKind>,
+	/// Playfield cells the tetromino locked on, before lines were cleared.
+	pub cells: Vec<(usize, usize)>,
+	/// Lines cleared by the lock.
+	pub lines: u32,
+	/// Was the tetromino a T rotated into a slot with three filled corners?
+	pub t_spin: bool,
+	/// Is the board empty after the clear?
+	pub perfect_clear: bool,
+	/// Finesse faults of the placement, when its optimal inputs could be found.
+	pub finesse: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
 pub struct App {
 	/// Is the application running?
 	pub running: bool,
@@ -49,21 +192,123 @@ pub struct App {
 
 	pub grace_period: bool,
 
-	pub paused: bool,
+	/// What the application shows.
+	pub screen: Screen,
+	/// Index of the selected item of a menu screen.
+	pub menu_selected: usize,
+	/// Where the items of a menu screen were last drawn on the terminal.
+	pub menu_buttons: Vec<Rect>,
+	/// Ticks left before a resumed game goes on.
+	pub countdown: u32,
 
 	pub buttons: Vec<Rect>,
 
 	pub score: u32,
 	pub high_score: u32,
 	pub level: u32,
+
+	/// Rule set of the current game.
+	pub mode: GameMode,
+	/// Lifecycle phase of the current tetromino.
+	pub phase: Phase,
+	/// Has the game ended?
+	pub game_over: bool,
+	/// Accumulated gravity in 1/256 rows.
+	pub gravity_accumulator: u32,
+	/// Ticks the current tetromino has been resting on the stack.
+	pub lock_timer: u32,
+	/// Progress of a Master game.
+	pub master: MasterState,
+	/// Ticks played so far, which undo turns back.
+	pub elapsed_ticks: u32,
+	/// Ticks since the game started, which undo leaves alone. Replay inputs are timed by them.
+	pub replay_ticks: u32,
+	/// Ticks a landed block stays visible in the Fading mode.
+	pub fade_ticks: u32,
+	/// Columns of the board, without padding.
+	pub board_width: usize,
+	/// Visible rows of the board, without padding.
+	pub board_height: usize,
+	/// Seed of the tetromino sequence.
+	pub seed: u64,
+	/// Random number generator drawing the tetromino sequence. Its algorithm is fixed, so that a seed
+	/// deals the same tetrominos to replays and daily challenges in every version.
+	pub rng: ChaCha8Rng,
+	/// The daily challenge being played, if any.
+	pub daily: Option<DailyChallenge>,
+	/// Was the last successful movement of the tetromino a rotation?
+	pub last_move_rotation: bool,
+	/// The puzzle being played, if any.
+	pub puzzle: Option<PuzzleState>,
+	/// Are results such as high scores and puzzle progress saved to disk?
+	pub record_results: bool,
+	/// Did the finished game beat every recorded game of its mode?
+	pub personal_best: bool,
+	/// Inputs of the game so far, to save as a replay.
+	pub replay: Replay,
+	/// Playback of a replay, if one is watched.
+	pub viewer: Option<Box<ReplayViewer>>,
+	/// Saved replays listed on the replays screen.
+	pub replay_list: Vec<Result<Replay, String>>,
+	/// Is the board being edited instead of played?
+	pub editing: bool,
+	/// Tetromino whose color the editor paints with, `None` for garbage.
+	pub brush: Option<TetrominoKind>,
+	/// Where the playfield was last drawn on the terminal.
+	pub playfield_area: Rect,
+	/// Placements and edits that can be undone.
+	pub history: History,
+	/// Feedback shown below the score, such as where a setup was saved.
+	pub status_message: Option<String>,
+	/// Is this a practice game, where placements can be undone and nothing is recorded?
+	pub practice: bool,
+	/// The opener being trained, if any.
+	pub opener: Option<OpenerState>,
+	/// Progress of the perfect clear trainer, if it is being played.
+	pub pc: Option<PcTrainer>,
+	/// Inputs and finesse faults of the game.
+	pub finesse: FinesseStats,
+	/// Progress of the finesse trainer, if it is being played.
+	pub finesse_trainer: Option<FinesseTrainer>,
+	/// Statistics of the game so far.
+	pub stats: GameStats,
+	/// What happened when the last tetromino locked.
+	pub last_lock: Option<LockResult>,
+	/// Are the detailed statistics shown over the playfield?
+	pub show_stats: bool,
+	/// Level the game started at.
+	pub start_level: u32,
+	/// Best games of every mode.
+	pub leaderboard: Leaderboard,
+	/// Name being typed for the leaderboard, while it is asked for.
+	pub name_entry: Option<String>,
+	/// Is the board of the game shown over the playfield?
+	pub show_leaderboard: bool,
+	/// Options of the config file and the command line.
+	pub config: Config,
+	/// Options of the config file alone, which the settings menu saves.
+	pub file_config: Config,
+	/// Fumen of a board exported from the Sandbox, printed once tetrs quits.
+	pub fumen_export: Option<String>,
+	/// Does the terminal report key releases, so that held shift keys can charge DAS?
+	pub key_releases: bool,
+	/// The shift key being held down, if any.
+	pub held_shift: Option<HeldShift>,
+	/// State of the settings menu.
+	pub settings: SettingsMenu,
 }
 
 impl Default for App {
 	fn default() -> Self {
+		let seed = rand::random();
+		let mut rng = ChaCha8Rng::seed_from_u64(seed);
 		Self {
 			running: true,
-			playfield: vec![vec![PlayFieldCell::default(); 18]; 26],
-			current_tetromino: TETROMINO_SHAPES[rand::thread_rng().gen_range(0..7)],
+			playfield: vec![
+				vec![PlayFieldCell::default(); BOARD_WIDTH + 2 * PLAYFIELD_PADDING];
+				BOARD_HEIGHT + PLAYFIELD_PADDING
+			],
+			current_tetromino: TETROMINO_SHAPES[rng.gen_range(0..7)],
 			swap_tetromino: Tetromino {
 				rotations: [
 					[
@@ -102,11 +347,58 @@ impl Default for App {
 			tick_count_target: 0,
 			default_tick_count_target: 15,
 			grace_period: false,
-			paused: false,
+			screen: Screen::default(),
+			menu_selected: 0,
+			menu_buttons: vec![],
+			countdown: 0,
 			buttons: vec![],
 			score: 0,
 			high_score: 0,
 			level: 1,
+			mode: GameMode::default(),
+			phase: Phase::default(),
+			game_over: false,
+			gravity_accumulator: 0,
+			lock_timer: 0,
+			master: MasterState::default(),
+			elapsed_ticks: 0,
+			replay_ticks: 0,
+			fade_ticks: 5000 / TICK_RATE as u32,
+			board_width: BOARD_WIDTH,
+			board_height: BOARD_HEIGHT,
+			seed,
+			rng,
+			daily: None,
+			last_move_rotation: false,
+			puzzle: None,
+			record_results: false,
+			personal_best: false,
+			replay: Replay::default(),
+			viewer: None,
+			replay_list: vec![],
+			editing: false,
+			brush: None,
+			playfield_area: Rect::default(),
+			history: History::default(),
+			status_message: None,
+			practice: false,
+			opener: None,
+			pc: None,
+			finesse: FinesseStats::default(),
+			finesse_trainer: None,
+			stats: GameStats::default(),
+			last_lock: None,
+			show_stats: false,
+			start_level: 1,
+			leaderboard: Leaderboard::default(),
+			name_entry: None,
+			show_leaderboard: false,
+			config: Config::default(),
+			file_config: Config::default(),
+			fumen_export: None,
+			key_releases: false,
+			held_shift: None,
+			settings: SettingsMenu::default(),
 		}
 	}
 }
@@ -119,20 +411,96 @@ impl App {
 		Self::default()
 	}
 
+	/// Constructs a new instance of [`App`] playing the given mode.
+	pub fn with_mode(mode: GameMode) -> Self {
+		Self::with_seed(mode, rand::random())
+	}
+
+	/// Constructs a new instance of [`App`] playing the given mode with a fixed tetromino
+	/// sequence.
+	pub fn with_seed(mode: GameMode, seed: u64) -> Self {
+		let mut rng = ChaCha8Rng::seed_from_u64(seed);
+		let mut app = Self {
+			mode,
+			current_tetromino: TETROMINO_SHAPES[rng.gen_range(0..7)],
+			seed,
+			rng,
+			..Self::default()
+		};
+		if mode == GameMode::Master {
+			app.level = 0;
+			app.phase = Phase::Are(master::timings(0).are);
+		}
+		if mode == GameMode::Big {
+			app.resize_board(BOARD_WIDTH / 2, BOARD_HEIGHT / 2);
+		}
+		if mode == GameMode::Sandbox {
+			// Without gravity the tetromino would otherwise only show up on the first input.
+			app.spawn_next_tetromino();
+		}
+		if mode == GameMode::Opener {
+			app.opener = Some(OpenerState::new(&OPENERS[0], false));
+			// The first tetromino has to come from the first bag.
+			app.spawn_next_tetromino();
+		}
+		if mode == GameMode::PerfectClear {
+			app.pc = Some(PcTrainer::default());
+			app.next_pc_board();
+		}
+		if mode == GameMode::Finesse {
+			app.finesse_trainer = Some(FinesseTrainer::new(seed));
+			// Without gravity the tetromino would otherwise only show up on the first input.
+			app.spawn_next_tetromino();
+		}
+		app
+	}
+
+	/// Replaces the playfield with an empty board of the given size and recenters the spawn
+	/// position.
+	pub fn resize_board(&mut self, width: usize, height: usize) {
+		self.board_width = width;
+		self.board_height = height;
+		self.playfield = vec![self.empty_row(); height + PLAYFIELD_PADDING];
+		self.start_x = PLAYFIELD_PADDING + (width - 3) / 2;
+		self.x = self.start_x;
+		self.y = self.start_y;
+	}
+
 	/// Handles the tick event of the terminal.
 	pub fn tick(&mut self) {
-		if self.paused {
+		match self.screen {
+			Screen::Playing if self.countdown > 0 => self.countdown -= 1,
+			Screen::Playing => self.advance(),
+			Screen::Watching => self.tick_viewer(),
+			_ => {}
+		}
+	}
+
+	/// Advances the game by one tick.
+	pub fn advance(&mut self) {
+		if self.game_over {
 			return;
 		}
 
-		if self.tetromino_queue.len() < 7 {
+		if self.tetromino_queue.len() < 7 && !self.has_fixed_queue() && !self.editable() {
 			self.populate_tetromino_queue();
 		}
 
+		self.elapsed_ticks += 1;
+		self.replay_ticks += 1;
+		self.auto_shift();
+		match self.mode {
+			GameMode::Master => self.tick_master(),
+			// Tetrominos only move on input in the Sandbox and the finesse trainer.
+			GameMode::Sandbox | GameMode::Finesse => {}
+			_ => self.tick_marathon(),
+		}
+	}
+
+	/// Advances a Marathon game by one tick.
+	fn tick_marathon(&mut self) {
 		self.tick_count += 1;
 		if self.tick_count > self.tick_count_target {
-			self.score += self.check_for_line_clear().pow(2) * 100 * self.level;
-			self.check_for_next_level();
 			if self.has_landed_cells_at_offset(0, 1) {
 				if self.grace_period {
 					self.reset_tetromino();
@@ -142,20 +510,376 @@ impl App {
 
 			self.tick_count = 0;
 
-			if !self.has_landed_cells_at_offset(0, 1) {
+			if !self.game_over && !self.has_landed_cells_at_offset(0, 1) {
 				self.move_tetromino(0, 1, self.current_tetromino);
+				self.last_move_rotation = false;
 				self.grace_period = false;
 			}
 		}
 		self.tick_count_target = self.default_tick_count_target;
 	}
 
+	/// Advances a Master game by one tick, stepping through line clear delay and ARE.
+	fn tick_master(&mut self) {
+		match self.phase {
+			Phase::LineClear(remaining) if remaining > 1 => {
+				self.phase = Phase::LineClear(remaining - 1);
+			}
+			Phase::LineClear(_) => {
+				self.check_for_line_clear();
+				self.phase = Phase::Are(master::timings(self.level).are);
+			}
+			Phase::Are(remaining) if remaining > 1 => {
+				self.phase = Phase::Are(remaining - 1);
+			}
+			Phase::Are(_) => {
+				self.phase = Phase::Falling;
+				self.spawn_next_tetromino();
+				let old_level = self.level;
+				self.level = MasterState::level_after_spawn(self.level);
+				self.master.update(old_level, self.level, self.score, self.elapsed_ticks);
+				self.apply_gravity();
+			}
+			Phase::Falling => self.apply_gravity(),
+		}
+	}
+
+	/// Moves the tetromino down by the gravity of the current level and locks it once the lock
+	/// delay has run out.
+	fn apply_gravity(&mut self) {
+		self.gravity_accumulator += master::gravity(self.level);
+		while self.gravity_accumulator >= 256 {
+			if self.has_landed_cells_at_offset(0, 1) {
+				self.gravity_accumulator = 0;
+				break;
+			}
+			self.move_tetromino(0, 1, self.current_tetromino);
+			self.last_move_rotation = false;
+			self.gravity_accumulator -= 256;
+			self.lock_timer = 0;
+		}
+
+		if self.has_landed_cells_at_offset(0, 1) {
+			self.lock_timer += 1;
+			if self.lock_timer >= master::timings(self.level).lock_delay {
+				self.lock_tetromino();
+			}
+		}
+	}
+
+	/// Lands the tetromino in a Master game, scores it and starts the line clear delay or ARE.
+	fn lock_tetromino(&mut self) {
+		self.save_snapshot();
+		let t_spin = self.is_t_spin();
+		let cells = self.tetromino_cells();
+		let finesse = self.judge_finesse(&cells);
+		self.land_tetromino();
+		self.clear_falling();
+		self.lock_timer = 0;
+		self.gravity_accumulator = 0;
+
+		let full_rows = self.full_rows();
+		let lines = full_rows.len() as u32;
+		let bravo = lines > 0
+			&& self.playfield.iter().enumerate().all(|(y, row)| {
+				full_rows.contains(&y) || row.iter().all(|cell| !cell.landed)
+			});
+
+		let old_level = self.level;
+		self.score += self.master.score_line_clear(old_level, lines, bravo);
+		self.level = (self.level + lines).min(MAX_LEVEL);
+		self.master.update(old_level, self.level, self.score, self.elapsed_ticks);
+		self.on_lock(LockResult {
+			tetromino: self.current_tetromino.kind(),
+			cells,
+			lines,
+			t_spin,
+			perfect_clear: bravo,
+			finesse,
+		});
+		if self.master.completed {
+			self.end_game();
+		}
+		if self.game_over {
+			return;
+		}
+
+		let timings = master::timings(self.level);
+		self.phase = if lines > 0 {
+			Phase::LineClear(timings.line_clear)
+		} else {
+			Phase::Are(timings.are)
+		};
+	}
+
+	/// Handles everything that depends on the outcome of a lock.
+	fn on_lock(&mut self, result: LockResult) {
+		self.last_lock = Some(result.clone());
+		self.stats.record_lock(&result);
+		self.check_opener_placement(result.tetromino, &result.cells);
+		self.check_finesse_placement(result.tetromino, &result.cells, result.finesse);
+		if let Some(state) = &mut self.puzzle {
+			state.pieces += 1;
+			state.lines += result.lines;
+			let solved = match state.puzzle.goal {
+				Some(Goal::Lines(lines)) => state.lines >= lines,
+				Some(Goal::PerfectClear) => result.perfect_clear,
+				Some(Goal::Tsd) => result.t_spin && result.lines == 2,
+				Some(Goal::Survive(pieces)) => state.pieces >= pieces,
+				None => false,
+			};
+			if solved {
+				state.status = PuzzleStatus::Solved;
+				if self.record_results && self.mode == GameMode::Puzzle {
+					let result = puzzle::record_solved(&state.puzzle.id);
+					self.report_save_error(result);
+				}
+				self.end_game();
+			}
+		}
+	}
+
+	/// Ends the game, recording it and saving its replay, along with the result of a scored daily
+	/// challenge, which replaces the attempt recorded when it started.
+	pub fn end_game(&mut self) {
+		if self.record_results && !self.game_over && self.mode.keeps_records() {
+			let record = self.game_record();
+			self.personal_best = records::is_personal_best(&records::load_records(), &record);
+			let result = records::record_game(&record);
+			self.report_save_error(result);
+		}
+		if self.record_results && !self.game_over && self.mode.has_replays() {
+			self.save_replay();
+		}
+		if !self.game_over {
+			self.check_leaderboard();
+		}
+		self.game_over = true;
+		if let Some(state) = self.puzzle.as_mut().filter(|state| state.status == PuzzleStatus::Playing)
+		{
+			state.status = PuzzleStatus::Failed;
+		}
+		if let Some(state) = &self.puzzle {
+			self.finish_pc_board(state.status == PuzzleStatus::Solved);
+		}
+		if let Some(challenge) = self
+			.daily
+			.filter(|challenge| self.record_results && !challenge.practice)
+		{
+			let result = daily::record_result(&DailyResult {
+				date: challenge.date,
+				score: self.score,
+				level: self.level,
+				ticks: self.elapsed_ticks,
+			});
+			self.report_save_error(result);
+			self.daily = Some(DailyChallenge {
+				practice: true,
+				..challenge
+			});
+		}
+		if self.name_entry.is_none() {
+			self.show_results();
+		}
+	}
+
 	/// Set running to false to quit the application.
 	pub fn quit(&mut self) {
 		self.running = false;
 	}
 	//--------------------------------------//
 
+	//----------[ Actions ]----------//
+	/// Can the player currently control the tetromino?
+	pub fn can_control(&self) -> bool {
+		matches!(self.screen, Screen::Playing | Screen::Watching)
+			&& self.countdown == 0
+			&& !self.game_over
+			&& !self.editing
+			&& self.phase == Phase::Falling
+	}
+
+	/// Moves the tetromino one column left (-1) or right (1) if there is room.
+	pub fn shift_tetromino(&mut self, direction: i32) {
+		if !self.can_control() {
+			return;
+		}
+		self.stats.keys += 1;
+		self.log_input(if direction < 0 {
+			Input::Left
+		} else {
+			Input::Right
+		});
+		if !self.has_landed_cells_at_offset(direction, 0) {
+			self.move_tetromino(direction, 0, self.current_tetromino);
+			self.last_move_rotation = false;
+		}
+	}
+
+	/// Moves the tetromino left (-1) or right (1) as far as it goes, like a fully charged DAS.
+	pub fn das_tetromino(&mut self, direction: i32) {
+		if !self.can_control() {
+			return;
+		}
+		self.stats.keys += 1;
+		self.log_input(if direction < 0 {
+			Input::DasLeft
+		} else {
+			Input::DasRight
+		});
+		while !self.has_landed_cells_at_offset(direction, 0) {
+			self.move_tetromino(direction, 0, self.current_tetromino);
+			self.last_move_rotation = false;
+		}
+	}
+
+	/// Shifts the tetromino once for a pressed shift key and keeps track of the key while it is
+	/// held, when the terminal reports its release.
+	pub fn press_shift(&mut self, direction: i32) {
+		self.shift_tetromino(direction);
+		if self.key_releases {
+			self.held_shift = Some(HeldShift {
+				direction,
+				pressed_at: self.replay_ticks,
+				shifted_at: self.replay_ticks,
+				charged: false,
+			});
+		}
+	}
+
+	/// Stops repeating the shift of a released key.
+	pub fn release_shift(&mut self, direction: i32) {
+		if self
+			.held_shift
+			.is_some_and(|held| held.direction == direction)
+		{
+			self.held_shift = None;
+		}
+	}
+
+	/// Repeats the shift of a held key once it is held for longer than DAS, every ARR or straight
+	/// to the wall.
+	fn auto_shift(&mut self) {
+		let Some(mut held) = self.held_shift else {
+			return;
+		};
+		let handling = self.config.handling;
+		if !self.can_control() || self.replay_ticks - held.pressed_at < handling.das_ticks() {
+			return;
+		}
+		if !held.charged {
+			// A charged shift counts as a single DAS input instead of the tap that started it.
+			let (tap, das) = if held.direction < 0 {
+				(Input::Left, Input::DasLeft)
+			} else {
+				(Input::Right, Input::DasRight)
+			};
+			if self.finesse.inputs.last() == Some(&tap) {
+				self.finesse.inputs.pop();
+			}
+			self.log_input(das);
+			held.charged = true;
+		}
+		if handling.arr_ticks() == 0 {
+			while !self.has_landed_cells_at_offset(held.direction, 0) {
+				self.move_tetromino(held.direction, 0, self.current_tetromino);
+				self.last_move_rotation = false;
+			}
+		} else if self.replay_ticks - held.shifted_at >= handling.arr_ticks()
+			&& !self.has_landed_cells_at_offset(held.direction, 0)
+		{
+			self.move_tetromino(held.direction, 0, self.current_tetromino);
+			self.last_move_rotation = false;
+			held.shifted_at = self.replay_ticks;
+		}
+		self.held_shift = Some(held);
+	}
+
+	/// Rotates the tetromino, kicking it one column to either side if it does not fit.
+	pub fn rotate_tetromino(&mut self, clockwise: bool) {
+		if !self.can_control() {
+			return;
+		}
+		self.stats.keys += 1;
+		self.log_input(if clockwise {
+			Input::RotateCw
+		} else {
+			Input::RotateCcw
+		});
+		let previous_rotation = self.current_rotation;
+		self.current_rotation = (self.current_rotation + if clockwise { 1 } else { 3 }) % 4;
+		for kick in [0, 1, -1] {
+			if !self.has_landed_cells_at_offset(kick, 0) {
+				self.move_tetromino(kick, 0, self.current_tetromino);
+				self.last_move_rotation = true;
+				return;
+			}
+		}
+		self.current_rotation = previous_rotation;
+	}
+
+	/// Speeds up the fall of the tetromino.
+	pub fn soft_drop(&mut self) {
+		if !self.can_control() {
+			return;
+		}
+		self.stats.keys += 1;
+		match self.mode {
+			GameMode::Master => {
+				if self.has_landed_cells_at_offset(0, 1) {
+					self.lock_tetromino();
+				} else {
+					self.move_tetromino(0, 1, self.current_tetromino);
+					self.last_move_rotation = false;
+					self.master.soft_drop += 1;
+					self.lock_timer = 0;
+				}
+			}
+			_ if self.config.handling.sdf == 0 => self.sonic_drop(),
+			GameMode::Sandbox => {
+				if !self.has_landed_cells_at_offset(0, 1) {
+					self.move_tetromino(0, 1, self.current_tetromino);
+					self.last_move_rotation = false;
+				}
+			}
+			_ => self.tick_count_target = self.default_tick_count_target / self.config.handling.sdf,
+		}
+	}
+
+	/// Moves the tetromino to the bottom without locking it.
+	pub fn sonic_drop(&mut self) {
+		while !self.has_landed_cells_at_offset(0, 1) {
+			self.move_tetromino(0, 1, self.current_tetromino);
+			self.last_move_rotation = false;
+		}
+	}
+
+	/// Drops the tetromino to the bottom and locks it.
+	pub fn hard_drop(&mut self) {
+		if !self.can_control() {
+			return;
+		}
+		self.stats.keys += 1;
+		match self.mode {
+			GameMode::Master => {
+				while !self.has_landed_cells_at_offset(0, 1) {
+					self.move_tetromino(0, 1, self.current_tetromino);
+				}
+				self.lock_tetromino();
+			}
+			_ => self.drop_tetromino(),
+		}
+	}
+
+	/// Swaps the tetromino with the held one.
+	pub fn hold_tetromino(&mut self) {
+		if self.can_control() {
+			self.stats.keys += 1;
+			self.swap_tetromino();
+		}
+	}
+	//-------------------------------//
+
 	//----------[ Tetromino Movement ]----------//
 	/// Randomly spawns a tetromino at the specified position on the playfield.
 	pub fn spawn_tetromino(
@@ -164,7 +888,10 @@ impl App {
 		start_y: usize,
 		tetromino: Tetromino,
 	) -> Tetromino {
-		self.check_for_game_over();
+		if self.check_for_game_over() {
+			self.end_game();
+		}
+		self.finesse.inputs.clear();
 
 		for y in 0..tetromino.rotations[self.current_rotation].len() {
 			for x in 0..tetromino.rotations[self.current_rotation][y].len() {
@@ -176,9 +903,16 @@ impl App {
 		tetromino
 	}
 
+	/// Is the tetromino sequence fixed by a puzzle?
+	pub fn has_fixed_queue(&self) -> bool {
+		self.puzzle
+			.as_ref()
+			.is_some_and(|state| !state.puzzle.queue.is_empty())
+	}
+
 	pub fn populate_tetromino_queue(&mut self) {
 		let mut tetromino_order: Vec<usize> = (0..7).collect();
-		tetromino_order.shuffle(&mut rand::thread_rng());
+		tetromino_order.shuffle(&mut self.rng);
 
 		for tetromino in tetromino_order {
 			self.tetromino_queue.push(TETROMINO_SHAPES[tetromino]);
@@ -187,13 +921,69 @@ impl App {
 
 	/// prepares for next tetromino.
 	pub fn reset_tetromino(&mut self) {
+		self.save_snapshot();
+		let t_spin = self.is_t_spin();
+		let cells = self.tetromino_cells();
+		let finesse = self.judge_finesse(&cells);
 		self.land_tetromino();
 		self.clear_falling();
+
+		let lines = self.check_for_line_clear();
+		self.score += lines.pow(2) * 100 * self.level;
+		self.check_for_next_level();
+		self.on_lock(LockResult {
+			tetromino: self.current_tetromino.kind(),
+			cells,
+			lines,
+			t_spin,
+			perfect_clear: lines > 0 && self.is_board_empty(),
+			finesse,
+		});
+
+		if !self.game_over {
+			self.spawn_next_tetromino();
+		}
+		self.auto_undo_opener();
+	}
+
+	/// Spawns the first tetromino of the queue at the start position.
+	pub fn spawn_next_tetromino(&mut self) {
+		if self.tetromino_queue.is_empty() && !self.has_fixed_queue() {
+			self.populate_tetromino_queue();
+		}
+		if self.tetromino_queue.is_empty() {
+			// A fixed sequence ran out.
+			self.end_game();
+			return;
+		}
 		self.x = self.start_x;
 		self.y = self.start_y;
 		self.current_rotation = 0;
 		self.current_tetromino = self.spawn_tetromino(self.x, self.y, self.tetromino_queue[0]);
 		self.tetromino_queue.remove(0);
+		self.pick_finesse_target();
+	}
+
+	/// Returns the playfield cells covered by the tetromino.
+	pub fn tetromino_cells(&self) -> Vec<(usize, usize)> {
+		let rotation = self.current_tetromino.rotations[self.current_rotation];
+		(0..4)
+			.flat_map(|y| (0..4).map(move |x| (x, y)))
+			.filter(|&(x, y)| rotation[y][x])
+			.map(|(x, y)| (self.x + x, self.y + y))
+			.collect()
+	}
+
+	/// Returns the playfield cells the tetromino would land on if it was dropped.
+	pub fn ghost_cells(&self) -> Vec<(usize, usize)> {
+		let mut drops = 0;
+		while !self.has_landed_cells_at_offset(0, drops + 1) {
+			drops += 1;
+		}
+		self.tetromino_cells()
+			.into_iter()
+			.map(|(x, y)| (x, y + drops as usize))
+			.collect()
 	}
 
 	/// Places the tetromino on the playfield.
@@ -204,7 +994,10 @@ impl App {
 			.for_each(|(y, row)| {
 				row.iter().enumerate().for_each(|(x, &cell)| {
 					if cell {
-						self.playfield[self.y + y][self.x + x].landed = true;
+						let playfield_cell = &mut self.playfield[self.y + y][self.x + x];
+						playfield_cell.landed = true;
+						playfield_cell.locked_at = self.elapsed_ticks;
+						playfield_cell.kind = self.current_tetromino.kind();
 					}
 				});
 			});
@@ -233,7 +1026,7 @@ impl App {
 
 	/// Instanly moves the tetromino as far down as possible.
 	pub fn drop_tetromino(&mut self) {
-		let mut min_drops = 20;
+		let mut min_drops = self.playfield.len() as i32;
 		for y in 0..4 {
 			for x in 0..4 {
 				if self.current_tetromino.rotations[self.current_rotation][y][x] {
@@ -252,6 +1045,9 @@ impl App {
 				}
 			}
 		}
+		if min_drops > 1 {
+			self.last_move_rotation = false;
+		}
 		self.move_tetromino(0, min_drops - 1, self.current_tetromino);
 		self.reset_tetromino();
 	}
@@ -267,9 +1063,10 @@ impl App {
 			.iter()
 			.any(|row| row.iter().any(|&cell| cell))
 		{
-			self.current_tetromino = self.spawn_tetromino(self.x, self.y, self.tetromino_queue[0]);
-			self.tetromino_queue.remove(0);
+			self.spawn_next_tetromino();
 		}
+		self.finesse.inputs.clear();
+		self.pick_finesse_target();
 	}
 	//------------------------------------------//
 
@@ -283,7 +1080,8 @@ impl App {
 					let check_x = (self.x + x) as i32 + x_offset;
 
 					let out_of_bounds = check_y >= self.playfield.len() as i32
-						|| check_y < 0 || !(4..=13).contains(&check_x);
+						|| check_y < 0 || check_x < 0
+						|| !self.board_columns().contains(&(check_x as usize));
 
 					if out_of_bounds || self.playfield[check_y as usize][check_x as usize].landed {
 						return true;
@@ -294,67 +1092,59 @@ impl App {
 		false
 	}
 
-	pub fn check_for_line_clear(&mut self) -> u32 {
-		let mut lines_cleared = 0;
-		let mut lines_to_be_cleared: Vec<usize> = vec![];
-
-		for y in 0..self.playfield.len() {
-			let mut remove_row = true;
-			for x in 4..14 {
-				if !self.playfield[y][x].landed {
-					remove_row = false;
-					break;
-				}
-			}
-			if remove_row {
-				lines_to_be_cleared.push(y);
-				lines_cleared += 1;
-			}
+	/// Is the tetromino a T that was rotated into a slot with at least three filled corners?
+	pub fn is_t_spin(&self) -> bool {
+		if !self.last_move_rotation || self.current_tetromino.kind() != Some(TetrominoKind::T) {
+			return false;
 		}
-		for row in lines_to_be_cleared {
+		// Every rotation of the T tetromino is centered on the second row and column.
+		[(0, 0), (0, 2), (2, 0), (2, 2)]
+			.iter()
+			.filter(|&&(y, x)| {
+				let check_y = self.y + y;
+				let check_x = self.x + x;
+				check_y >= self.playfield.len()
+					|| !self.board_columns().contains(&check_x)
+					|| self.playfield[check_y][check_x].landed
+			})
+			.count() >= 3
+	}
+
+	/// Is every cell of the board empty?
+	pub fn is_board_empty(&self) -> bool {
+		self.playfield
+			.iter()
+			.all(|row| row[self.board_columns()].iter().all(|cell| !cell.landed))
+	}
+
+	/// Returns the indices of the rows that are completely filled.
+	pub fn full_rows(&self) -> Vec<usize> {
+		(0..self.playfield.len())
+			.filter(|&y| self.board_columns().all(|x| self.playfield[y][x].landed))
+			.collect()
+	}
+
+	pub fn check_for_line_clear(&mut self) -> u32 {
+		let lines_to_be_cleared = self.full_rows();
+		for &row in &lines_to_be_cleared {
 			self.playfield.remove(row);
-			self.playfield.insert(0, vec![PlayFieldCell::default(); 18]);
+			self.playfield.insert(0, self.empty_row());
 		}
 
-		lines_cleared
+		lines_to_be_cleared.len() as u32
 	}
 
 	pub fn check_for_game_over(&self) -> bool {
-		if self.playfield[self.start_y + 1_usize][self.start_x + 1_usize].landed {
-			// path of home directory
-			let mut path = dirs::home_dir().unwrap();
-			path.push(".tetrs_highscore");
-
-			let file = match std::fs::read_to_string(&path) {
-				Ok(contents) => contents,
-				Err(_) => {
-					std::fs::write(&path, "0").expect("Failed to create file");
-					String::from("0")
-				}
-			};
-			let highscore = file.parse::<u32>().unwrap_or(0);
-
-			if self.score > highscore {
-				std::fs::write(&path, self.score.to_string()).expect("Failed to write file");
-			}
-			return true;
-		}
-		false
+		self.playfield[self.start_y + 1_usize][self.start_x + 1_usize].landed
 	}
+
+	/// Shows the best score on the board of the game as the high score.
 	pub fn check_for_highscore(&mut self) {
-		// path of home directory
-		let mut path = dirs::home_dir().unwrap();
-		path.push(".tetrs_highscore");
-
-		let file = match std::fs::read_to_string(&path) {
-			Ok(contents) => contents,
-			Err(_) => {
-				std::fs::write(&path, "0").expect("Failed to create file");
-				String::from("0")
-			}
-		};
-		let high_score = file.parse::<u32>().unwrap_or(0);
-		self.high_score = high_score;
+		self.high_score = self
+			.leaderboard
+			.board(self.mode, &self.ruleset())
+			.first()
+			.map_or(0, |entry| entry.score);
 	}
 
 	pub fn check_for_next_level(&mut self) {
@@ -365,6 +1155,60 @@ impl App {
 	}
 	//-----------------------------------------//
 
+	//----------[ Board ]----------//
+	/// Sets up the board, queue and hold of a puzzle and spawns its first tetromino.
+	pub fn load_puzzle(&mut self, puzzle: Puzzle) {
+		self.apply_setup(&puzzle);
+		self.puzzle = Some(PuzzleState {
+			puzzle,
+			pieces: 0,
+			lines: 0,
+			status: PuzzleStatus::Playing,
+		});
+		self.spawn_next_tetromino();
+	}
+
+	/// Replaces the board, queue and hold with the ones of a puzzle. Without a queue the tetrominos
+	/// are dealt anew, and without a hold the hold is emptied.
+	pub fn apply_setup(&mut self, puzzle: &Puzzle) {
+		self.playfield = vec![self.empty_row(); self.board_height + PLAYFIELD_PADDING];
+		let top = self.playfield.len() - puzzle.board.len().min(self.board_height);
+		for (row, cells) in self.playfield[top..]
+			.iter_mut()
+			.zip(puzzle.board.iter().skip(puzzle.board.len().saturating_sub(self.board_height)))
+		{
+			for (cell, &filled) in row[PLAYFIELD_PADDING..].iter_mut().zip(cells) {
+				*cell = filled;
+			}
+		}
+
+		self.tetromino_queue = puzzle.queue.iter().map(|kind| kind.shape()).collect();
+		if self.tetromino_queue.is_empty() {
+			self.populate_tetromino_queue();
+		}
+		self.swap_tetromino = puzzle.hold.map_or(Tetromino::EMPTY, |hold| hold.shape());
+	}
+
+	/// Returns the playfield columns that belong to the board.
+	pub fn board_columns(&self) -> Range<usize> {
+		PLAYFIELD_PADDING..PLAYFIELD_PADDING + self.board_width
+	}
+
+	/// Returns an empty playfield row.
+	pub fn empty_row(&self) -> Vec<PlayFieldCell> {
+		vec![PlayFieldCell::default(); self.board_width + 2 * PLAYFIELD_PADDING]
+	}
+
+	/// Returns how many terminal cells each board cell is drawn as, in both directions.
+	pub fn cell_scale(&self) -> usize {
+		if self.mode == GameMode::Big {
+			2
+		} else {
+			1
+		}
+	}
+	//-----------------------------//
+
 	//----------[ Rendering ]----------//
 	/// Clears the falling cells from the playfield.
 	pub fn clear_falling(&mut self) {
@@ -377,26 +1221,85 @@ impl App {
 		});
 	}
 
+	/// Returns the playfield as colored lines, scaling every cell up in the Big mode.
+	pub fn playfield_lines(&self) -> Vec<Line<'static>> {
+		let scale = self.cell_scale();
+		let falling_color = self
+			.current_tetromino
+			.kind()
+			.map_or(Color::White, |kind| kind.color());
+		let target = self
+			.opener_target()
+			.or_else(|| self.finesse_target())
+			.unwrap_or_default();
+		let ghost = if self.config.layout.ghost && self.can_control() {
+			self.ghost_cells()
+		} else {
+			vec![]
+		};
+		let mut lines = vec![];
+		for (y, row) in self.playfield.iter().enumerate().skip(PLAYFIELD_PADDING) {
+			let spans: Vec<Span<'static>> = row[self.board_columns()]
+				.iter()
+				.zip(self.board_columns())
+				.map(|(cell, x)| {
+					let (content, color) = if cell.landed {
+						(self.landed_cell_string(cell), cell.color())
+					} else if cell.falling {
+						("▒▒", falling_color)
+					} else if target.contains(&(x, y)) {
+						("[]", falling_color)
+					} else if ghost.contains(&(x, y)) {
+						("░░", falling_color)
+					} else {
+						("  ", Color::White)
+					};
+					Span::styled(content.repeat(scale), Style::default().fg(color))
+				})
+				.collect();
+			for _ in 0..scale {
+				lines.push(Line::from(spans.clone()));
+			}
+		}
+		lines
+	}
+
 	/// Returns the playfield as a string.
 	pub fn playfield_string(&self) -> String {
 		let mut result = String::new();
-		for row_index in 4..self.playfield.len() {
-			let row = &self.playfield[row_index];
-			for col_index in row.iter().skip(4) {
-				let cell = col_index;
-				result.push_str(if cell.landed {
-					"██"
-				} else if cell.falling {
-					"▒▒"
-				} else {
-					"  "
-				});
+		for line in self.playfield_lines() {
+			for span in line.spans {
+				result.push_str(&span.content);
 			}
 			result.push('\n');
 		}
 		result
 	}
 
+	/// Returns how a landed cell is drawn, hiding the stack in the Invisible and Fading modes
+	/// until the game is over.
+	pub fn landed_cell_string(&self, cell: &PlayFieldCell) -> &'static str {
+		let fade_ticks = match self.mode {
+			GameMode::Invisible => 0,
+			GameMode::Fading => self.fade_ticks,
+			_ => return "██",
+		};
+		if self.game_over {
+			return "██";
+		}
+
+		let age = self.elapsed_ticks.saturating_sub(cell.locked_at);
+		if age >= fade_ticks {
+			"  "
+		} else if age < fade_ticks / 3 {
+			"██"
+		} else if age < fade_ticks * 2 / 3 {
+			"▓▓"
+		} else {
+			"░░"
+		}
+	}
+
 	/// Returns a tetromino as a string.
 	pub fn tetromino_string(&self, tetromino: Tetromino) -> String {
 		let mut result = String::new();
@@ -416,7 +1319,11 @@ impl App {
 	/// Returns the tetromino queue as a string.
 	pub fn tetromino_queue_string(&self) -> String {
 		let mut result = String::new();
-		for tetromino in &self.tetromino_queue {
+		for tetromino in self
+			.tetromino_queue
+			.iter()
+			.take(self.config.layout.preview as usize)
+		{
 			result += &self.tetromino_string(*tetromino);
 			result.push('\n');
 		}
@@ -424,3 +1331,14 @@ impl App {
 	}
 	//---------------------------------//
 }
+
+/// Formats a number of ticks as `mm:ss.cc`.
+pub fn format_ticks(ticks: u32) -> String {
+	let centiseconds = ticks as u64 * TICK_RATE / 10;
+	format!(
+		"{:02}:{:02}.{:02}",
+		centiseconds / 6000,
+		centiseconds / 100 % 60,
+		centiseconds % 100
+	)
+}
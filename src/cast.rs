@@ -0,0 +1,208 @@
+//! Replays are exported as [asciicast v2](https://docs.asciinema.org/manual/asciicast/v2/) files,
+//! which asciinema plays in a terminal or a browser. The game is drawn on a terminal of a fixed
+//! size kept in memory. The first frame draws the whole screen, and every tick that changes it
+//! after that is written as a frame that only draws the cells that changed.
+
+use std::path::{
+	Path,
+	PathBuf,
+};
+
+use chrono::Local;
+use ratatui::{
+	backend::TestBackend,
+	buffer::Buffer,
+	style::{
+		Color,
+		Modifier,
+	},
+	Terminal,
+};
+
+use crate::{
+	app::TICK_RATE,
+	config::Config,
+	replay::{
+		self,
+		Replay,
+	},
+	storage,
+	ui,
+};
+
+/// Columns of the exported terminal.
+pub const CAST_WIDTH: u16 = 64;
+/// Rows of the exported terminal.
+pub const CAST_HEIGHT: u16 = 46;
+
+//----------[ Escape sequences ]----------//
+/// Returns the parameters of the escape sequence that sets a color, if it is not the default one.
+fn color_parameters(color: Color, foreground: bool) -> Option<String> {
+	let base = if foreground { 30 } else { 40 };
+	let code = |offset: u8| Some((base + offset).to_string());
+	let bright = |offset: u8| Some((base + 60 + offset).to_string());
+	match color {
+		Color::Reset => None,
+		Color::Black => code(0),
+		Color::Red => code(1),
+		Color::Green => code(2),
+		Color::Yellow => code(3),
+		Color::Blue => code(4),
+		Color::Magenta => code(5),
+		Color::Cyan => code(6),
+		Color::Gray => code(7),
+		Color::DarkGray => bright(0),
+		Color::LightRed => bright(1),
+		Color::LightGreen => bright(2),
+		Color::LightYellow => bright(3),
+		Color::LightBlue => bright(4),
+		Color::LightMagenta => bright(5),
+		Color::LightCyan => bright(6),
+		Color::White => bright(7),
+		Color::Rgb(red, green, blue) => Some(format!("{};2;{};{};{}", base + 8, red, green, blue)),
+		Color::Indexed(index) => Some(format!("{};5;{}", base + 8, index)),
+	}
+}
+
+/// Returns the escape sequence that sets the colors and modifiers of a cell.
+fn style_sequence(foreground: Color, background: Color, modifier: Modifier) -> String {
+	let mut parameters = vec![String::from("0")];
+	for (flag, code) in [
+		(Modifier::BOLD, "1"),
+		(Modifier::DIM, "2"),
+		(Modifier::ITALIC, "3"),
+		(Modifier::UNDERLINED, "4"),
+		(Modifier::REVERSED, "7"),
+	] {
+		if modifier.contains(flag) {
+			parameters.push(code.to_string());
+		}
+	}
+	parameters.extend(color_parameters(foreground, true));
+	parameters.extend(color_parameters(background, false));
+	format!("\x1b[{}m", parameters.join(";"))
+}
+
+/// Returns the text that draws a whole buffer from the top left corner of the terminal.
+pub fn buffer_string(buffer: &Buffer) -> String {
+	let mut result = String::from("\x1b[H");
+	for y in 0..buffer.area.height {
+		let mut style = None;
+		for x in 0..buffer.area.width {
+			let cell = buffer.get(x, y);
+			let cell_style = (cell.fg, cell.bg, cell.modifier);
+			if style != Some(cell_style) {
+				result += &style_sequence(cell.fg, cell.bg, cell.modifier);
+				style = Some(cell_style);
+			}
+			result += cell.symbol();
+		}
+		result += "\x1b[0m";
+		if y + 1 < buffer.area.height {
+			result += "\r\n";
+		}
+	}
+	result
+}
+
+/// Returns the text that draws the cells of a buffer that differ from the previous one, moving the
+/// cursor to every run of changed cells.
+pub fn diff_string(previous: &Buffer, buffer: &Buffer) -> String {
+	let mut result = String::new();
+	let (mut cursor, mut style) = (None, None);
+	for (x, y, cell) in previous.diff(buffer) {
+		if cursor != Some((x, y)) {
+			result += &format!("\x1b[{};{}H", y + 1, x + 1);
+		}
+		let cell_style = (cell.fg, cell.bg, cell.modifier);
+		if style != Some(cell_style) {
+			result += &style_sequence(cell.fg, cell.bg, cell.modifier);
+			style = Some(cell_style);
+		}
+		result += cell.symbol();
+		// Cells covered by a wide symbol are never changed on their own, so the cursor is moved
+		// explicitly to whatever follows it.
+		cursor = Some((x + 1, y));
+	}
+	if !result.is_empty() {
+		result += "\x1b[0m";
+	}
+	result
+}
+//----------------------------------------//
+
+/// Writes a string as a JSON string.
+fn json_string(value: &str) -> String {
+	let mut result = String::from("\"");
+	for character in value.chars() {
+		match character {
+			'"' => result += "\\\"",
+			'\\' => result += "\\\\",
+			'\n' => result += "\\n",
+			'\r' => result += "\\r",
+			'\t' => result += "\\t",
+			character if (character as u32) < 0x20 => {
+				result += &format!("\\u{:04x}", character as u32);
+			}
+			character => result.push(character),
+		}
+	}
+	result + "\""
+}
+
+/// Plays a replay back and returns it as an asciicast, drawn with the theme of the config.
+pub fn cast(replay: &Replay, config: &Config) -> Result<String, String> {
+	let mut game = replay.game_with_config(config);
+	// There is no pointer to press the buttons with.
+	game.config.layout.buttons = false;
+	let mut terminal = Terminal::new(TestBackend::new(CAST_WIDTH, CAST_HEIGHT))
+		.map_err(|error| error.to_string())?;
+
+	let mut header = format!(
+		"{{\"version\": 2, \"width\": {}, \"height\": {}",
+		CAST_WIDTH, CAST_HEIGHT
+	);
+	if let Some(date) = replay.date.and_local_timezone(Local).single() {
+		header += &format!(", \"timestamp\": {}", date.timestamp());
+	}
+	header += &format!(
+		", \"title\": {}, \"env\": {{\"TERM\": \"xterm-256color\"}}}}\n",
+		json_string(&format!("tetrs {} {}", replay.mode.name(), replay.date))
+	);
+
+	let mut result = header;
+	let mut last_buffer: Option<Buffer> = None;
+	let mut next_input = 0;
+	loop {
+		let buffer = terminal
+			.draw(|frame| ui::render(&mut game, frame))
+			.map(|frame| frame.buffer.clone())
+			.map_err(|error| error.to_string())?;
+		let output = match &last_buffer {
+			Some(last_buffer) => diff_string(last_buffer, &buffer),
+			// Clear the screen and hide the cursor before the first frame.
+			None => format!("\x1b[2J\x1b[?25l{}", buffer_string(&buffer)),
+		};
+		if !output.is_empty() {
+			let seconds = game.replay_ticks as f64 * TICK_RATE as f64 / 1000.0;
+			result += &format!("[{:.3}, \"o\", {}]\n", seconds, json_string(&output));
+		}
+		last_buffer = Some(buffer);
+		if replay.finished(&game, next_input) {
+			break;
+		}
+		next_input = replay.step(&mut game, next_input);
+	}
+	Ok(result)
+}
+
+/// Exports a replay file as an asciicast, next to it unless another path is given, returning the
+/// path of the cast.
+pub fn export(path: &Path, output: Option<&Path>, config: &Config) -> Result<PathBuf, String> {
+	let replay = replay::load(path)?;
+	let output = output.map_or_else(|| path.with_extension("cast"), Path::to_path_buf);
+	let contents = cast(&replay, config)?;
+	storage::write_path(&output, &contents)
+		.map_err(|error| format!("{}: {}", output.display(), error))?;
+	Ok(output)
+}
@@ -1,5 +1,19 @@
 use clap::*;
 
+use crate::{
+	config::{
+		Border,
+		Config,
+	},
+	fumen,
+	mode::GameMode,
+	opener::{
+		Opener,
+		OPENERS,
+	},
+	puzzle::Puzzle,
+};
+
 //----------[ Args ]----------//
 pub fn clap_parse() -> ArgMatches {
 	let cmd = clap::Command::new("cargo")
@@ -18,8 +32,8 @@ pub fn clap_parse() -> ArgMatches {
 			Arg::new("color")
 				.long("color")
 				.short('c')
-				.value_parser(0..=15)
-				.default_value("3"),
+				.help("Accent color, an ANSI color from 0 to 15")
+				.value_parser(0..=15),
 		)
 		//-----------------------------//
 		//----------[ Border Corners ]----------//
@@ -28,13 +42,10 @@ pub fn clap_parse() -> ArgMatches {
 				.long("border")
 				.short('b')
 				.value_parser(|input: &str| {
-					if input == "Plain" || input == "Rounded" {
-						Ok(input.to_string())
-					} else {
-						Err("Invalid value for BorderCorners. Allowed values are 'Plain' or 'Rounded'".to_string())
-					}
-				})
-				.default_value("Plain"),
+					Border::from_name(input).ok_or_else(|| {
+						"Invalid value for BorderCorners. Allowed values are 'Plain' or 'Rounded'".to_string()
+					})
+				}),
 		)
 		//--------------------------------------//
 		//----------[ Start Level ]----------//
@@ -42,10 +53,84 @@ pub fn clap_parse() -> ArgMatches {
 			Arg::new("startlevel")
 				.long("level")
 				.short('l')
-				.value_parser(1..=15)
-				.default_value("1"),
+				.value_parser(1..=15),
 		)
 		//----------------------------------//
+		//----------[ Mode ]----------//
+		.arg(
+			Arg::new("mode")
+				.long("mode")
+				.short('m')
+				.value_parser(|input: &str| {
+					GameMode::from_name(input).ok_or_else(|| {
+						format!(
+							"Invalid value for mode. Allowed values are {}",
+							GameMode::ALL
+								.map(|mode| format!("'{}'", mode.name()))
+								.join(", ")
+						)
+					})
+				}),
+		)
+		//----------------------------//
+		//----------[ Puzzle ]----------//
+		.arg(
+			Arg::new("puzzle")
+				.long("puzzle")
+				.help("Puzzle file to play, implies the Puzzle mode")
+				.value_parser(value_parser!(std::path::PathBuf)),
+		)
+		//------------------------------//
+		//----------[ Fumen ]----------//
+		.arg(
+			Arg::new("fumen")
+				.long("fumen")
+				.help("Fumen board to start from, in the Sandbox mode unless the Puzzle mode is asked")
+				.value_parser(|input: &str| {
+					fumen::parse(input).map(|pages| fumen::setup(&pages))
+				}),
+		)
+		//-----------------------------//
+		//----------[ Opener ]----------//
+		.arg(
+			Arg::new("opener")
+				.long("opener")
+				.help("Opener to train, implies the Opener mode")
+				.value_parser(|input: &str| {
+					Opener::from_name(input).ok_or_else(|| {
+						format!(
+							"Invalid value for opener. Allowed values are {}",
+							OPENERS
+								.map(|opener| format!("'{}'", opener.name))
+								.join(", ")
+						)
+					})
+				}),
+		)
+		.arg(
+			Arg::new("auto-undo")
+				.long("auto-undo")
+				.help("Undo wrong placements in the Opener mode right away")
+				.action(ArgAction::SetTrue),
+		)
+		//------------------------------//
+		//----------[ Practice ]----------//
+		.arg(
+			Arg::new("practice")
+				.long("practice")
+				.help("Allow undoing placements with Ctrl+Z, nothing is recorded")
+				.action(ArgAction::SetTrue),
+		)
+		//--------------------------------//
+		//----------[ Fade ]----------//
+		.arg(
+			Arg::new("fade")
+				.long("fade")
+				.short('f')
+				.help("Seconds a landed block stays visible in the Fading mode")
+				.value_parser(1..=30),
+		)
+		//----------------------------//
 		//----------[ Buttons ]----------//
 		.arg(
 			Arg::new("ControlButtons")
@@ -58,11 +143,152 @@ pub fn clap_parse() -> ArgMatches {
 					_ => Err(
 						"Invalid value for bool. Allowed values are 'true' or 'false'".to_string(),
 					),
-				})
-				.default_value("false"),
+				}),
+		)
+		//-------------------------------//
+		//----------[ Daily ]----------//
+		.subcommand(
+			clap::Command::new("daily").about("Shows the results of past daily challenges"),
+		)
+		//-----------------------------//
+		//----------[ Puzzles ]----------//
+		.subcommand(clap::Command::new("puzzles").about("Lists the puzzle pack and your progress"))
+		//-------------------------------//
+		//----------[ Stats ]----------//
+		.subcommand(
+			clap::Command::new("stats")
+				.about("Summarizes your finished games with bests, averages and trends per mode"),
+		)
+		//-----------------------------//
+		//----------[ Leaderboard ]----------//
+		.subcommand(clap::Command::new("leaderboard").about("Shows the best games of every mode"))
+		//-----------------------------------//
+		//----------[ Config ]----------//
+		.subcommand(
+			clap::Command::new("config")
+				.about("Shows where the config file is read from")
+				.arg(
+					Arg::new("print-default")
+						.long("print-default")
+						.help("Prints a config file with every default value")
+						.action(ArgAction::SetTrue),
+				),
+		)
+		//------------------------------//
+		//----------[ Replay ]----------//
+		.subcommand(
+			clap::Command::new("replay").about("Plays a saved replay back").arg(
+				Arg::new("file")
+					.help("Replay file to watch")
+					.required(true)
+					.value_parser(value_parser!(std::path::PathBuf)),
+			),
+		)
+		//------------------------------//
+		//----------[ Verify ]----------//
+		.subcommand(
+			clap::Command::new("verify")
+				.about("Plays a replay back without a terminal and checks its claimed result")
+				.arg(
+					Arg::new("file")
+						.help("Replay file to verify")
+						.required(true)
+						.value_parser(value_parser!(std::path::PathBuf)),
+				),
+		)
+		//------------------------------//
+		//----------[ Export ]----------//
+		.subcommand(
+			clap::Command::new("export")
+				.about("Exports a replay to share it")
+				.arg(
+					Arg::new("file")
+						.help("Replay file to export")
+						.required(true)
+						.value_parser(value_parser!(std::path::PathBuf)),
+				)
+				.arg(
+					Arg::new("cast")
+						.long("cast")
+						.help("Exports an asciinema cast of the game")
+						.action(ArgAction::SetTrue),
+				)
+				.arg(
+					Arg::new("png")
+						.long("png")
+						.help("Exports a PNG picture of the board at the end of the game")
+						.action(ArgAction::SetTrue),
+				)
+				.arg(
+					Arg::new("gif")
+						.long("gif")
+						.help("Exports an animated GIF of the board")
+						.action(ArgAction::SetTrue),
+				)
+				.arg(
+					Arg::new("fumen")
+						.long("fumen")
+						.help("Prints a fumen with a page for every placement")
+						.conflicts_with("output")
+						.action(ArgAction::SetTrue),
+				)
+				.arg(
+					Arg::new("piece")
+						.long("piece")
+						.help("With --png, takes the picture once this many pieces are locked")
+						.value_parser(value_parser!(u32)),
+				)
+				.arg(
+					Arg::new("output")
+						.long("output")
+						.short('o')
+						.help("File to write, next to the replay by default")
+						.value_parser(value_parser!(std::path::PathBuf)),
+				)
+				.group(ArgGroup::new("format").args(["cast", "png", "gif", "fumen"]).required(true)),
 		);
-	//-------------------------------//
+	//------------------------------//
 
 	cmd.get_matches()
 }
 //----------------------------//
+
+/// Overrides the values of the config file with the flags given on the command line.
+pub fn apply_args(matches: &ArgMatches, config: &mut Config) {
+	if let Some(&color) = matches.get_one::<i64>("color") {
+		config.theme.accent = color as u8;
+	}
+	if let Some(&border) = matches.get_one::<Border>("BorderCorners") {
+		config.theme.border = border;
+	}
+	if let Some(&level) = matches.get_one::<i64>("startlevel") {
+		config.game.level = level as u32;
+	}
+	if let Some(&mode) = matches.get_one::<GameMode>("mode") {
+		config.game.mode = mode;
+	}
+	if let Some(opener) = matches.get_one::<&'static Opener>("opener") {
+		config.game.mode = GameMode::Opener;
+		config.game.opener = opener.name.to_string();
+	}
+	if matches.get_one::<std::path::PathBuf>("puzzle").is_some() {
+		config.game.mode = GameMode::Puzzle;
+	}
+	if matches.get_one::<Puzzle>("fumen").is_some()
+		&& matches.get_one::<GameMode>("mode") != Some(&GameMode::Puzzle)
+	{
+		config.game.mode = GameMode::Sandbox;
+	}
+	if matches.get_flag("auto-undo") {
+		config.game.auto_undo = true;
+	}
+	if matches.get_flag("practice") {
+		config.game.practice = true;
+	}
+	if let Some(&fade) = matches.get_one::<i64>("fade") {
+		config.game.fade = fade as u32;
+	}
+	if let Some(&buttons) = matches.get_one::<bool>("ControlButtons") {
+		config.layout.buttons = buttons;
+	}
+}
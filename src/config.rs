@@ -0,0 +1,614 @@
+//! Options are read from `config.toml` in the config directory, `$XDG_CONFIG_HOME/tetrs` on
+//! Linux. Every value can be left out and falls back to its default, and flags on the command line
+//! override the file. `tetrs config --print-default` prints a file with every default value.
+
+use std::{
+	fmt,
+	io,
+	ops::RangeInclusive,
+	path::PathBuf,
+	str::FromStr,
+};
+
+use crossterm::event::KeyCode;
+use ratatui::{
+	style::Color,
+	widgets::BorderType,
+};
+use serde::{
+	de,
+	Deserialize,
+	Deserializer,
+	Serialize,
+	Serializer,
+};
+
+use crate::{
+	app::TICK_RATE,
+	mode::GameMode,
+	opener::{
+		Opener,
+		OPENERS,
+	},
+	storage,
+};
+
+/// Name of the directory in the config directory of the platform.
+const CONFIG_DIRECTORY: &str = "tetrs";
+
+/// Name of the config file in the config directory.
+const CONFIG_FILE: &str = "config.toml";
+
+/// Allowed start levels.
+pub const LEVEL_RANGE: RangeInclusive<u32> = 1..=15;
+/// Allowed seconds a landed block stays visible in the Fading mode.
+pub const FADE_RANGE: RangeInclusive<u32> = 1..=30;
+/// Allowed milliseconds of DAS.
+pub const DAS_RANGE: RangeInclusive<u32> = 0..=1000;
+/// Allowed milliseconds of ARR.
+pub const ARR_RANGE: RangeInclusive<u32> = 0..=500;
+/// Allowed soft drop factors.
+pub const SDF_RANGE: RangeInclusive<u32> = 0..=40;
+/// Allowed ANSI accent colors.
+pub const ACCENT_RANGE: RangeInclusive<u32> = 0..=15;
+/// Allowed numbers of tetrominos shown in the queue.
+pub const PREVIEW_RANGE: RangeInclusive<u32> = 0..=7;
+
+/// The config file printed by `tetrs config --print-default`, holding every default value.
+pub const DEFAULT_CONFIG: &str = r#"# Options of tetrs. Every value can be left out and command line flags override them.
+
+[game]
+# Marathon, Master, Invisible, Fading, Big, Daily, Puzzle, Sandbox, Opener, PerfectClear or Finesse
+mode = "Marathon"
+# Level a game starts at, 1 to 15
+level = 1
+# Seconds a landed block stays visible in the Fading mode, 1 to 30
+fade = 5
+# Allow undoing placements with Ctrl+Z, nothing is recorded
+practice = false
+# Opener trained in the Opener mode: TKI, PCO or DT
+opener = "TKI"
+# Undo wrong placements in the Opener mode right away
+auto_undo = false
+
+[controls]
+# A key is a character, Space, Enter, Tab, Backspace, Esc, Left, Right, Up, Down, Home, End,
+# PageUp, PageDown, Delete, Insert or F1 to F12. Every action takes a list of keys.
+left = ["a"]
+right = ["d"]
+das_left = ["A"]
+das_right = ["D"]
+rotate_cw = ["e"]
+rotate_ccw = ["q"]
+soft_drop = ["s"]
+hard_drop = ["Space"]
+hold = ["w"]
+pause = ["p"]
+stats = ["i"]
+leaderboard = ["l"]
+quit = ["Esc"]
+
+[handling]
+# Milliseconds a shift key is held before the tetromino shifts on its own, 0 to 1000. Needs a
+# terminal that reports key releases.
+das = 167
+# Milliseconds between two automatic shifts, 0 to 500, where 0 shifts straight to the wall
+arr = 33
+# Times faster than gravity a soft drop falls, 0 to 40, where 0 drops to the bottom at once
+sdf = 20
+
+[theme]
+# Accent color of borders and text, an ANSI color from 0 to 15
+accent = 3
+# Corners of the borders: Plain or Rounded
+border = "Plain"
+
+[layout]
+# Show buttons to play with the mouse
+buttons = false
+# Show where the tetromino will land
+ghost = true
+# Tetrominos shown in the queue, 0 to 7
+preview = 5
+"#;
+
+//----------[ Structs ]----------//
+/// Every option of the game.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+	pub game: GameConfig,
+	pub controls: Controls,
+	pub handling: Handling,
+	pub theme: Theme,
+	pub layout: Layout,
+}
+
+/// Mode and ruleset a game starts with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+	pub mode: GameMode,
+	/// Level a game starts at.
+	pub level: u32,
+	/// Seconds a landed block stays visible in the Fading mode.
+	pub fade: u32,
+	/// Are placements undoable, with nothing recorded?
+	pub practice: bool,
+	/// Name of the opener trained in the Opener mode.
+	pub opener: String,
+	/// Are wrong placements in the Opener mode undone right away?
+	pub auto_undo: bool,
+}
+
+/// Keys bound to every action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Controls {
+	pub left: Vec<Key>,
+	pub right: Vec<Key>,
+	pub das_left: Vec<Key>,
+	pub das_right: Vec<Key>,
+	pub rotate_cw: Vec<Key>,
+	pub rotate_ccw: Vec<Key>,
+	pub soft_drop: Vec<Key>,
+	pub hard_drop: Vec<Key>,
+	pub hold: Vec<Key>,
+	pub pause: Vec<Key>,
+	pub stats: Vec<Key>,
+	pub leaderboard: Vec<Key>,
+	pub quit: Vec<Key>,
+}
+
+/// How the tetromino reacts to held keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Handling {
+	/// Delayed auto shift, the milliseconds a shift key is held before it repeats.
+	pub das: u32,
+	/// Auto repeat rate, the milliseconds between two repeated shifts, 0 for instant.
+	pub arr: u32,
+	/// Soft drop factor, how many times faster than gravity a soft drop falls, 0 for instant.
+	pub sdf: u32,
+}
+
+/// Colors and borders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+	/// ANSI color of borders and text.
+	pub accent: u8,
+	pub border: Border,
+}
+
+/// What is shown around the playfield.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Layout {
+	/// Are buttons to play with the mouse shown?
+	pub buttons: bool,
+	/// Is the cell where the tetromino will land outlined?
+	pub ghost: bool,
+	/// Tetrominos shown in the queue.
+	pub preview: u32,
+}
+
+/// Corners of the borders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Border {
+	#[default]
+	Plain,
+	Rounded,
+}
+
+/// A key on the keyboard, written as a character or the name of the key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key(pub KeyCode);
+
+/// Something a key can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+	Left,
+	Right,
+	DasLeft,
+	DasRight,
+	RotateCw,
+	RotateCcw,
+	SoftDrop,
+	HardDrop,
+	Hold,
+	Pause,
+	Stats,
+	Leaderboard,
+	Quit,
+}
+//-------------------------------//
+
+//----------[ Defaults ]----------//
+impl Default for GameConfig {
+	fn default() -> Self {
+		Self {
+			mode: GameMode::default(),
+			level: 1,
+			fade: 5,
+			practice: false,
+			opener: OPENERS[0].name.to_string(),
+			auto_undo: false,
+		}
+	}
+}
+
+impl Default for Controls {
+	fn default() -> Self {
+		let keys = |codes: &[KeyCode]| codes.iter().map(|&code| Key(code)).collect();
+		Self {
+			left: keys(&[KeyCode::Char('a')]),
+			right: keys(&[KeyCode::Char('d')]),
+			das_left: keys(&[KeyCode::Char('A')]),
+			das_right: keys(&[KeyCode::Char('D')]),
+			rotate_cw: keys(&[KeyCode::Char('e')]),
+			rotate_ccw: keys(&[KeyCode::Char('q')]),
+			soft_drop: keys(&[KeyCode::Char('s')]),
+			hard_drop: keys(&[KeyCode::Char(' ')]),
+			hold: keys(&[KeyCode::Char('w')]),
+			pause: keys(&[KeyCode::Char('p')]),
+			stats: keys(&[KeyCode::Char('i')]),
+			leaderboard: keys(&[KeyCode::Char('l')]),
+			quit: keys(&[KeyCode::Esc]),
+		}
+	}
+}
+
+impl Default for Handling {
+	fn default() -> Self {
+		Self {
+			das: 167,
+			arr: 33,
+			sdf: 20,
+		}
+	}
+}
+
+impl Default for Theme {
+	fn default() -> Self {
+		Self {
+			accent: 3,
+			border: Border::default(),
+		}
+	}
+}
+
+impl Default for Layout {
+	fn default() -> Self {
+		Self {
+			buttons: false,
+			ghost: true,
+			preview: 5,
+		}
+	}
+}
+//--------------------------------//
+
+//----------[ Config ]----------//
+/// Returns the path of the config file. Tests keep theirs in their data directory.
+pub fn config_path() -> Option<PathBuf> {
+	match cfg!(test) {
+		true => storage::data_directory().map(|path| path.join(CONFIG_FILE)),
+		false => dirs::config_dir().map(|path| path.join(CONFIG_DIRECTORY).join(CONFIG_FILE)),
+	}
+}
+
+impl Config {
+	/// Loads the config file, which holds the defaults if it does not exist yet.
+	pub fn load() -> Result<Self, String> {
+		let Some(path) = config_path() else {
+			return Ok(Self::default());
+		};
+		let in_file = |error: String| format!("{}: {}", path.display(), error);
+		match storage::read_path(&path).map_err(|error| in_file(error.to_string()))? {
+			Some(contents) => Self::parse(&contents).map_err(in_file),
+			None => Ok(Self::default()),
+		}
+	}
+
+	/// Parses and checks a config file.
+	pub fn parse(contents: &str) -> Result<Self, String> {
+		let config: Self = toml::from_str(contents).map_err(|error| error.to_string())?;
+		config.validate()?;
+		Ok(config)
+	}
+
+	/// Checks the values that are out of range of their type.
+	pub fn validate(&self) -> Result<(), String> {
+		let check = |name: &str, value: u32, range: RangeInclusive<u32>| {
+			if range.contains(&value) {
+				Ok(())
+			} else {
+				Err(format!(
+					"Invalid value {} for {}. Allowed values are {} to {}",
+					value,
+					name,
+					range.start(),
+					range.end()
+				))
+			}
+		};
+		check("game.level", self.game.level, LEVEL_RANGE)?;
+		check("game.fade", self.game.fade, FADE_RANGE)?;
+		check("handling.das", self.handling.das, DAS_RANGE)?;
+		check("handling.arr", self.handling.arr, ARR_RANGE)?;
+		check("handling.sdf", self.handling.sdf, SDF_RANGE)?;
+		check("theme.accent", self.theme.accent as u32, ACCENT_RANGE)?;
+		check("layout.preview", self.layout.preview, PREVIEW_RANGE)?;
+		if Opener::from_name(&self.game.opener).is_none() {
+			return Err(format!(
+				"Invalid value '{}' for game.opener. Allowed values are {}",
+				self.game.opener,
+				OPENERS
+					.map(|opener| format!("'{}'", opener.name))
+					.join(", ")
+			));
+		}
+		Ok(())
+	}
+
+	/// Replaces the config file.
+	pub fn save(&self) -> io::Result<()> {
+		let path = config_path().ok_or_else(|| io::Error::other("No config directory found"))?;
+		let contents = toml::to_string(self).map_err(io::Error::other)?;
+		storage::write_path(&path, &contents)
+	}
+
+	/// Returns the opener trained in the Opener mode.
+	pub fn opener(&self) -> &'static Opener {
+		Opener::from_name(&self.game.opener).unwrap_or(&OPENERS[0])
+	}
+}
+//------------------------------//
+
+//----------[ Controls ]----------//
+impl Controls {
+	/// Returns the keys bound to every action.
+	pub fn bindings(&self) -> [(Action, &Vec<Key>); 13] {
+		[
+			(Action::Left, &self.left),
+			(Action::Right, &self.right),
+			(Action::DasLeft, &self.das_left),
+			(Action::DasRight, &self.das_right),
+			(Action::RotateCw, &self.rotate_cw),
+			(Action::RotateCcw, &self.rotate_ccw),
+			(Action::SoftDrop, &self.soft_drop),
+			(Action::HardDrop, &self.hard_drop),
+			(Action::Hold, &self.hold),
+			(Action::Pause, &self.pause),
+			(Action::Stats, &self.stats),
+			(Action::Leaderboard, &self.leaderboard),
+			(Action::Quit, &self.quit),
+		]
+	}
+
+	/// Returns the keys bound to an action.
+	pub fn keys_mut(&mut self, action: Action) -> &mut Vec<Key> {
+		match action {
+			Action::Left => &mut self.left,
+			Action::Right => &mut self.right,
+			Action::DasLeft => &mut self.das_left,
+			Action::DasRight => &mut self.das_right,
+			Action::RotateCw => &mut self.rotate_cw,
+			Action::RotateCcw => &mut self.rotate_ccw,
+			Action::SoftDrop => &mut self.soft_drop,
+			Action::HardDrop => &mut self.hard_drop,
+			Action::Hold => &mut self.hold,
+			Action::Pause => &mut self.pause,
+			Action::Stats => &mut self.stats,
+			Action::Leaderboard => &mut self.leaderboard,
+			Action::Quit => &mut self.quit,
+		}
+	}
+
+	/// Binds a single key to an action, unbinding it from every other action.
+	pub fn bind(&mut self, action: Action, key: Key) {
+		for other in Action::ALL {
+			self.keys_mut(other).retain(|&bound| bound != key);
+		}
+		*self.keys_mut(action) = vec![key];
+	}
+
+	/// Returns the action a key is bound to, if any.
+	pub fn action(&self, code: KeyCode) -> Option<Action> {
+		self.bindings()
+			.into_iter()
+			.find(|(_, keys)| keys.contains(&Key(code)))
+			.map(|(action, _)| action)
+	}
+}
+
+impl Action {
+	/// Every action, in the order of the config file.
+	pub const ALL: [Action; 13] = [
+		Action::Left,
+		Action::Right,
+		Action::DasLeft,
+		Action::DasRight,
+		Action::RotateCw,
+		Action::RotateCcw,
+		Action::SoftDrop,
+		Action::HardDrop,
+		Action::Hold,
+		Action::Pause,
+		Action::Stats,
+		Action::Leaderboard,
+		Action::Quit,
+	];
+
+	/// Returns the name shown in the settings.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Action::Left => "Left",
+			Action::Right => "Right",
+			Action::DasLeft => "DAS left",
+			Action::DasRight => "DAS right",
+			Action::RotateCw => "Rotate right",
+			Action::RotateCcw => "Rotate left",
+			Action::SoftDrop => "Soft drop",
+			Action::HardDrop => "Hard drop",
+			Action::Hold => "Hold",
+			Action::Pause => "Pause",
+			Action::Stats => "Stats",
+			Action::Leaderboard => "Leaderboard",
+			Action::Quit => "Quit",
+		}
+	}
+}
+
+/// Names of the keys that are not written as a character.
+const KEY_NAMES: [(&str, KeyCode); 15] = [
+	("Space", KeyCode::Char(' ')),
+	("Enter", KeyCode::Enter),
+	("Tab", KeyCode::Tab),
+	("Backspace", KeyCode::Backspace),
+	("Esc", KeyCode::Esc),
+	("Left", KeyCode::Left),
+	("Right", KeyCode::Right),
+	("Up", KeyCode::Up),
+	("Down", KeyCode::Down),
+	("Home", KeyCode::Home),
+	("End", KeyCode::End),
+	("PageUp", KeyCode::PageUp),
+	("PageDown", KeyCode::PageDown),
+	("Delete", KeyCode::Delete),
+	("Insert", KeyCode::Insert),
+];
+
+impl FromStr for Key {
+	type Err = String;
+
+	/// Parses a single character, case sensitive, or the name of a key, ignoring case.
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		let mut characters = name.chars();
+		if let (Some(character), None) = (characters.next(), characters.next()) {
+			return Ok(Key(KeyCode::Char(character)));
+		}
+		if let Some(&(_, code)) = KEY_NAMES
+			.iter()
+			.find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+		{
+			return Ok(Key(code));
+		}
+		match name.strip_prefix(['F', 'f']).and_then(|number| number.parse().ok()) {
+			Some(number @ 1..=12) => Ok(Key(KeyCode::F(number))),
+			_ => Err(format!(
+				"Invalid key '{}'. Allowed keys are a single character, {} or F1 to F12",
+				name,
+				KEY_NAMES.map(|(key_name, _)| key_name).join(", ")
+			)),
+		}
+	}
+}
+
+impl fmt::Display for Key {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match KEY_NAMES.iter().find(|&&(_, code)| code == self.0) {
+			Some((name, _)) => write!(f, "{}", name),
+			None => match self.0 {
+				KeyCode::Char(character) => write!(f, "{}", character),
+				KeyCode::F(number) => write!(f, "F{}", number),
+				code => write!(f, "{:?}", code),
+			},
+		}
+	}
+}
+
+impl Serialize for Key {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(self)
+	}
+}
+
+impl<'de> Deserialize<'de> for Key {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		String::deserialize(deserializer)?
+			.parse()
+			.map_err(de::Error::custom)
+	}
+}
+//--------------------------------//
+
+//----------[ Handling ]----------//
+/// Returns the ticks that pass in a number of milliseconds, rounded up.
+fn ticks(milliseconds: u32) -> u32 {
+	(milliseconds as u64).div_ceil(TICK_RATE) as u32
+}
+
+impl Handling {
+	/// Returns the ticks a shift key is held before it repeats.
+	pub fn das_ticks(&self) -> u32 {
+		ticks(self.das)
+	}
+
+	/// Returns the ticks between two repeated shifts, 0 for instant.
+	pub fn arr_ticks(&self) -> u32 {
+		ticks(self.arr)
+	}
+}
+//--------------------------------//
+
+//----------[ Theme ]----------//
+impl Theme {
+	/// Returns the color of borders and text.
+	pub fn accent_color(&self) -> Color {
+		Color::Indexed(self.accent)
+	}
+}
+
+impl Border {
+	/// Returns the border type the widgets are drawn with.
+	pub fn border_type(&self) -> BorderType {
+		match self {
+			Border::Plain => BorderType::Plain,
+			Border::Rounded => BorderType::Rounded,
+		}
+	}
+
+	/// Looks up corners by their name.
+	pub fn from_name(name: &str) -> Option<Border> {
+		match name {
+			"Plain" => Some(Border::Plain),
+			"Rounded" => Some(Border::Rounded),
+			_ => None,
+		}
+	}
+}
+//-----------------------------//
+
+//----------[ Game Mode ]----------//
+impl Serialize for GameMode {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(self.name())
+	}
+}
+
+impl<'de> Deserialize<'de> for GameMode {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let name = String::deserialize(deserializer)?;
+		GameMode::from_name(&name).ok_or_else(|| {
+			de::Error::custom(format!(
+				"Invalid value '{}' for mode. Allowed values are {}",
+				name,
+				GameMode::ALL
+					.map(|mode| format!("'{}'", mode.name()))
+					.join(", ")
+			))
+		})
+	}
+}
+//---------------------------------//
+
+/// Prints where the config file is read from.
+pub fn print_path() {
+	match config_path() {
+		Some(path) if path.exists() => println!("{}", path.display()),
+		Some(path) => println!("{} (not created yet, using the defaults)", path.display()),
+		None => println!("No config directory found, using the defaults"),
+	}
+}
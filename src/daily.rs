@@ -0,0 +1,123 @@
+use std::io;
+
+use chrono::{
+	Datelike,
+	Local,
+	NaiveDate,
+};
+
+use crate::{
+	app::format_ticks,
+	storage,
+};
+
+//----------[ Structs ]----------//
+/// The daily challenge being played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailyChallenge {
+	/// Local date of the challenge.
+	pub date: NaiveDate,
+	/// Seed of the piece sequence, shared by everyone playing on this date.
+	pub seed: u64,
+	/// Has the scored attempt for this date already been used?
+	pub practice: bool,
+}
+
+/// The recorded result of a daily challenge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailyResult {
+	pub date: NaiveDate,
+	pub score: u32,
+	pub level: u32,
+	/// Length of the game in ticks.
+	pub ticks: u32,
+}
+//-------------------------------//
+
+impl DailyChallenge {
+	/// Returns the challenge for the given date.
+	pub fn for_date(date: NaiveDate, history: &[DailyResult]) -> Self {
+		Self {
+			date,
+			seed: seed_for_date(date),
+			practice: history.iter().any(|result| result.date == date),
+		}
+	}
+
+	/// Returns today's challenge, checking the local history for an earlier attempt.
+	pub fn today() -> Self {
+		Self::for_date(Local::now().date_naive(), &load_history())
+	}
+}
+
+/// Derives the seed of a daily challenge from its date.
+pub fn seed_for_date(date: NaiveDate) -> u64 {
+	date.year() as u64 * 10000 + date.month() as u64 * 100 + date.day() as u64
+}
+
+//----------[ History ]----------//
+/// Name of the daily history file in the data directory.
+const HISTORY_FILE: &str = "daily";
+
+/// Parses a line of the history file, formatted as `date score level ticks`.
+pub fn parse_result(line: &str) -> Option<DailyResult> {
+	let mut fields = line.split_whitespace();
+	Some(DailyResult {
+		date: fields.next()?.parse().ok()?,
+		score: fields.next()?.parse().ok()?,
+		level: fields.next()?.parse().ok()?,
+		ticks: fields.next()?.parse().ok()?,
+	})
+}
+
+/// Loads every recorded daily result, skipping malformed lines.
+pub fn load_history() -> Vec<DailyResult> {
+	storage::read(HISTORY_FILE)
+		.ok()
+		.flatten()
+		.map(|contents| contents.lines().filter_map(parse_result).collect())
+		.unwrap_or_default()
+}
+
+/// Records a result in the history file, replacing an earlier one of the same date.
+pub fn record_result(result: &DailyResult) -> io::Result<()> {
+	let mut history = load_history();
+	history.retain(|other| other.date != result.date);
+	history.push(*result);
+	let contents: String = history
+		.iter()
+		.map(|result| {
+			format!(
+				"{} {} {} {}\n",
+				result.date, result.score, result.level, result.ticks
+			)
+		})
+		.collect();
+	storage::write(HISTORY_FILE, &contents)
+}
+
+/// Prints the daily history, newest first, followed by the best result.
+pub fn print_history() {
+	let mut history = load_history();
+	if history.is_empty() {
+		println!("No daily challenges played yet.");
+		return;
+	}
+	history.sort_by_key(|result| std::cmp::Reverse(result.date));
+
+	println!("{:<12}{:>10}{:>8}{:>12}", "Date", "Score", "Level", "Time");
+	for result in &history {
+		println!(
+			"{:<12}{:>10}{:>8}{:>12}",
+			result.date.to_string(),
+			result.score,
+			result.level,
+			format_ticks(result.ticks)
+		);
+	}
+
+	if let Some(best) = history.iter().max_by_key(|result| result.score) {
+		println!("\nBest: {} on {}", best.score, best.date);
+	}
+}
+//-------------------------------//
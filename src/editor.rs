@@ -0,0 +1,154 @@
+use std::path::PathBuf;
+
+use crate::{
+	app::{
+		App,
+		PlayFieldCell,
+		PLAYFIELD_PADDING,
+	},
+	mode::GameMode,
+	puzzle::{
+		self,
+		Puzzle,
+	},
+	storage,
+	tetromino::TetrominoKind,
+};
+
+/// Returns the path sandbox setups are saved to.
+pub fn setup_path() -> Option<PathBuf> {
+	storage::data_directory().map(|directory| directory.join("sandbox.txt"))
+}
+
+impl App {
+	//----------[ Editing ]----------//
+	/// Can the board be edited in the current mode?
+	pub fn editable(&self) -> bool {
+		self.mode == GameMode::Sandbox
+	}
+
+	/// Switches between placing tetrominos and editing the board.
+	pub fn toggle_editing(&mut self) {
+		if self.editable() {
+			self.editing = !self.editing;
+		}
+	}
+
+	/// Returns the playfield position of the board cell drawn at a terminal position.
+	pub fn cell_at(&self, column: u16, row: u16) -> Option<(usize, usize)> {
+		let area = self.playfield_area;
+		// Skip the border around the playfield.
+		let column = column.checked_sub(area.x + 1)? as usize;
+		let row = row.checked_sub(area.y + 1)? as usize;
+		let scale = self.cell_scale();
+		let (x, y) = (column / (2 * scale), row / scale);
+		(x < self.board_width && y < self.board_height)
+			.then_some((PLAYFIELD_PADDING + x, PLAYFIELD_PADDING + y))
+	}
+
+	/// Paints a cell with the brush, or erases it. Cells of the falling tetromino are left alone.
+	pub fn paint_cell(&mut self, x: usize, y: usize, erase: bool) {
+		let cell = &mut self.playfield[y][x];
+		if cell.falling {
+			return;
+		}
+		*cell = if erase {
+			PlayFieldCell::default()
+		} else {
+			PlayFieldCell::filled(self.brush)
+		};
+	}
+
+	/// Removes every landed cell from the board.
+	pub fn clear_board(&mut self) {
+		self.save_snapshot();
+		self.playfield
+			.iter_mut()
+			.flatten()
+			.filter(|cell| cell.landed)
+			.for_each(|cell| *cell = PlayFieldCell::default());
+	}
+
+	/// Adds a tetromino to the end of the queue.
+	pub fn push_queue(&mut self, kind: TetrominoKind) {
+		self.save_snapshot();
+		self.tetromino_queue.push(kind.shape());
+	}
+
+	/// Removes the last tetromino of the queue.
+	pub fn pop_queue(&mut self) {
+		self.save_snapshot();
+		self.tetromino_queue.pop();
+	}
+
+	/// Removes every tetromino from the queue, so that it can be written from scratch.
+	pub fn clear_queue(&mut self) {
+		self.save_snapshot();
+		self.tetromino_queue.clear();
+	}
+
+	/// Puts a tetromino in the hold.
+	pub fn set_hold(&mut self, kind: TetrominoKind) {
+		self.save_snapshot();
+		self.swap_tetromino = kind.shape();
+	}
+	//-------------------------------//
+
+	//----------[ Saving ]----------//
+	/// Returns the board, current tetromino, queue and hold as a setup.
+	pub fn setup(&self) -> Puzzle {
+		let board: Vec<Vec<PlayFieldCell>> = self
+			.snapshot()
+			.playfield
+			.iter()
+			.skip(PLAYFIELD_PADDING)
+			.map(|row| row[self.board_columns()].to_vec())
+			.skip_while(|row| row.iter().all(|cell| !cell.landed))
+			.collect();
+		Puzzle {
+			id: String::from("sandbox"),
+			name: String::from("Sandbox"),
+			goal: None,
+			queue: std::iter::once(&self.current_tetromino)
+				.chain(&self.tetromino_queue)
+				.filter_map(|tetromino| tetromino.kind())
+				.collect(),
+			hold: self.swap_tetromino.kind(),
+			board,
+		}
+	}
+
+	/// Saves the setup to the sandbox file.
+	pub fn save_setup(&mut self) {
+		let result = setup_path()
+			.ok_or_else(|| String::from("No data directory found"))
+			.and_then(|path| {
+				storage::write_path(&path, puzzle::to_file_string(&self.setup()))
+					.map(|_| path)
+					.map_err(|error| error.to_string())
+			});
+		self.status_message = Some(match result {
+			Ok(path) => format!("Saved to {}", path.display()),
+			Err(error) => format!("Could not save: {}", error),
+		});
+	}
+
+	/// Loads the setup from the sandbox file.
+	pub fn load_setup(&mut self) {
+		let result = setup_path()
+			.ok_or_else(|| String::from("No data directory found"))
+			.and_then(|path| puzzle::load_setup(&path));
+		match result {
+			Ok(setup) => {
+				self.save_snapshot();
+				self.clear_falling();
+				self.apply_setup(&setup);
+				self.game_over = false;
+				self.spawn_next_tetromino();
+				self.status_message = Some(String::from("Setup loaded"));
+			}
+			Err(error) => self.status_message = Some(format!("Could not load: {}", error)),
+		}
+	}
+	//------------------------------//
+}
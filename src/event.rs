@@ -18,12 +18,14 @@ use crate::app::AppResult;
 pub enum Event {
 	/// Terminal tick.
 	Tick,
-	/// Key press.
+	/// Key press, repeat or release.
 	Key(KeyEvent),
 	/// Mouse click/scroll.
 	Mouse(MouseEvent),
 	/// Terminal resize.
 	Resize(u16, u16),
+	/// The terminal window lost focus.
+	FocusLost,
 }
 
 /// Terminal event handler.
@@ -60,9 +62,7 @@ impl EventHandler {
 				  Some(Ok(evt)) = crossterm_event => {
 					match evt {
 					  CrosstermEvent::Key(key) => {
-						if key.kind == crossterm::event::KeyEventKind::Press {
-						  _sender.send(Event::Key(key)).unwrap();
-						}
+						_sender.send(Event::Key(key)).unwrap();
 					  },
 					  CrosstermEvent::Mouse(mouse) => {
 						_sender.send(Event::Mouse(mouse)).unwrap();
@@ -71,6 +71,7 @@ impl EventHandler {
 						_sender.send(Event::Resize(x, y)).unwrap();
 					  },
 					  CrosstermEvent::FocusLost => {
+						_sender.send(Event::FocusLost).unwrap();
 					  },
 					  CrosstermEvent::FocusGained => {
 					  },
@@ -96,9 +97,6 @@ impl EventHandler {
 		self.receiver
 			.recv()
 			.await
-			.ok_or(Box::new(std::io::Error::new(
-				std::io::ErrorKind::Other,
-				"This is an IO error",
-			)))
+			.ok_or(Box::new(std::io::Error::other("This is an IO error")))
 	}
 }
@@ -0,0 +1,342 @@
+//! Finesse is placing a tetromino with as few inputs as possible. The optimal inputs are found
+//! with a breadth first search over the positions a tetromino can reach on its spawn row before
+//! it is hard dropped, so placements that need a soft drop are not judged.
+
+use std::collections::{
+	HashMap,
+	VecDeque,
+};
+
+use rand::{
+	Rng,
+	SeedableRng,
+};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+	app::{
+		App,
+		PlayFieldCell,
+	},
+	tetromino::{
+		Tetromino,
+		TetrominoKind,
+	},
+};
+
+//----------[ Structs ]----------//
+/// An input that moves or rotates the tetromino.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Input {
+	Left,
+	Right,
+	RotateCw,
+	RotateCcw,
+	/// Moves the tetromino as far left as it goes.
+	DasLeft,
+	/// Moves the tetromino as far right as it goes.
+	DasRight,
+}
+
+/// Finesse of the game so far.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FinesseStats {
+	/// Inputs for the current tetromino.
+	pub inputs: Vec<Input>,
+	/// Inputs beyond the optimal ones, over every judged placement.
+	pub faults: u32,
+	/// Placements judged.
+	pub pieces: u32,
+	/// Optimal inputs of the last placement, when it had faults.
+	pub last_fault: Option<Vec<Input>>,
+}
+
+/// The finesse trainer, which asks for placements on an empty board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinesseTrainer {
+	/// Tetromino type and playfield cells of the placement asked for.
+	pub target: Option<(TetrominoKind, Vec<(usize, usize)>)>,
+	pub correct: u32,
+	pub wrong: u32,
+	/// Picks the targets, apart from the tetrominos so that a seed deals the same ones as in other
+	/// modes.
+	pub rng: ChaCha8Rng,
+}
+//-------------------------------//
+
+impl FinesseTrainer {
+	/// Returns a trainer picking its targets from a seed.
+	pub fn new(seed: u64) -> Self {
+		Self {
+			target: None,
+			correct: 0,
+			wrong: 0,
+			rng: ChaCha8Rng::seed_from_u64(seed),
+		}
+	}
+}
+
+impl Input {
+	/// Every input, taps before DAS so that ties are broken towards taps.
+	pub const ALL: [Input; 6] = [
+		Input::Left,
+		Input::Right,
+		Input::RotateCw,
+		Input::RotateCcw,
+		Input::DasLeft,
+		Input::DasRight,
+	];
+
+	/// Returns the name shown in hints.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Input::Left => "Left",
+			Input::Right => "Right",
+			Input::RotateCw => "CW",
+			Input::RotateCcw => "CCW",
+			Input::DasLeft => "DAS Left",
+			Input::DasRight => "DAS Right",
+		}
+	}
+}
+
+/// Formats inputs as a hint.
+pub fn inputs_string(inputs: &[Input]) -> String {
+	if inputs.is_empty() {
+		String::from("none")
+	} else {
+		inputs
+			.iter()
+			.map(|input| input.name())
+			.collect::<Vec<_>>()
+			.join(" ")
+	}
+}
+
+impl App {
+	//----------[ Positions ]----------//
+	/// Does a tetromino fit at the given rotation and playfield position?
+	fn fits(&self, tetromino: &Tetromino, rotation: usize, x: i32, y: usize) -> bool {
+		(0..4)
+			.flat_map(|cy| (0..4).map(move |cx| (cx, cy)))
+			.filter(|&(cx, cy)| tetromino.rotations[rotation][cy][cx])
+			.all(|(cx, cy)| {
+				let column = x + cx as i32;
+				column >= 0
+					&& self.board_columns().contains(&(column as usize))
+					&& y + cy < self.playfield.len()
+					&& !self.playfield[y + cy][column as usize].landed
+			})
+	}
+
+	/// Applies an input to a tetromino on the spawn row like the game would, returning the
+	/// rotation and column it ends up at.
+	fn apply_input(
+		&self,
+		tetromino: &Tetromino,
+		(rotation, x): (usize, i32),
+		input: Input,
+	) -> (usize, i32) {
+		let y = self.start_y;
+		match input {
+			Input::Left | Input::Right => {
+				let moved = x + if input == Input::Left { -1 } else { 1 };
+				if self.fits(tetromino, rotation, moved, y) {
+					(rotation, moved)
+				} else {
+					(rotation, x)
+				}
+			}
+			Input::DasLeft | Input::DasRight => {
+				let direction = if input == Input::DasLeft { -1 } else { 1 };
+				let mut x = x;
+				while self.fits(tetromino, rotation, x + direction, y) {
+					x += direction;
+				}
+				(rotation, x)
+			}
+			Input::RotateCw | Input::RotateCcw => {
+				let rotated = (rotation + if input == Input::RotateCw { 1 } else { 3 }) % 4;
+				[0, 1, -1]
+					.into_iter()
+					.find(|kick| self.fits(tetromino, rotated, x + kick, y))
+					.map_or((rotation, x), |kick| (rotated, x + kick))
+			}
+		}
+	}
+
+	/// Returns the sorted playfield cells a tetromino covers once hard dropped from the spawn row.
+	fn dropped_cells(&self, tetromino: &Tetromino, rotation: usize, x: i32) -> Vec<(usize, usize)> {
+		let mut y = self.start_y;
+		while self.fits(tetromino, rotation, x, y + 1) {
+			y += 1;
+		}
+		let mut cells: Vec<(usize, usize)> = (0..4)
+			.flat_map(|cy| (0..4).map(move |cx| (cx, cy)))
+			.filter(|&(cx, cy)| tetromino.rotations[rotation][cy][cx])
+			.map(|(cx, cy)| ((x + cx as i32) as usize, y + cy))
+			.collect();
+		cells.sort();
+		cells
+	}
+
+	/// Returns the fewest inputs that place a tetromino from its spawn position on the given
+	/// playfield cells with a hard drop, or `None` when that takes a soft drop.
+	pub fn optimal_inputs(
+		&self,
+		tetromino: &Tetromino,
+		cells: &[(usize, usize)],
+	) -> Option<Vec<Input>> {
+		let mut cells = cells.to_vec();
+		cells.sort();
+		let start = (0, self.start_x as i32);
+		if !self.fits(tetromino, start.0, start.1, self.start_y) {
+			return None;
+		}
+
+		let mut previous: HashMap<(usize, i32), ((usize, i32), Input)> = HashMap::new();
+		let mut queue = VecDeque::from([start]);
+		while let Some(position) = queue.pop_front() {
+			if self.dropped_cells(tetromino, position.0, position.1) == cells {
+				let mut inputs = vec![];
+				let mut position = position;
+				while let Some(&(from, input)) = previous.get(&position) {
+					inputs.push(input);
+					position = from;
+				}
+				inputs.reverse();
+				return Some(inputs);
+			}
+			for input in Input::ALL {
+				let next = self.apply_input(tetromino, position, input);
+				if next != start && !previous.contains_key(&next) {
+					previous.insert(next, (position, input));
+					queue.push_back(next);
+				}
+			}
+		}
+		None
+	}
+	//---------------------------------//
+
+	//----------[ Tracking ]----------//
+	/// Logs an input for the current tetromino.
+	pub fn log_input(&mut self, input: Input) {
+		self.finesse.inputs.push(input);
+	}
+
+	/// Judges the inputs for the current tetromino before it lands on the given cells, returning
+	/// its finesse faults when the optimal inputs could be found.
+	pub fn judge_finesse(&mut self, cells: &[(usize, usize)]) -> Option<u32> {
+		let inputs = std::mem::take(&mut self.finesse.inputs);
+		let optimal = self.optimal_inputs(&self.current_tetromino, cells)?;
+		let faults = inputs.len().saturating_sub(optimal.len()) as u32;
+		self.finesse.pieces += 1;
+		self.finesse.faults += faults;
+		self.finesse.last_fault = (faults > 0).then_some(optimal);
+		Some(faults)
+	}
+	//--------------------------------//
+
+	//----------[ Trainer ]----------//
+	/// Returns the playfield cells the trainer asks the current tetromino to be placed on.
+	pub fn finesse_target(&self) -> Option<Vec<(usize, usize)>> {
+		let (kind, cells) = self.finesse_trainer.as_ref()?.target.as_ref()?;
+		(self.current_tetromino.kind() == Some(*kind)).then(|| cells.clone())
+	}
+
+	/// Picks a random placement for the trainer to ask for, unless the current tetromino already
+	/// has one.
+	pub fn pick_finesse_target(&mut self) {
+		let Some(kind) = self.current_tetromino.kind() else {
+			return;
+		};
+		let asked = match &self.finesse_trainer {
+			Some(trainer) => trainer.target.as_ref().map(|(target, _)| *target),
+			None => return,
+		};
+		if asked == Some(kind) {
+			return;
+		}
+
+		let tetromino = self.current_tetromino;
+		let mut placements: Vec<Vec<(usize, usize)>> = vec![];
+		for rotation in 0..4 {
+			for x in 0..self.playfield[0].len() as i32 {
+				if !self.fits(&tetromino, rotation, x, self.start_y) {
+					continue;
+				}
+				let cells = self.dropped_cells(&tetromino, rotation, x);
+				if !placements.contains(&cells)
+					&& self.optimal_inputs(&tetromino, &cells).is_some()
+				{
+					placements.push(cells);
+				}
+			}
+		}
+		// Nothing fits while the stack tops out.
+		if placements.is_empty() {
+			return;
+		}
+		if let Some(trainer) = &mut self.finesse_trainer {
+			let cells = placements.swap_remove(trainer.rng.gen_range(0..placements.len()));
+			trainer.target = Some((kind, cells));
+		}
+	}
+
+	/// Judges a placement of the trainer, asking for it again when it missed the target or took
+	/// too many inputs.
+	pub fn check_finesse_placement(
+		&mut self,
+		kind: Option<TetrominoKind>,
+		cells: &[(usize, usize)],
+		faults: Option<u32>,
+	) {
+		let Some(trainer) = &self.finesse_trainer else {
+			return;
+		};
+		let target = trainer.target.clone();
+		let mut cells = cells.to_vec();
+		cells.sort();
+		let on_target = target
+			.as_ref()
+			.is_some_and(|(target_kind, target)| kind == Some(*target_kind) && *target == cells);
+		let correct = on_target && faults == Some(0);
+
+		// The trainer always asks for placements on an empty board.
+		self.playfield
+			.iter_mut()
+			.flatten()
+			.filter(|cell| cell.landed)
+			.for_each(|cell| *cell = PlayFieldCell::default());
+
+		self.status_message = match target.filter(|_| !correct) {
+			Some((_, target)) => {
+				let optimal = self
+					.optimal_inputs(&self.current_tetromino, &target)
+					.unwrap_or_default();
+				Some(format!(
+					"{}, optimal: {}",
+					if on_target {
+						"Too many inputs"
+					} else {
+						"Wrong placement"
+					},
+					inputs_string(&optimal)
+				))
+			}
+			None => None,
+		};
+		if let Some(trainer) = &mut self.finesse_trainer {
+			if correct {
+				trainer.correct += 1;
+				trainer.target = None;
+			} else {
+				trainer.wrong += 1;
+				// Ask for the same placement with the same tetromino again.
+				self.tetromino_queue.insert(0, self.current_tetromino);
+			}
+		}
+	}
+	//-------------------------------//
+}
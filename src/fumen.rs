@@ -0,0 +1,648 @@
+//! Fumen is the format the community shares boards in, as strings like `v115@vhAAgH`. Only version
+//! 115 is read and written.
+//!
+//! - The data after `v115@` is a list of numbers written as base 64 digits, least significant
+//!   first, with a `?` every 47 characters that is skipped.
+//! - Every page starts with its field as runs of changes from the field the previous page leads
+//!   to. A field without changes is followed by the number of following pages that leave it out.
+//! - The field has 23 rows of 10 cells, and a row of garbage below them that can rise.
+//! - Then comes the piece of the page with its rotation and position, and flags telling whether
+//!   it locks, whether the garbage row rises and whether the field is mirrored afterwards.
+//! - Pages with a new comment follow with it, escaped and four characters to 5 digits.
+//!
+//! Boards are imported as sandbox setups, whose queue holds the pieces of every page.
+
+use crate::{
+	app::{
+		App,
+		PlayFieldCell,
+		PLAYFIELD_PADDING,
+	},
+	puzzle::Puzzle,
+	replay::{
+		self,
+		Replay,
+	},
+	tetromino::TetrominoKind,
+};
+
+/// Digits of the data, each worth its index.
+const DIGITS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+/// Characters of comments, each worth its index.
+const COMMENT_CHARACTERS: &str = concat!(
+	" !\"#$%&'()*+,-./0123456789:;<=>?@",
+	"ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~",
+);
+/// Values of comment characters.
+const COMMENT_BASE: u32 = 96;
+const PREFIX: &str = "v115@";
+/// Columns of the field.
+const FIELD_WIDTH: usize = 10;
+/// Rows of the field above the garbage row.
+const FIELD_TOP: usize = 23;
+/// Cells of the field with the garbage row.
+const FIELD_CELLS: usize = (FIELD_TOP + 1) * FIELD_WIDTH;
+/// Tetromino types by their fumen code, after 0 for an empty cell and before 8 for garbage.
+const KINDS: [TetrominoKind; 7] = [
+	TetrominoKind::I,
+	TetrominoKind::L,
+	TetrominoKind::O,
+	TetrominoKind::Z,
+	TetrominoKind::T,
+	TetrominoKind::J,
+	TetrominoKind::S,
+];
+/// Fumen codes of the rotations clockwise from spawn, which are also the rotations of the codes.
+const ROTATIONS: [u32; 4] = [2, 1, 0, 3];
+
+//----------[ Structs ]----------//
+/// A piece shown on a page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FumenPiece {
+	pub kind: TetrominoKind,
+	/// Rotation clockwise from spawn.
+	pub rotation: usize,
+	/// Column of the rotation center, from the left.
+	pub x: i32,
+	/// Row of the rotation center, from the bottom.
+	pub y: i32,
+}
+
+/// A page of a fumen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FumenPage {
+	/// Rows of the field from top to bottom, with the garbage row last.
+	pub field: Vec<Vec<PlayFieldCell>>,
+	pub piece: Option<FumenPiece>,
+	pub comment: String,
+	/// Is the piece locked, clearing lines, in the field of the next page?
+	pub lock: bool,
+	/// Does the garbage row rise into the field of the next page?
+	pub rise: bool,
+	/// Is the field of the next page mirrored?
+	pub mirror: bool,
+}
+//-------------------------------//
+
+impl Default for FumenPage {
+	fn default() -> Self {
+		Self {
+			field: empty_field(),
+			piece: None,
+			comment: String::new(),
+			lock: true,
+			rise: false,
+			mirror: false,
+		}
+	}
+}
+
+/// Returns a field without any cell.
+fn empty_field() -> Vec<Vec<PlayFieldCell>> {
+	vec![vec![PlayFieldCell::default(); FIELD_WIDTH]; FIELD_TOP + 1]
+}
+
+/// Returns the fumen code of a cell.
+fn cell_code(cell: &PlayFieldCell) -> u32 {
+	match (cell.landed, cell.kind) {
+		(false, _) => 0,
+		(true, None) => 8,
+		(true, Some(kind)) => KINDS.iter().position(|&other| other == kind).unwrap_or(0) as u32 + 1,
+	}
+}
+
+/// Returns the cell of a fumen code.
+fn code_cell(code: u32) -> PlayFieldCell {
+	match code {
+		1..=7 => PlayFieldCell::filled(Some(KINDS[code as usize - 1])),
+		8 => PlayFieldCell::filled(None),
+		_ => PlayFieldCell::default(),
+	}
+}
+
+impl FumenPiece {
+	/// Returns the columns and rows from the bottom of the cells of the piece.
+	pub fn cells(&self) -> [(i32, i32); 4] {
+		let shape = match self.kind {
+			TetrominoKind::I => [(0, 0), (-1, 0), (1, 0), (2, 0)],
+			TetrominoKind::T => [(0, 0), (-1, 0), (1, 0), (0, 1)],
+			TetrominoKind::O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+			TetrominoKind::L => [(0, 0), (-1, 0), (1, 0), (1, 1)],
+			TetrominoKind::J => [(0, 0), (-1, 0), (1, 0), (-1, 1)],
+			TetrominoKind::S => [(0, 0), (-1, 0), (0, 1), (1, 1)],
+			TetrominoKind::Z => [(0, 0), (1, 0), (0, 1), (-1, 1)],
+		};
+		shape.map(|(x, y)| {
+			let (x, y) = match self.rotation % 4 {
+				0 => (x, y),
+				1 => (y, -x),
+				2 => (-x, -y),
+				_ => (-y, x),
+			};
+			(self.x + x, self.y + y)
+		})
+	}
+
+	/// Returns the piece covering some cells, trying the given rotation first.
+	pub fn covering(kind: TetrominoKind, cells: &[(i32, i32)], rotation: usize) -> Option<Self> {
+		let (first_x, first_y) = *cells.first()?;
+		(0..4).map(|offset| (rotation + offset) % 4).find_map(|rotation| {
+			let piece = FumenPiece {
+				kind,
+				rotation,
+				x: 0,
+				y: 0,
+			};
+			piece.cells().into_iter().find_map(|(x, y)| {
+				let piece = FumenPiece {
+					x: first_x - x,
+					y: first_y - y,
+					..piece
+				};
+				let covered = piece.cells();
+				(covered.len() == cells.len() && cells.iter().all(|cell| covered.contains(cell)))
+					.then_some(piece)
+			})
+		})
+	}
+
+	/// Returns how far the position fumen stores is from the rotation center.
+	fn position_offset(&self) -> (i32, i32) {
+		match (self.kind, self.rotation % 4) {
+			(TetrominoKind::O, 3) => (1, -1),
+			(TetrominoKind::O, 2) | (TetrominoKind::I, 2) => (1, 0),
+			(TetrominoKind::O, 0) | (TetrominoKind::S, 0) | (TetrominoKind::Z, 0) => (0, -1),
+			(TetrominoKind::I, 3) => (0, -1),
+			(TetrominoKind::S, 1) => (-1, 0),
+			(TetrominoKind::Z, 3) => (1, 0),
+			_ => (0, 0),
+		}
+	}
+}
+
+/// Returns the field a page leads to, with its piece locked and lines cleared.
+fn next_field(page: &FumenPage) -> Vec<Vec<PlayFieldCell>> {
+	let mut field = page.field.clone();
+	if !page.lock {
+		return field;
+	}
+	for (x, y) in page.piece.iter().flat_map(FumenPiece::cells) {
+		if (0..FIELD_WIDTH as i32).contains(&x) && (0..FIELD_TOP as i32).contains(&y) {
+			let kind = page.piece.map(|piece| piece.kind);
+			field[FIELD_TOP - 1 - y as usize][x as usize] = PlayFieldCell::filled(kind);
+		}
+	}
+	let garbage = field.pop().unwrap_or_default();
+	field.retain(|row| !row.iter().all(|cell| cell.landed));
+	while field.len() < FIELD_TOP {
+		field.insert(0, vec![PlayFieldCell::default(); FIELD_WIDTH]);
+	}
+	if page.rise {
+		field.remove(0);
+		field.push(garbage);
+		field.push(vec![PlayFieldCell::default(); FIELD_WIDTH]);
+	} else {
+		field.push(garbage);
+	}
+	if page.mirror {
+		field[..FIELD_TOP].iter_mut().for_each(|row| row.reverse());
+	}
+	field
+}
+
+//----------[ Comments ]----------//
+/// Escapes a comment like JavaScript's `escape`.
+fn escape(comment: &str) -> String {
+	let mut result = String::new();
+	for character in comment.chars() {
+		match character {
+			'A'..='Z' | 'a'..='z' | '0'..='9' | '@' | '*' | '_' | '+' | '-' | '.' | '/' => {
+				result.push(character);
+			}
+			character if (character as u32) < 0x100 => result += &format!("%{:02X}", character as u32),
+			character => {
+				for unit in character.encode_utf16(&mut [0; 2]) {
+					result += &format!("%u{:04X}", unit);
+				}
+			}
+		}
+	}
+	result
+}
+
+/// Unescapes a comment like JavaScript's `unescape`.
+fn unescape(comment: &str) -> String {
+	let mut units = vec![];
+	let mut rest = comment;
+	while let Some(character) = rest.chars().next() {
+		let code = |digits: &str| u16::from_str_radix(digits, 16).ok();
+		let (unit, length) = match (character, rest.get(1..2), rest.get(2..6), rest.get(1..3)) {
+			('%', Some("u"), Some(digits), _) if code(digits).is_some() => (code(digits), 6),
+			('%', _, _, Some(digits)) if code(digits).is_some() => (code(digits), 3),
+			_ => (None, character.len_utf8()),
+		};
+		match unit {
+			Some(unit) => units.push(unit),
+			None => units.extend(character.encode_utf16(&mut [0; 2]).iter()),
+		}
+		rest = &rest[length..];
+	}
+	String::from_utf16_lossy(&units)
+}
+//--------------------------------//
+
+//----------[ Parsing ]----------//
+/// Digits of the data being parsed.
+struct Digits<'a>(std::str::Chars<'a>);
+
+impl Digits<'_> {
+	/// Reads a number of some digits.
+	fn poll(&mut self, count: u32) -> Result<u32, String> {
+		let mut value = 0;
+		for index in 0..count {
+			let digit = self
+				.0
+				.next()
+				.ok_or_else(|| String::from("the data ends too early"))?;
+			let digit = DIGITS
+				.find(digit)
+				.ok_or_else(|| format!("invalid character '{}'", digit))?;
+			value += digit as u32 * 64u32.pow(index);
+		}
+		Ok(value)
+	}
+
+	/// Is every digit read?
+	fn is_empty(&self) -> bool {
+		self.0.as_str().is_empty()
+	}
+}
+
+/// Reads a field as changes from the previous one, returning whether anything changed.
+fn parse_field(
+	digits: &mut Digits,
+	previous: &[Vec<PlayFieldCell>],
+) -> Result<(Vec<Vec<PlayFieldCell>>, bool), String> {
+	let mut field = previous.to_vec();
+	let mut index = 0;
+	let mut changed = true;
+	while index < FIELD_CELLS {
+		let value = digits.poll(2)?;
+		let (change, count) = (value / FIELD_CELLS as u32, value as usize % FIELD_CELLS + 1);
+		if change == 8 && count == FIELD_CELLS {
+			changed = false;
+		}
+		if index + count > FIELD_CELLS {
+			return Err(String::from("the field has too many cells"));
+		}
+		for index in index..index + count {
+			let cell = &mut field[index / FIELD_WIDTH][index % FIELD_WIDTH];
+			let code = (cell_code(cell) + change)
+				.checked_sub(8)
+				.filter(|&code| code <= 8)
+				.ok_or_else(|| String::from("invalid field cell"))?;
+			*cell = code_cell(code);
+		}
+		index += count;
+	}
+	Ok((field, changed))
+}
+
+/// Parses the pages of a fumen, given alone or in a link.
+pub fn parse(data: &str) -> Result<Vec<FumenPage>, String> {
+	let start = data
+		.find(PREFIX)
+		.ok_or_else(|| String::from("not a fumen of version 115, expected 'v115@...'"))?;
+	let data: String = data[start + PREFIX.len()..]
+		.trim()
+		.chars()
+		.filter(|&character| character != '?')
+		.collect();
+	let mut digits = Digits(data.chars());
+	let mut pages: Vec<FumenPage> = vec![];
+	let mut field = empty_field();
+	let mut repeats = 0;
+	let mut comment = String::new();
+
+	while !digits.is_empty() {
+		if repeats > 0 {
+			repeats -= 1;
+		} else {
+			let changed;
+			(field, changed) = parse_field(&mut digits, &field)?;
+			if !changed {
+				repeats = digits.poll(1)?;
+			}
+		}
+
+		let mut action = digits.poll(3)?;
+		let mut take = |base: u32| {
+			let value = action % base;
+			action /= base;
+			value
+		};
+		let (kind, rotation, position) = (take(8), take(4), take(FIELD_CELLS as u32));
+		let [rise, mirror, _color, has_comment, keep] = [(); 5].map(|_| take(2) == 1);
+		let piece = (1..=7).contains(&kind).then(|| {
+			let mut piece = FumenPiece {
+				kind: KINDS[kind as usize - 1],
+				rotation: ROTATIONS[rotation as usize] as usize,
+				x: (position as usize % FIELD_WIDTH) as i32,
+				y: FIELD_TOP as i32 - 1 - (position as usize / FIELD_WIDTH) as i32,
+			};
+			let (x, y) = piece.position_offset();
+			(piece.x, piece.y) = (piece.x + x, piece.y + y);
+			piece
+		});
+
+		if has_comment {
+			let length = digits.poll(2)? as usize;
+			let mut escaped = String::new();
+			while escaped.chars().count() < length {
+				let mut value = digits.poll(5)?;
+				for _ in 0..(length - escaped.chars().count()).min(4) {
+					let character = COMMENT_CHARACTERS
+						.chars()
+						.nth((value % COMMENT_BASE) as usize)
+						.ok_or_else(|| String::from("invalid comment"))?;
+					escaped.push(character);
+					value /= COMMENT_BASE;
+				}
+			}
+			comment = unescape(&escaped);
+		}
+
+		let page = FumenPage {
+			field,
+			piece,
+			comment: comment.clone(),
+			lock: !keep,
+			rise,
+			mirror,
+		};
+		field = next_field(&page);
+		pages.push(page);
+	}
+
+	if pages.is_empty() {
+		return Err(String::from("the fumen has no page"));
+	}
+	Ok(pages)
+}
+
+/// Returns the board of the first page as a sandbox setup, with the pieces of every page as its
+/// queue.
+pub fn setup(pages: &[FumenPage]) -> Puzzle {
+	let field = pages.first().map_or_else(empty_field, |page| page.field.clone());
+	let name = pages
+		.first()
+		.map(|page| page.comment.clone())
+		.filter(|comment| !comment.is_empty())
+		.unwrap_or(String::from("Fumen"));
+	Puzzle {
+		id: String::from("fumen"),
+		name,
+		goal: None,
+		queue: pages
+			.iter()
+			.filter_map(|page| page.piece.map(|piece| piece.kind))
+			.collect(),
+		hold: None,
+		board: field[..FIELD_TOP]
+			.iter()
+			.skip_while(|row| row.iter().all(|cell| !cell.landed))
+			.cloned()
+			.collect(),
+	}
+}
+//-------------------------------//
+
+//----------[ Writing ]----------//
+/// Writes a number as some digits.
+fn push(values: &mut Vec<u32>, mut value: u32, count: u32) {
+	for _ in 0..count {
+		values.push(value % 64);
+		value /= 64;
+	}
+}
+
+/// Writes a field as runs of changes from the previous one, returning whether anything changed.
+fn field_values(previous: &[Vec<PlayFieldCell>], field: &[Vec<PlayFieldCell>]) -> (Vec<u32>, bool) {
+	let changes: Vec<u32> = previous
+		.iter()
+		.flatten()
+		.zip(field.iter().flatten())
+		.map(|(before, after)| cell_code(after) + 8 - cell_code(before))
+		.collect();
+	let mut values = vec![];
+	let mut index = 0;
+	while index < changes.len() {
+		let count = changes[index..]
+			.iter()
+			.take_while(|&&change| change == changes[index])
+			.count();
+		push(&mut values, changes[index] * FIELD_CELLS as u32 + count as u32 - 1, 2);
+		index += count;
+	}
+	let changed = changes.iter().any(|&change| change != 8);
+	(values, changed)
+}
+
+/// Writes pages as a fumen.
+pub fn encode(pages: &[FumenPage]) -> String {
+	let mut values = vec![];
+	let mut field = empty_field();
+	let mut comment = String::new();
+	// Index of the number of pages that leave the current field out, while it is unchanged.
+	let mut repeats: Option<usize> = None;
+
+	for (index, page) in pages.iter().enumerate() {
+		let (changes, changed) = field_values(&field, &page.field);
+		match repeats {
+			Some(repeats) if !changed && values[repeats] < 63 => values[repeats] += 1,
+			_ if !changed => {
+				values.extend(changes);
+				values.push(0);
+				repeats = Some(values.len() - 1);
+			}
+			_ => {
+				values.extend(changes);
+				repeats = None;
+			}
+		}
+
+		let has_comment = page.comment != comment;
+		let (kind, rotation, position) = match page.piece {
+			Some(piece) => {
+				let (x, y) = piece.position_offset();
+				let (x, y) = (piece.x - x, piece.y - y);
+				(
+					KINDS.iter().position(|&kind| kind == piece.kind).unwrap_or(0) as u32 + 1,
+					ROTATIONS[piece.rotation % 4],
+					((FIELD_TOP as i32 - 1 - y) * FIELD_WIDTH as i32 + x).clamp(0, FIELD_CELLS as i32 - 1) as u32,
+				)
+			}
+			None => (0, 0, 0),
+		};
+		let flags = [page.rise, page.mirror, index == 0, has_comment, !page.lock]
+			.iter()
+			.rev()
+			.fold(0, |flags, &flag| flags * 2 + flag as u32);
+		push(
+			&mut values,
+			kind + 8 * (rotation + 4 * (position + FIELD_CELLS as u32 * flags)),
+			3,
+		);
+
+		if has_comment {
+			let escaped: Vec<u32> = escape(&page.comment)
+				.chars()
+				.take(4095)
+				.map(|character| COMMENT_CHARACTERS.find(character).unwrap_or(0) as u32)
+				.collect();
+			push(&mut values, escaped.len() as u32, 2);
+			for chunk in escaped.chunks(4) {
+				let value = chunk
+					.iter()
+					.rev()
+					.fold(0, |value, &character| value * COMMENT_BASE + character);
+				push(&mut values, value, 5);
+			}
+			comment = page.comment.clone();
+		}
+		field = next_field(page);
+	}
+
+	let data: String = values
+		.iter()
+		.map(|&value| DIGITS.as_bytes()[value as usize] as char)
+		.collect();
+	// A `?` follows the first 42 characters and every 47 after them.
+	let mut result = String::from(PREFIX);
+	let (head, mut tail) = data.split_at(data.len().min(42));
+	result += head;
+	while !tail.is_empty() {
+		let (chunk, rest) = tail.split_at(tail.len().min(47));
+		result += &format!("?{}", chunk);
+		tail = rest;
+	}
+	result
+}
+//-------------------------------//
+
+impl App {
+	/// Returns the board as a fumen field, which is 10 columns wide.
+	pub fn fumen_field(&self) -> Result<Vec<Vec<PlayFieldCell>>, String> {
+		if self.board_width != FIELD_WIDTH {
+			return Err(format!("fumen boards are {} columns wide", FIELD_WIDTH));
+		}
+		let mut field = empty_field();
+		// The board and the field share their bottom row.
+		for (row, cells) in field[..FIELD_TOP].iter_mut().rev().zip(self.playfield.iter().rev()) {
+			for (cell, board_cell) in row.iter_mut().zip(&cells[self.board_columns()]) {
+				if board_cell.landed {
+					*cell = PlayFieldCell::filled(board_cell.kind);
+				}
+			}
+		}
+		Ok(field)
+	}
+
+	/// Returns the piece covering some playfield cells, trying the given rotation first.
+	pub fn fumen_piece(
+		&self,
+		kind: TetrominoKind,
+		cells: &[(usize, usize)],
+		rotation: usize,
+	) -> Option<FumenPiece> {
+		let cells: Vec<(i32, i32)> = cells
+			.iter()
+			.map(|&(x, y)| {
+				(
+					x as i32 - PLAYFIELD_PADDING as i32,
+					self.playfield.len() as i32 - 1 - y as i32,
+				)
+			})
+			.collect();
+		FumenPiece::covering(kind, &cells, rotation)
+	}
+
+	/// Returns the board with the falling tetromino as a page.
+	pub fn fumen_page(&self) -> Result<FumenPage, String> {
+		let falling = self.playfield.iter().flatten().any(|cell| cell.falling);
+		let piece = self
+			.current_tetromino
+			.kind()
+			.filter(|_| falling && !self.game_over)
+			.and_then(|kind| self.fumen_piece(kind, &self.tetromino_cells(), self.current_rotation));
+		Ok(FumenPage {
+			field: self.fumen_field()?,
+			piece,
+			// The tetromino is only shown where it is, not placed.
+			lock: false,
+			..FumenPage::default()
+		})
+	}
+
+	/// Exports the board with the falling tetromino as a fumen, printed once tetrs quits.
+	pub fn export_fumen(&mut self) {
+		self.status_message = Some(match self.fumen_page() {
+			Ok(page) => {
+				self.fumen_export = Some(encode(&[page]));
+				String::from("Fumen exported, it is printed when tetrs quits")
+			}
+			Err(error) => format!("Could not export: {}", error),
+		});
+	}
+}
+
+/// Plays a replay back and returns a page for every placement, with the board before it, and a
+/// last page with the board at the end.
+pub fn replay_pages(replay: &Replay) -> Result<Vec<FumenPage>, String> {
+	let mut game = replay.game();
+	let mut pages = vec![];
+	let mut next_input = 0;
+	while !replay.finished(&game, next_input) {
+		// Every input is played on its own, since each can lock a tetromino.
+		let mut inputs = vec![];
+		while let Some(&(tick, input)) = replay.inputs.get(next_input) {
+			if tick != game.replay_ticks {
+				break;
+			}
+			inputs.push(Some(input));
+			next_input += 1;
+		}
+		inputs.push(None);
+		for input in inputs {
+			let field = game.fumen_field()?;
+			let pieces = game.stats.pieces;
+			match input {
+				Some(input) => game.play_input(input),
+				None => game.advance(),
+			}
+			let Some(lock) = game.last_lock.as_ref().filter(|_| game.stats.pieces != pieces) else {
+				continue;
+			};
+			pages.push(FumenPage {
+				field,
+				piece: lock
+					.tetromino
+					.and_then(|kind| game.fumen_piece(kind, &lock.cells, 0)),
+				..FumenPage::default()
+			});
+		}
+	}
+	pages.push(FumenPage {
+		field: game.fumen_field()?,
+		..FumenPage::default()
+	});
+	Ok(pages)
+}
+
+/// Returns the fumen of the placements of a replay file.
+pub fn export(path: &std::path::Path) -> Result<String, String> {
+	let replay = replay::load(path)?;
+	replay_pages(&replay)
+		.map(|pages| encode(&pages))
+		.map_err(|error| format!("{}: {}", path.display(), error))
+}
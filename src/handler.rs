@@ -3,20 +3,44 @@ use crate::{
 		App,
 		AppResult,
 	},
-	tetromino::TETROMINO_SHAPES,
+	config::Action,
+	replay::ReplayInput,
+	screen::Screen,
+	tetromino::TetrominoKind,
 };
 use crossterm::event::{
 	KeyCode,
 	KeyEvent,
+	KeyEventKind,
 	KeyModifiers,
 	MouseButton,
 	MouseEvent,
 	MouseEventKind,
 };
-use rand::Rng;
 
 /// handles the mouse events
 pub fn handle_mouse_events(mouse_event: MouseEvent, app: &mut App) {
+	if app.screen.is_menu() || app.screen == Screen::Paused {
+		handle_menu_mouse_events(mouse_event, app);
+		return;
+	}
+	if app.screen == Screen::Watching {
+		return;
+	}
+	if app.screen == Screen::Settings {
+		match mouse_event.kind {
+			MouseEventKind::ScrollUp => app.select_setting(-1),
+			MouseEventKind::ScrollDown => app.select_setting(1),
+			_ => {}
+		}
+		return;
+	}
+
+	if app.editing {
+		handle_editor_mouse_events(mouse_event, app);
+		return;
+	}
+
 	if mouse_event.kind == MouseEventKind::Down(MouseButton::Left) {
 		let mut button_index: u16 = 99;
 		for (index, button) in app.buttons.iter().enumerate() {
@@ -30,134 +54,271 @@ pub fn handle_mouse_events(mouse_event: MouseEvent, app: &mut App) {
 			}
 		}
 		match button_index {
-			0 => {
-				if app.current_rotation > 0 {
-					app.current_rotation -= 1;
-				} else {
-					app.current_rotation = 3;
-				}
-				app.clear_falling();
-				app.move_tetromino(0, 0, app.current_tetromino);
-			}
-			1 => {
-				if !app.has_landed_cells_at_offset(-1, 0) {
-					app.move_tetromino(-1, 0, app.current_tetromino);
-				}
-			}
-			2 => {
-				if app.current_rotation < 3 {
-					app.current_rotation += 1;
-				} else {
-					app.current_rotation = 0;
-				}
-				app.clear_falling();
-				app.move_tetromino(0, 0, app.current_tetromino);
-			}
-			3 => {
-				if !app.has_landed_cells_at_offset(1, 0) {
-					app.move_tetromino(1, 0, app.current_tetromino);
-				}
-			}
-			4 => {
-				app.tick_count_target = 0;
-			}
-			5 => {
-				app.drop_tetromino();
-			}
-			6 => {
-				app.paused = !app.paused;
-			}
-			7 => {
-				app.swap_tetromino();
-			}
+			0 => app.input(ReplayInput::Press(Action::RotateCcw)),
+			1 => tap(Action::Left, app),
+			2 => app.input(ReplayInput::Press(Action::RotateCw)),
+			3 => tap(Action::Right, app),
+			4 => app.input(ReplayInput::Press(Action::SoftDrop)),
+			5 => app.input(ReplayInput::Press(Action::HardDrop)),
+			6 => app.toggle_pause(),
+			7 => app.input(ReplayInput::Press(Action::Hold)),
 			_ => {}
 		}
 	}
 }
 
-/// Handles the key events and updates the state of [`App`].
-pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
-	if app.paused && (key_event.code != KeyCode::Char('p')) {
-		// Exit application on `ESC`
-		if key_event.code == KeyCode::Esc
-			|| (key_event.code == KeyCode::Char('c') || key_event.code == KeyCode::Char('C'))
-				&& key_event.modifiers == KeyModifiers::CONTROL
-		{
-			app.quit();
-		}
-		return Ok(()); // Return early if not paused and not 'p' key
-	}
+/// Presses and releases the key of an action at once, so that it never repeats.
+fn tap(action: Action, app: &mut App) {
+	app.input(ReplayInput::Press(action));
+	app.input(ReplayInput::Release(action));
+}
 
-	match key_event.code {
-		// Exit application on `ESC`
-		KeyCode::Esc => {
-			app.quit();
-		}
-		// Exit application on `Ctrl-C`
-		KeyCode::Char('c') | KeyCode::Char('C') => {
-			if key_event.modifiers == KeyModifiers::CONTROL {
-				app.quit();
+/// Picks an item with the left mouse button and selects them with the wheel on a menu screen.
+fn handle_menu_mouse_events(mouse_event: MouseEvent, app: &mut App) {
+	match mouse_event.kind {
+		MouseEventKind::Down(MouseButton::Left) => {
+			let clicked = app.menu_buttons.iter().position(|button| {
+				mouse_event.column >= button.x
+					&& mouse_event.column < button.x + button.width
+					&& mouse_event.row >= button.y
+					&& mouse_event.row < button.y + button.height
+			});
+			if let Some(index) = clicked {
+				app.menu_selected = index;
+				app.activate_menu_item();
 			}
 		}
-		// Toggle pause
-		KeyCode::Char('p') => {
-			app.paused = !app.paused;
-		}
-		// Counter handlers
-		KeyCode::Right => {
-			app.playfield[app.start_y + 1_usize][app.start_x + 1_usize].landed =
-				!app.playfield[app.start_y + 1_usize][app.start_x + 1_usize].landed;
-		}
-		KeyCode::Left => {
-			app.clear_falling();
-			app.current_tetromino = app.spawn_tetromino(
-				app.x,
-				app.y,
-				TETROMINO_SHAPES[rand::thread_rng().gen_range(0..TETROMINO_SHAPES.len())],
-			);
-		}
-		KeyCode::Up => {
-			app.reset_tetromino();
-		}
-		KeyCode::Char(' ') => {
-			app.drop_tetromino();
+		MouseEventKind::ScrollUp => app.select_menu_item(-1),
+		MouseEventKind::ScrollDown => app.select_menu_item(1),
+		_ => {}
+	}
+}
+
+/// Paints with the left mouse button and erases with the right one while editing the board.
+fn handle_editor_mouse_events(mouse_event: MouseEvent, app: &mut App) {
+	let erase = match mouse_event.kind {
+		MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Drag(MouseButton::Left) => false,
+		MouseEventKind::Down(MouseButton::Right) | MouseEventKind::Drag(MouseButton::Right) => true,
+		_ => return,
+	};
+	if let Some((x, y)) = app.cell_at(mouse_event.column, mouse_event.row) {
+		// A stroke is undone as a whole.
+		if matches!(mouse_event.kind, MouseEventKind::Down(_)) {
+			app.save_snapshot();
 		}
-		KeyCode::Char('w') => {
-			app.swap_tetromino();
+		app.paint_cell(x, y, erase);
+	}
+}
+
+/// Handles the key events while editing the board.
+fn handle_editor_key_events(key_event: KeyEvent, app: &mut App) {
+	match key_event.code {
+		KeyCode::Tab | KeyCode::Esc => app.toggle_editing(),
+		// Pick the brush
+		KeyCode::Char(digit @ '1'..='7') => {
+			app.brush = Some(TetrominoKind::ALL[digit as usize - '1' as usize]);
 		}
-		KeyCode::Char('a') => {
-			if !app.has_landed_cells_at_offset(-1, 0) {
-				app.move_tetromino(-1, 0, app.current_tetromino);
+		KeyCode::Char('8') => app.brush = None,
+		// Edit the queue and hold
+		KeyCode::Char(letter) if TetrominoKind::from_letter(letter).is_some() => {
+			let kind = TetrominoKind::from_letter(letter).unwrap();
+			if letter.is_ascii_uppercase() {
+				app.set_hold(kind);
+			} else {
+				app.push_queue(kind);
 			}
 		}
-		KeyCode::Char('s') => {
-			app.tick_count_target = 0;
-		}
-		KeyCode::Char('d') => {
-			if !app.has_landed_cells_at_offset(1, 0) {
-				app.move_tetromino(1, 0, app.current_tetromino);
-			}
+		KeyCode::Backspace => app.pop_queue(),
+		KeyCode::Delete => app.clear_queue(),
+		KeyCode::Char('c') => app.clear_board(),
+		_ => {}
+	}
+}
+
+/// Handles the key events while the settings menu is open.
+fn handle_settings_key_events(key_event: KeyEvent, app: &mut App) {
+	if app.settings.binding {
+		app.bind_setting(key_event.code);
+		return;
+	}
+	match key_event.code {
+		KeyCode::Up => app.select_setting(-1),
+		KeyCode::Down => app.select_setting(1),
+		KeyCode::Left => app.adjust_setting(-1),
+		KeyCode::Right => app.adjust_setting(1),
+		KeyCode::Enter | KeyCode::Char(' ') => app.activate_setting(),
+		KeyCode::Esc => app.close_settings(),
+		_ => {}
+	}
+}
+
+/// Handles the key events on a menu screen.
+fn handle_menu_key_events(key_event: KeyEvent, app: &mut App) {
+	match key_event.code {
+		KeyCode::Up | KeyCode::Char('k') => app.select_menu_item(-1),
+		KeyCode::Down | KeyCode::Char('j') => app.select_menu_item(1),
+		KeyCode::Enter | KeyCode::Char(' ') => app.activate_menu_item(),
+		KeyCode::Esc | KeyCode::Char('q') => app.back(),
+		_ => {}
+	}
+}
+
+/// Handles the key events on the results screen, where a single key retries unless it is bound to
+/// an action of the game.
+fn handle_results_key_events(key_event: KeyEvent, action: Option<Action>, app: &mut App) {
+	match (key_event.code, action) {
+		(KeyCode::Char('r'), None) => app.restart(),
+		(KeyCode::Char('R'), None) => app.restart_same_seed(),
+		_ => handle_menu_key_events(key_event, app),
+	}
+}
+
+/// Handles the key events while a replay is watched.
+fn handle_viewer_key_events(key_event: KeyEvent, action: Option<Action>, app: &mut App) {
+	match (key_event.code, action) {
+		(KeyCode::Char(' '), _) | (_, Some(Action::Pause)) => app.toggle_playback(),
+		(KeyCode::Up, _) => app.change_speed(1),
+		(KeyCode::Down, _) => app.change_speed(-1),
+		(KeyCode::Char('.'), _) => app.step_frame(),
+		(KeyCode::Char('['), _) => app.seek_pieces(-1),
+		(KeyCode::Char(']'), _) => app.seek_pieces(1),
+		(KeyCode::Char('{'), _) => app.seek_pieces(-10),
+		(KeyCode::Char('}'), _) => app.seek_pieces(10),
+		(KeyCode::Home, _) => app.seek_piece(0),
+		(KeyCode::Esc | KeyCode::Char('q'), _) | (_, Some(Action::Quit)) => app.back(),
+		_ => {}
+	}
+}
+
+/// Handles the key events while the game is paused, where the pause key resumes it.
+fn handle_paused_key_events(key_event: KeyEvent, action: Option<Action>, app: &mut App) {
+	match action {
+		Some(Action::Pause) => app.resume(),
+		_ => handle_menu_key_events(key_event, app),
+	}
+}
+
+/// Handles the key events while a name is typed for the leaderboard.
+fn handle_name_entry_key_events(key_event: KeyEvent, app: &mut App) {
+	match key_event.code {
+		KeyCode::Enter => app.submit_name(),
+		KeyCode::Esc => app.skip_name(),
+		KeyCode::Backspace => app.pop_name_char(),
+		KeyCode::Char(character) if !key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+			app.push_name_char(character);
 		}
-		KeyCode::Char('q') => {
-			if app.current_rotation > 0 {
-				app.current_rotation -= 1;
-			} else {
-				app.current_rotation = 3;
-			}
-			app.clear_falling();
-			app.move_tetromino(0, 0, app.current_tetromino);
+		_ => {}
+	}
+}
+
+/// Performs the action a key is bound to, recording the ones that change the game.
+fn perform_action(action: Action, app: &mut App) {
+	match action {
+		Action::Pause => app.toggle_pause(),
+		Action::Stats => app.toggle_stats(),
+		Action::Leaderboard => app.toggle_leaderboard(),
+		Action::Quit => app.back(),
+		action => app.input(ReplayInput::Press(action)),
+	}
+}
+
+/// Handles the key events and updates the state of [`App`].
+pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
+	let control_c = matches!(key_event.code, KeyCode::Char('c' | 'C'))
+		&& key_event.modifiers == KeyModifiers::CONTROL;
+	let action = if key_event
+		.modifiers
+		.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+	{
+		None
+	} else {
+		app.config.controls.action(key_event.code)
+	};
+
+	// Releases only matter to held shift keys.
+	if key_event.kind == KeyEventKind::Release {
+		if let (Screen::Playing, Some(action @ (Action::Left | Action::Right))) = (app.screen, action) {
+			app.input(ReplayInput::Release(action));
 		}
-		KeyCode::Char('e') => {
-			if app.current_rotation < 3 {
-				app.current_rotation += 1;
-			} else {
-				app.current_rotation = 0;
-			}
-			app.clear_falling();
-			app.move_tetromino(0, 0, app.current_tetromino);
+		return Ok(());
+	}
+
+	// Exit application on `Ctrl-C`
+	if control_c {
+		app.quit();
+		return Ok(());
+	}
+
+	// A key held through the end of a game must not act on the screen that follows it.
+	if key_event.kind == KeyEventKind::Repeat && app.screen != Screen::Playing {
+		return Ok(());
+	}
+
+	match app.screen {
+		Screen::Settings => handle_settings_key_events(key_event, app),
+		Screen::Paused => handle_paused_key_events(key_event, action, app),
+		Screen::Playing => handle_game_key_events(key_event, action, app),
+		Screen::Results => handle_results_key_events(key_event, action, app),
+		Screen::Watching => handle_viewer_key_events(key_event, action, app),
+		_ => handle_menu_key_events(key_event, app),
+	}
+	Ok(())
+}
+
+/// Handles the key events while a game is played.
+fn handle_game_key_events(key_event: KeyEvent, action: Option<Action>, app: &mut App) {
+	// Undo and redo
+	if app.keeps_history()
+		&& key_event.modifiers == KeyModifiers::CONTROL
+		&& matches!(key_event.code, KeyCode::Char('z' | 'y'))
+	{
+		app.input(if key_event.code == KeyCode::Char('z') {
+			ReplayInput::Undo
+		} else {
+			ReplayInput::Redo
+		});
+		return;
+	}
+
+	// Screenshot of the board
+	if key_event.modifiers == KeyModifiers::CONTROL && key_event.code == KeyCode::Char('p') {
+		app.save_screenshot();
+		return;
+	}
+
+	// Save, load and export the sandbox setup
+	if app.editable()
+		&& key_event.modifiers == KeyModifiers::CONTROL
+		&& matches!(key_event.code, KeyCode::Char('s' | 'l' | 'e'))
+	{
+		match key_event.code {
+			KeyCode::Char('s') => app.save_setup(),
+			KeyCode::Char('l') => app.load_setup(),
+			_ => app.export_fumen(),
 		}
+		return;
+	}
+
+	if app.editing {
+		handle_editor_key_events(key_event, app);
+		return;
+	}
+
+	if app.name_entry.is_some() {
+		handle_name_entry_key_events(key_event, app);
+		return;
+	}
+
+	match (key_event.code, action) {
+		(KeyCode::Tab, _) => app.toggle_editing(),
+		// Perfect clear trainer boards
+		(KeyCode::Char('n'), _) if app.pc.is_some() => app.next_pc_board(),
+		(KeyCode::Char('r'), _) if app.pc.is_some() => app.retry_pc_board(),
+		// Only soft drop repeats while held, shift keys repeat on their own once DAS has charged.
+		(_, Some(action))
+			if key_event.kind == KeyEventKind::Repeat && action != Action::SoftDrop => {}
+		(_, Some(action)) => perform_action(action, app),
 		// Other handlers you could add here.
 		_ => {}
 	}
-	Ok(())
 }
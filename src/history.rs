@@ -0,0 +1,135 @@
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+	app::{
+		App,
+		Phase,
+		PlayFieldCell,
+	},
+	finesse::FinesseStats,
+	master::MasterState,
+	mode::GameMode,
+	puzzle::PuzzleState,
+	stats::GameStats,
+	tetromino::Tetromino,
+};
+
+//----------[ Structs ]----------//
+/// A copy of the game state that undo and redo return to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+	/// The playfield without the falling tetromino.
+	pub playfield: Vec<Vec<PlayFieldCell>>,
+	pub current_tetromino: Tetromino,
+	pub swap_tetromino: Tetromino,
+	pub tetromino_queue: Vec<Tetromino>,
+	pub score: u32,
+	pub level: u32,
+	pub default_tick_count_target: u32,
+	/// State of the random number generator, so that the same tetrominos follow.
+	pub rng: ChaCha8Rng,
+	pub master: MasterState,
+	pub puzzle: Option<PuzzleState>,
+	pub stats: GameStats,
+	pub finesse: FinesseStats,
+	pub elapsed_ticks: u32,
+}
+
+/// Snapshots to undo and redo, the most recent one last.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+	pub undo: Vec<Snapshot>,
+	pub redo: Vec<Snapshot>,
+}
+//-------------------------------//
+
+impl App {
+	/// Does the game keep a history of placements and edits?
+	pub fn keeps_history(&self) -> bool {
+		self.practice || self.editable() || self.mode == GameMode::Opener
+	}
+
+	/// Returns a snapshot of the current state.
+	pub fn snapshot(&self) -> Snapshot {
+		let mut playfield = self.playfield.clone();
+		playfield.iter_mut().flatten().for_each(|cell| cell.falling = false);
+		Snapshot {
+			playfield,
+			current_tetromino: self.current_tetromino,
+			swap_tetromino: self.swap_tetromino,
+			tetromino_queue: self.tetromino_queue.clone(),
+			score: self.score,
+			level: self.level,
+			default_tick_count_target: self.default_tick_count_target,
+			rng: self.rng.clone(),
+			master: self.master.clone(),
+			puzzle: self.puzzle.clone(),
+			stats: self.stats,
+			finesse: self.finesse.clone(),
+			elapsed_ticks: self.elapsed_ticks,
+		}
+	}
+
+	/// Returns to a snapshot, respawning its tetromino at the start position. Keys pressed and time
+	/// spent before the snapshot still count.
+	pub fn restore(&mut self, snapshot: Snapshot) {
+		self.playfield = snapshot.playfield;
+		self.swap_tetromino = snapshot.swap_tetromino;
+		self.tetromino_queue = snapshot.tetromino_queue;
+		self.score = snapshot.score;
+		self.level = snapshot.level;
+		self.default_tick_count_target = snapshot.default_tick_count_target;
+		self.rng = snapshot.rng;
+		self.master = snapshot.master;
+		self.puzzle = snapshot.puzzle;
+		self.stats = snapshot.stats;
+		self.finesse = snapshot.finesse;
+		// The respawned tetromino has not been moved yet.
+		self.finesse.inputs.clear();
+		self.elapsed_ticks = snapshot.elapsed_ticks;
+		self.game_over = false;
+		self.phase = Phase::Falling;
+		self.lock_timer = 0;
+		self.gravity_accumulator = 0;
+		self.grace_period = false;
+		self.x = self.start_x;
+		self.y = self.start_y;
+		self.current_rotation = 0;
+		self.current_tetromino = self.spawn_tetromino(self.x, self.y, snapshot.current_tetromino);
+	}
+
+	/// Records the current state so it can be returned to, dropping everything that was undone.
+	pub fn save_snapshot(&mut self) {
+		if self.keeps_history() {
+			let snapshot = self.snapshot();
+			self.history.undo.push(snapshot);
+			self.history.redo.clear();
+		}
+	}
+
+	/// Returns to the state before the last placement or edit.
+	pub fn undo(&mut self) -> bool {
+		match self.history.undo.pop() {
+			Some(snapshot) => {
+				let current = self.snapshot();
+				self.history.redo.push(current);
+				self.restore(snapshot);
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Returns to the state before the last undo.
+	pub fn redo(&mut self) -> bool {
+		match self.history.redo.pop() {
+			Some(snapshot) => {
+				let current = self.snapshot();
+				self.history.undo.push(current);
+				self.restore(snapshot);
+				true
+			}
+			None => false,
+		}
+	}
+}
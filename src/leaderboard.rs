@@ -0,0 +1,398 @@
+//! The leaderboard file has one entry per line, with tab separated fields:
+//! `mode ruleset name date score ticks lines level seed`. Every mode and ruleset has its own
+//! board, keeping the best [`LEADERBOARD_SIZE`] entries.
+
+use std::{
+	cmp::Reverse,
+	fs,
+	io,
+};
+
+use chrono::{
+	Local,
+	NaiveDate,
+};
+
+use crate::{
+	app::{
+		format_ticks,
+		App,
+		TICK_RATE,
+	},
+	mode::GameMode,
+	storage,
+};
+
+/// Name of the leaderboard file in the data directory.
+const LEADERBOARD_FILE: &str = "leaderboard";
+/// Entries kept on every board.
+pub const LEADERBOARD_SIZE: usize = 10;
+/// Longest player name.
+pub const NAME_LENGTH: usize = 16;
+
+//----------[ Structs ]----------//
+/// A game on the leaderboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeaderboardEntry {
+	pub mode: GameMode,
+	/// Settings the game was played with, such as the start level.
+	pub ruleset: String,
+	pub name: String,
+	pub date: NaiveDate,
+	pub score: u32,
+	/// Length of the game in ticks.
+	pub ticks: u32,
+	pub lines: u32,
+	pub level: u32,
+	pub seed: u64,
+}
+
+/// The best games of every mode and ruleset.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Leaderboard {
+	pub entries: Vec<LeaderboardEntry>,
+}
+//-------------------------------//
+
+impl LeaderboardEntry {
+	/// Parses a line of the leaderboard file.
+	pub fn parse(line: &str) -> Option<Self> {
+		let mut fields = line.split('\t');
+		Some(Self {
+			mode: GameMode::from_name(fields.next()?)?,
+			ruleset: fields.next()?.to_string(),
+			name: fields.next()?.to_string(),
+			date: fields.next()?.parse().ok()?,
+			score: fields.next()?.parse().ok()?,
+			ticks: fields.next()?.parse().ok()?,
+			lines: fields.next()?.parse().ok()?,
+			level: fields.next()?.parse().ok()?,
+			seed: fields.next()?.parse().ok()?,
+		})
+	}
+
+	/// Formats the entry as a line of the leaderboard file.
+	pub fn to_line(&self) -> String {
+		format!(
+			"{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+			self.mode.name(),
+			self.ruleset,
+			self.name,
+			self.date,
+			self.score,
+			self.ticks,
+			self.lines,
+			self.level,
+			self.seed
+		)
+	}
+
+	/// Returns a key that sorts better entries first. Master games are ranked by level and then
+	/// time, every other mode by score.
+	fn rank_key(&self) -> (Reverse<u32>, u32) {
+		match self.mode {
+			GameMode::Master => (Reverse(self.level), self.ticks),
+			_ => (Reverse(self.score), self.ticks),
+		}
+	}
+
+	/// Returns the result shown on the board.
+	pub fn result_string(&self) -> String {
+		match self.mode {
+			GameMode::Master => format_ticks(self.ticks),
+			_ => self.score.to_string(),
+		}
+	}
+}
+
+impl Leaderboard {
+	/// Parses the leaderboard file, skipping malformed lines.
+	pub fn parse(contents: &str) -> Self {
+		Self {
+			entries: contents.lines().filter_map(LeaderboardEntry::parse).collect(),
+		}
+	}
+
+	/// Formats the leaderboard file.
+	pub fn to_file_string(&self) -> String {
+		self.entries
+			.iter()
+			.map(|entry| entry.to_line() + "\n")
+			.collect()
+	}
+
+	/// Returns the board of a mode and ruleset, best entry first.
+	pub fn board(&self, mode: GameMode, ruleset: &str) -> Vec<&LeaderboardEntry> {
+		let mut board: Vec<&LeaderboardEntry> = self
+			.entries
+			.iter()
+			.filter(|entry| entry.mode == mode && entry.ruleset == ruleset)
+			.collect();
+		board.sort_by_key(|entry| entry.rank_key());
+		board.truncate(LEADERBOARD_SIZE);
+		board
+	}
+
+	/// Would the entry make it onto its board? Games that scored nothing, or Master games that did
+	/// not leave level 0, never do.
+	pub fn qualifies(&self, entry: &LeaderboardEntry) -> bool {
+		let played = match entry.mode {
+			GameMode::Master => entry.level > 0,
+			_ => entry.score > 0,
+		};
+		if !played {
+			return false;
+		}
+		let board = self.board(entry.mode, &entry.ruleset);
+		board.len() < LEADERBOARD_SIZE
+			|| board
+				.last()
+				.is_some_and(|last| entry.rank_key() < last.rank_key())
+	}
+
+	/// Adds an entry, dropping whatever falls off its board, and returns its rank counting from 1.
+	pub fn insert(&mut self, entry: LeaderboardEntry) -> Option<usize> {
+		let (mode, ruleset) = (entry.mode, entry.ruleset.clone());
+		self.entries.push(entry.clone());
+		let kept: Vec<LeaderboardEntry> = self
+			.board(mode, &ruleset)
+			.into_iter()
+			.cloned()
+			.collect();
+		self.entries
+			.retain(|other| other.mode != mode || other.ruleset != ruleset);
+		let rank = kept.iter().position(|other| *other == entry).map(|index| index + 1);
+		self.entries.extend(kept);
+		rank
+	}
+
+	/// Returns the rulesets a mode has a board for, in order.
+	pub fn rulesets(&self, mode: GameMode) -> Vec<&str> {
+		let mut rulesets: Vec<&str> = self
+			.entries
+			.iter()
+			.filter(|entry| entry.mode == mode)
+			.map(|entry| entry.ruleset.as_str())
+			.collect();
+		rulesets.sort();
+		rulesets.dedup();
+		rulesets
+	}
+
+	/// Returns every board of a mode, one ruleset after the other.
+	pub fn mode_string(&self, mode: GameMode) -> String {
+		let rulesets = self.rulesets(mode);
+		if rulesets.is_empty() {
+			return String::from("No entries yet");
+		}
+		rulesets
+			.into_iter()
+			.map(|ruleset| format!("{}\n{}", ruleset, board_string(&self.board(mode, ruleset))))
+			.collect::<Vec<String>>()
+			.join("\n")
+	}
+
+	/// Returns the name of the newest entry, to suggest when entering a name.
+	pub fn last_name(&self) -> Option<&str> {
+		self.entries
+			.iter()
+			.max_by_key(|entry| entry.date)
+			.map(|entry| entry.name.as_str())
+	}
+
+	/// Loads the leaderboard, which is empty if there is no file yet.
+	pub fn load() -> io::Result<Self> {
+		Ok(storage::read(LEADERBOARD_FILE)?
+			.map(|contents| Self::parse(&contents))
+			.unwrap_or_default())
+	}
+
+	/// Saves the leaderboard.
+	pub fn save(&self) -> io::Result<()> {
+		storage::write(LEADERBOARD_FILE, &self.to_file_string())
+	}
+
+	/// Moves the single high score of older versions onto the Marathon board.
+	pub fn import_legacy_highscore(&mut self) -> io::Result<()> {
+		let Some(contents) = storage::read(storage::HIGHSCORE_FILE)? else {
+			return Ok(());
+		};
+		if let Some(score) = contents.trim().parse().ok().filter(|&score| score > 0) {
+			self.insert(LeaderboardEntry {
+				mode: GameMode::Marathon,
+				ruleset: String::from("start 1"),
+				name: String::from("Player"),
+				date: Local::now().date_naive(),
+				score,
+				ticks: 0,
+				lines: 0,
+				level: 1,
+				seed: 0,
+			});
+			self.save()?;
+		}
+		fs::remove_file(storage::path(storage::HIGHSCORE_FILE)?)
+	}
+}
+
+/// Formats a board with one entry per line.
+pub fn board_string(board: &[&LeaderboardEntry]) -> String {
+	board
+		.iter()
+		.enumerate()
+		.map(|(index, entry)| {
+			format!(
+				"{:>2} {:<8.8}{:>9}\n",
+				index + 1,
+				entry.name,
+				entry.result_string()
+			)
+		})
+		.collect()
+}
+
+/// Prints every board, one mode after the other.
+pub fn print_leaderboards() {
+	let leaderboard = match Leaderboard::load() {
+		Ok(leaderboard) => leaderboard,
+		Err(error) => {
+			eprintln!("Could not load the leaderboard: {}", error);
+			return;
+		}
+	};
+	if leaderboard.entries.is_empty() {
+		println!("No games on the leaderboard yet.");
+		return;
+	}
+	let boards = GameMode::ALL.into_iter().flat_map(|mode| {
+		leaderboard
+			.rulesets(mode)
+			.into_iter()
+			.map(move |ruleset| (mode, ruleset))
+	});
+	for (mode, ruleset) in boards {
+		println!("{} {}", mode.name(), ruleset);
+		for (index, entry) in leaderboard.board(mode, ruleset).iter().enumerate() {
+			println!(
+				"{:>4}. {:<18}{:>10}{:>8}{:>8}{:>12}  {}  seed {}",
+				index + 1,
+				entry.name,
+				entry.score,
+				entry.lines,
+				entry.level,
+				format_ticks(entry.ticks),
+				entry.date,
+				entry.seed
+			);
+		}
+		println!();
+	}
+}
+
+impl App {
+	//----------[ Leaderboard ]----------//
+	/// Returns the settings the game is ranked under on the leaderboard.
+	pub fn ruleset(&self) -> String {
+		match self.mode {
+			GameMode::Master => String::new(),
+			GameMode::Daily => self
+				.daily
+				.map_or(String::new(), |challenge| challenge.date.to_string()),
+			GameMode::Fading => format!(
+				"start {}, fade {}s",
+				self.start_level,
+				(self.fade_ticks as u64 * TICK_RATE + 500) / 1000
+			),
+			_ => format!("start {}", self.start_level),
+		}
+	}
+
+	/// Returns the leaderboard entry of the game.
+	pub fn leaderboard_entry(&self, name: &str) -> LeaderboardEntry {
+		LeaderboardEntry {
+			mode: self.mode,
+			ruleset: self.ruleset(),
+			name: name.to_string(),
+			date: Local::now().date_naive(),
+			score: self.score,
+			ticks: self.elapsed_ticks,
+			lines: self.stats.lines,
+			level: self.level,
+			seed: self.seed,
+		}
+	}
+
+	/// Asks for a name when the finished game makes it onto the leaderboard.
+	pub fn check_leaderboard(&mut self) {
+		if self.record_results
+			&& self.mode.has_leaderboard()
+			&& !self.daily.is_some_and(|challenge| challenge.practice)
+			&& self.leaderboard.qualifies(&self.leaderboard_entry(""))
+		{
+			self.name_entry = Some(self.leaderboard.last_name().unwrap_or_default().to_string());
+		}
+	}
+
+	/// Types a character of the name.
+	pub fn push_name_char(&mut self, character: char) {
+		if let Some(name) = &mut self.name_entry {
+			if name.chars().count() < NAME_LENGTH && !character.is_control() {
+				name.push(character);
+			}
+		}
+	}
+
+	/// Erases the last character of the name.
+	pub fn pop_name_char(&mut self) {
+		if let Some(name) = &mut self.name_entry {
+			name.pop();
+		}
+	}
+
+	/// Puts the game on the leaderboard under the entered name and shows the board.
+	pub fn submit_name(&mut self) {
+		let Some(name) = self.name_entry.take() else {
+			return;
+		};
+		let name = if name.trim().is_empty() {
+			String::from("Player")
+		} else {
+			name.trim().to_string()
+		};
+		self.leaderboard.insert(self.leaderboard_entry(&name));
+		if self.record_results {
+			let result = self.leaderboard.save();
+			self.report_save_error(result);
+		}
+		self.show_leaderboard = true;
+		self.show_results();
+	}
+
+	/// Leaves the game off the leaderboard.
+	pub fn skip_name(&mut self) {
+		self.name_entry = None;
+		self.show_results();
+	}
+
+	/// Shows or hides the board of the game.
+	pub fn toggle_leaderboard(&mut self) {
+		self.show_leaderboard = !self.show_leaderboard;
+	}
+
+	/// Returns the board of the game.
+	pub fn leaderboard_string(&self) -> String {
+		let ruleset = self.ruleset();
+		let board = self.leaderboard.board(self.mode, &ruleset);
+		format!(
+			"{} {}\n{}",
+			self.mode.name(),
+			ruleset,
+			if board.is_empty() {
+				String::from("No entries yet")
+			} else {
+				board_string(&board)
+			}
+		)
+	}
+	//-----------------------------------//
+}
@@ -16,6 +16,69 @@ pub mod handler;
 /// Tetromino definitions.
 pub mod tetromino;
 
+/// Game modes.
+pub mod mode;
+
+/// Master mode speed tables and grading.
+pub mod master;
+
+/// Daily challenge seeds and results.
+pub mod daily;
+
+/// Puzzle files, packs and progress.
+pub mod puzzle;
+
+/// Undo and redo.
+pub mod history;
+
+/// Sandbox board editor.
+pub mod editor;
+
+/// Opener definitions and the opener trainer.
+pub mod opener;
+
+/// Perfect clear solver and trainer.
+pub mod pc;
+
+/// Finesse tracking and the finesse trainer.
+pub mod finesse;
+
+/// Live game statistics.
+pub mod stats;
+
+/// Records of finished games and their summary.
+pub mod records;
+
+/// Leaderboards of every mode.
+pub mod leaderboard;
+
+/// Files kept between games.
+pub mod storage;
+
+/// Options and the config file.
+pub mod config;
+
+/// Settings menu.
+pub mod settings;
+
+/// Screens of the application and their menus.
+pub mod screen;
+
+/// Recording of games and the replay file format.
+pub mod replay;
+
+/// Playback of replays.
+pub mod viewer;
+
+/// Export of replays as asciinema casts.
+pub mod cast;
+
+/// Pictures of the board as PNG and GIF images.
+pub mod picture;
+
+/// Fumen import and export.
+pub mod fumen;
+
 /// Tests.
 pub mod tests;
 
@@ -1,7 +1,6 @@
-use crossterm::style::Color;
+use clap::parser::ValueSource;
 use ratatui::{
 	backend::CrosstermBackend,
-	widgets::BorderType,
 	Terminal,
 };
 use std::io;
@@ -9,8 +8,17 @@ use tetrs::{
 	app::{
 		App,
 		AppResult,
+		TICK_RATE,
+	},
+	clap::{
+		apply_args,
+		clap_parse,
+	},
+	config::{
+		self,
+		Config,
+		DEFAULT_CONFIG,
 	},
-	clap::clap_parse,
 	event::{
 		Event,
 		EventHandler,
@@ -19,59 +27,178 @@ use tetrs::{
 		handle_key_events,
 		handle_mouse_events,
 	},
+	cast,
+	daily,
+	fumen,
+	leaderboard::{
+		self,
+		Leaderboard,
+	},
+	picture,
+	puzzle,
+	records,
+	replay,
+	screen::Screen,
+	storage,
 	tui::Tui,
 };
 
 #[tokio::main]
 async fn main() -> AppResult<()> {
-	let mut app = App::new();
-
 	//----------[ Command line arguments ]----------//
 	let binding = clap_parse();
 
 	let version: bool = *binding.get_one("version").unwrap();
 
-	let color: i64 = *binding.get_one("color").unwrap();
+	if version {
+		println!("tetrs v{}", env!("CARGO_PKG_VERSION"));
+		std::process::exit(0);
+	}
+	if let Some(("config", matches)) = binding.subcommand() {
+		if matches.get_flag("print-default") {
+			print!("{}", DEFAULT_CONFIG);
+		} else {
+			config::print_path();
+		}
+		std::process::exit(0);
+	}
+	if let Some(("verify", matches)) = binding.subcommand() {
+		let path = matches.get_one::<std::path::PathBuf>("file").unwrap();
+		std::process::exit(if replay::print_verification(path) { 0 } else { 1 });
+	}
 
-	let binding = clap_parse();
+	// Move the high score of older versions over before anything reads it.
+	if let Err(error) = storage::migrate() {
+		eprintln!("Could not move the old high score to the data directory: {}", error);
+	}
 
-	// Assuming clap_parse() parses the user input correctly
-	let border_type_str: String = binding
-		.get_one::<String>("BorderCorners")
-		.unwrap()
-		.to_string();
-	let border_type: BorderType = match border_type_str.as_str() {
-		"Plain" => BorderType::Plain,
-		"Rounded" => BorderType::Rounded,
-		_ => unreachable!(),
+	match binding.subcommand() {
+		Some(("daily", _)) => {
+			daily::print_history();
+			std::process::exit(0);
+		}
+		Some(("puzzles", _)) => {
+			puzzle::print_pack();
+			std::process::exit(0);
+		}
+		Some(("stats", _)) => {
+			records::print_stats();
+			std::process::exit(0);
+		}
+		Some(("leaderboard", _)) => {
+			leaderboard::print_leaderboards();
+			std::process::exit(0);
+		}
+		Some(("export", matches)) if matches.get_flag("fumen") => {
+			let path = matches.get_one::<std::path::PathBuf>("file").unwrap();
+			match fumen::export(path) {
+				Ok(data) => println!("{}", data),
+				Err(error) => {
+					eprintln!("Could not export the replay {}", error);
+					std::process::exit(1);
+				}
+			}
+			std::process::exit(0);
+		}
+		_ => {}
+	}
+
+	// Only what plays or draws a game reads the config.
+	let mut config = match Config::load() {
+		Ok(config) => config,
+		Err(error) => {
+			eprintln!("Invalid config file {}", error);
+			std::process::exit(1);
+		}
 	};
+	let file_config = config.clone();
+	apply_args(&binding, &mut config);
 
-	let level: i64 = *binding.get_one("startlevel").unwrap();
+	let puzzle_path = binding.get_one::<std::path::PathBuf>("puzzle");
 
-	if version {
-		println!("tetrs v{}", env!("CARGO_PKG_VERSION"));
+	let mode = config.game.mode;
+
+	if let Some(("export", matches)) = binding.subcommand() {
+		let path = matches.get_one::<std::path::PathBuf>("file").unwrap();
+		let output = matches.get_one::<std::path::PathBuf>("output");
+		let output = output.map(|output| output.as_path());
+		let result = if matches.get_flag("png") {
+			let pieces = matches.get_one::<u32>("piece").copied();
+			picture::export_png(path, output, pieces, &config)
+		} else if matches.get_flag("gif") {
+			picture::export_gif(path, output, &config)
+		} else {
+			cast::export(path, output, &config)
+		};
+		match result {
+			Ok(output) => println!("Exported to {}", output.display()),
+			Err(error) => {
+				eprintln!("Could not export the replay {}", error);
+				std::process::exit(1);
+			}
+		}
 		std::process::exit(0);
 	}
-	app.level = level.try_into().unwrap();
+
+	let mut app = App::new();
+	match Leaderboard::load() {
+		Ok(mut leaderboard) => {
+			if let Err(error) = leaderboard.import_legacy_highscore() {
+				eprintln!("Could not move the old high score onto the leaderboard: {}", error);
+			}
+			app.leaderboard = leaderboard;
+		}
+		Err(error) => {
+			app.status_message = Some(format!("Could not load the leaderboard: {}", error));
+		}
+	}
+	app.config = config;
+	app.file_config = file_config;
+	// Asking for a game on the command line skips the title screen.
+	let game_asked = ["mode", "puzzle", "opener", "fumen"]
+		.into_iter()
+		.any(|id| binding.value_source(id) == Some(ValueSource::CommandLine));
+	let puzzle = match binding.get_one::<puzzle::Puzzle>("fumen") {
+		Some(setup) => Some(setup.clone()),
+		None => puzzle_path.map(|path| puzzle::load(path)).transpose()?,
+	};
+	let watched_replay = match binding.subcommand() {
+		Some(("replay", matches)) => {
+			let path = matches.get_one::<std::path::PathBuf>("file").unwrap();
+			match replay::load(path) {
+				Ok(replay) => Some(replay),
+				Err(error) => {
+					eprintln!("Invalid replay {}", error);
+					std::process::exit(1);
+				}
+			}
+		}
+		_ => None,
+	};
 	//----------------------------------------------//
 
 	//----------[ Init UI ]----------//
 	let backend = CrosstermBackend::new(io::stderr());
 	let terminal = Terminal::new(backend)?;
-	let events = EventHandler::new(30);
+	let events = EventHandler::new(TICK_RATE);
 	let mut tui = Tui::new(terminal, events);
 	tui.init()?;
+	app.key_releases = tui.key_releases;
+	// The game starts once the terminal is known, which its replay records.
+	if let Some(replay) = watched_replay {
+		app.watch_replay(replay);
+	} else if game_asked {
+		app.start_game(mode, puzzle, None);
+	} else {
+		app.show_screen(Screen::Title);
+	}
 	//-------------------------------//
 
 	//----------[ Event Loop ]----------//
 	while app.running {
 		//----------[ Rendering ]----------//
 		{
-			tui.draw(
-				&mut app,
-				Color::AnsiValue(color.try_into().unwrap()),
-				border_type,
-			)?;
+			tui.draw(&mut app)?;
 		}
 		//---------------------------------//
 
@@ -82,6 +209,7 @@ async fn main() -> AppResult<()> {
 				Event::Key(key_event) => handle_key_events(key_event, &mut app)?,
 				Event::Mouse(mouse_event) => handle_mouse_events(mouse_event, &mut app),
 				Event::Resize(_, _) => {}
+				Event::FocusLost => app.pause(),
 			}
 		}
 		//--------------------------------------//
@@ -91,6 +219,9 @@ async fn main() -> AppResult<()> {
 	//----------[ Cleanup ]----------//
 	{
 		tui.exit()?;
+		if let Some(data) = app.fumen_export {
+			println!("{}", data);
+		}
 		Ok(())
 	}
 	//-------------------------------//
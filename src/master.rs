@@ -0,0 +1,233 @@
+use std::fmt;
+
+use crate::app::TICK_RATE;
+
+//----------[ Tables ]----------//
+/// Highest reachable level, the game ends once it is reached.
+pub const MAX_LEVEL: u32 = 999;
+
+/// Gravity in 1/256 rows per 60 Hz frame, starting at the given level.
+const GRAVITY_TABLE: [(u32, u32); 30] = [
+	(0, 4),
+	(30, 6),
+	(35, 8),
+	(40, 10),
+	(50, 12),
+	(60, 16),
+	(70, 32),
+	(80, 48),
+	(90, 64),
+	(100, 80),
+	(120, 96),
+	(140, 112),
+	(160, 128),
+	(170, 144),
+	(200, 4),
+	(220, 32),
+	(230, 64),
+	(233, 96),
+	(236, 128),
+	(239, 160),
+	(243, 192),
+	(247, 224),
+	(251, 256),
+	(300, 512),
+	(330, 768),
+	(360, 1024),
+	(400, 1280),
+	(420, 1024),
+	(450, 768),
+	(500, 5120),
+];
+
+/// ARE, lock delay and line clear delay in 60 Hz frames, starting at the given level.
+const TIMING_TABLE: [(u32, Timings); 6] = [
+	(0, Timings { are: 25, lock_delay: 30, line_clear: 40 }),
+	(500, Timings { are: 25, lock_delay: 30, line_clear: 25 }),
+	(600, Timings { are: 16, lock_delay: 30, line_clear: 16 }),
+	(700, Timings { are: 12, lock_delay: 30, line_clear: 12 }),
+	(800, Timings { are: 12, lock_delay: 30, line_clear: 6 }),
+	(900, Timings { are: 12, lock_delay: 17, line_clear: 6 }),
+];
+
+/// Grade names and the score needed to reach them.
+const GRADE_TABLE: [(&str, u32); 18] = [
+	("9", 0),
+	("8", 400),
+	("7", 800),
+	("6", 1400),
+	("5", 2000),
+	("4", 3500),
+	("3", 5500),
+	("2", 8000),
+	("1", 12000),
+	("S1", 16000),
+	("S2", 22000),
+	("S3", 30000),
+	("S4", 40000),
+	("S5", 52000),
+	("S6", 66000),
+	("S7", 82000),
+	("S8", 100000),
+	("S9", 120000),
+];
+
+/// Level, minimum score and maximum time in seconds needed to stay in the running for GM.
+const GM_CHECKPOINTS: [(u32, u32, u32); 3] = [(300, 12000, 255), (500, 40000, 450), (999, 126000, 810)];
+//------------------------------//
+
+//----------[ Structs ]----------//
+/// Delays applied around a piece lock, in ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timings {
+	/// Delay between a piece locking and the next one spawning.
+	pub are: u32,
+	/// Time a grounded piece may stay active before it locks.
+	pub lock_delay: u32,
+	/// Time cleared lines stay on the board before collapsing.
+	pub line_clear: u32,
+}
+
+/// A TGM grade, from 9 up to S9 and finally GM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Grade(usize);
+
+impl Grade {
+	/// The Grand Master grade, only awarded by finishing the game under the GM conditions.
+	pub const GM: Grade = Grade(GRADE_TABLE.len());
+
+	/// Returns the highest grade the score qualifies for, never GM.
+	pub fn for_score(score: u32) -> Grade {
+		Grade(GRADE_TABLE.iter().rposition(|&(_, required)| score >= required).unwrap_or(0))
+	}
+
+	/// Returns the score needed for the next grade, if there is one below GM.
+	pub fn next_requirement(&self) -> Option<u32> {
+		GRADE_TABLE.get(self.0 + 1).map(|&(_, required)| required)
+	}
+}
+
+impl fmt::Display for Grade {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match GRADE_TABLE.get(self.0) {
+			Some((name, _)) => write!(f, "{}", name),
+			None => write!(f, "GM"),
+		}
+	}
+}
+
+/// Progress of a Master game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MasterState {
+	/// Ticks spent in each completed section of 100 levels.
+	pub section_times: Vec<u32>,
+	/// Combo multiplier for the next line clear.
+	pub combo: u32,
+	/// Rows soft dropped by the current piece.
+	pub soft_drop: u32,
+	/// Current grade.
+	pub grade: Grade,
+	/// Whether every GM checkpoint so far has been met.
+	pub gm_eligible: bool,
+	/// Whether level 999 has been reached.
+	pub completed: bool,
+}
+
+impl Default for MasterState {
+	fn default() -> Self {
+		Self {
+			section_times: vec![],
+			combo: 1,
+			soft_drop: 0,
+			grade: Grade::default(),
+			gm_eligible: true,
+			completed: false,
+		}
+	}
+}
+//-------------------------------//
+
+//----------[ Speed ]----------//
+/// Converts a duration in 60 Hz frames to engine ticks, rounding up.
+pub fn frames_to_ticks(frames: u32) -> u32 {
+	(frames * 1000).div_ceil(60 * TICK_RATE as u32)
+}
+
+/// Returns the gravity at a level in 1/256 rows per tick.
+pub fn gravity(level: u32) -> u32 {
+	let per_frame = GRAVITY_TABLE
+		.iter()
+		.rev()
+		.find(|&&(start, _)| level >= start)
+		.map_or(GRAVITY_TABLE[0].1, |&(_, gravity)| gravity);
+	per_frame * 60 * TICK_RATE as u32 / 1000
+}
+
+/// Returns the delays used at a level, in ticks.
+pub fn timings(level: u32) -> Timings {
+	let frames = TIMING_TABLE
+		.iter()
+		.rev()
+		.find(|&&(start, _)| level >= start)
+		.map_or(TIMING_TABLE[0].1, |&(_, timings)| timings);
+	Timings {
+		are: frames_to_ticks(frames.are),
+		lock_delay: frames_to_ticks(frames.lock_delay),
+		line_clear: frames_to_ticks(frames.line_clear),
+	}
+}
+//-----------------------------//
+
+//----------[ Progress ]----------//
+impl MasterState {
+	/// Returns the level after a piece spawns, which stops at the end of each section.
+	pub fn level_after_spawn(level: u32) -> u32 {
+		if level % 100 == 99 || level >= MAX_LEVEL - 1 {
+			level
+		} else {
+			level + 1
+		}
+	}
+
+	/// Scores a line clear with the TGM formula and updates the combo.
+	pub fn score_line_clear(&mut self, level: u32, lines: u32, bravo: bool) -> u32 {
+		if lines == 0 {
+			self.combo = 1;
+			self.soft_drop = 0;
+			return 0;
+		}
+		self.combo += 2 * lines - 2;
+		let score = ((level + lines).div_ceil(4) + self.soft_drop)
+			* lines * self.combo
+			* if bravo { 4 } else { 1 };
+		self.soft_drop = 0;
+		score
+	}
+
+	/// Records section times, GM checkpoints and the grade after the level changed.
+	pub fn update(&mut self, old_level: u32, new_level: u32, score: u32, elapsed_ticks: u32) {
+		if new_level / 100 > old_level / 100 || (new_level >= MAX_LEVEL && old_level < MAX_LEVEL) {
+			let section_start: u32 = self.section_times.iter().sum();
+			self.section_times.push(elapsed_ticks - section_start);
+		}
+
+		let seconds = elapsed_ticks * TICK_RATE as u32 / 1000;
+		for (level, required_score, max_seconds) in GM_CHECKPOINTS {
+			if old_level < level
+				&& new_level >= level
+				&& (score < required_score || seconds > max_seconds)
+			{
+				self.gm_eligible = false;
+			}
+		}
+
+		self.grade = self.grade.max(Grade::for_score(score));
+		if new_level >= MAX_LEVEL {
+			self.completed = true;
+			if self.gm_eligible {
+				self.grade = Grade::GM;
+			}
+		}
+	}
+}
+//--------------------------------//
@@ -0,0 +1,107 @@
+//----------[ Game Modes ]----------//
+/// The rule set a game is played with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameMode {
+	/// Endless play with the classic score based level curve.
+	#[default]
+	Marathon,
+	/// TGM style 20G mode with section levels 0-999 and grading.
+	Master,
+	/// Marathon where landed blocks turn invisible as soon as they lock.
+	Invisible,
+	/// Marathon where landed blocks fade out a few seconds after locking.
+	Fading,
+	/// Marathon on a half size board with every block drawn at double size.
+	Big,
+	/// Marathon with a piece sequence derived from the local date and one scored attempt a day.
+	Daily,
+	/// Solve puzzles loaded from level files.
+	Puzzle,
+	/// Freeplay without gravity, with a board editor and undo.
+	Sandbox,
+	/// Build the first bag of an opener on outlined placements.
+	Opener,
+	/// Perfect clear generated boards with the tetrominos of the queue.
+	PerfectClear,
+	/// Place tetrominos on outlined targets with the fewest inputs.
+	Finesse,
+}
+
+impl GameMode {
+	/// Every selectable mode, in menu order.
+	pub const ALL: [GameMode; 11] = [
+		GameMode::Marathon,
+		GameMode::Master,
+		GameMode::Invisible,
+		GameMode::Fading,
+		GameMode::Big,
+		GameMode::Daily,
+		GameMode::Puzzle,
+		GameMode::Sandbox,
+		GameMode::Opener,
+		GameMode::PerfectClear,
+		GameMode::Finesse,
+	];
+
+	/// Returns the name used on the command line and in saved files.
+	pub fn name(&self) -> &'static str {
+		match self {
+			GameMode::Marathon => "Marathon",
+			GameMode::Master => "Master",
+			GameMode::Invisible => "Invisible",
+			GameMode::Fading => "Fading",
+			GameMode::Big => "Big",
+			GameMode::Daily => "Daily",
+			GameMode::Puzzle => "Puzzle",
+			GameMode::Sandbox => "Sandbox",
+			GameMode::Opener => "Opener",
+			GameMode::PerfectClear => "PerfectClear",
+			GameMode::Finesse => "Finesse",
+		}
+	}
+
+	/// Returns what the mode is about, shown when it is selected.
+	pub fn description(&self) -> &'static str {
+		match self {
+			GameMode::Marathon => "Endless play with the classic score based level curve",
+			GameMode::Master => "20G with section levels 0-999 and grading",
+			GameMode::Invisible => "Landed blocks turn invisible as soon as they lock",
+			GameMode::Fading => "Landed blocks fade out a few seconds after locking",
+			GameMode::Big => "A half size board with every block drawn at double size",
+			GameMode::Daily => "The same pieces for everyone today, one scored attempt",
+			GameMode::Puzzle => "Solve the puzzles of the pack",
+			GameMode::Sandbox => "Freeplay without gravity, with a board editor and undo",
+			GameMode::Opener => "Build the first bag of an opener on outlined placements",
+			GameMode::PerfectClear => "Perfect clear generated boards",
+			GameMode::Finesse => "Place tetrominos on outlined targets with the fewest inputs",
+		}
+	}
+
+	/// Are finished games of this mode kept in the game records? Trainers and the Sandbox have no
+	/// real end.
+	pub fn keeps_records(&self) -> bool {
+		!matches!(
+			self,
+			GameMode::Sandbox | GameMode::Opener | GameMode::PerfectClear | GameMode::Finesse
+		)
+	}
+
+	/// Are the best games of this mode ranked on the leaderboard?
+	pub fn has_leaderboard(&self) -> bool {
+		self.keeps_records() && *self != GameMode::Puzzle
+	}
+
+	/// Can games of this mode be saved as replays? Puzzles start from a board that replays leave
+	/// out.
+	pub fn has_replays(&self) -> bool {
+		self.keeps_records() && *self != GameMode::Puzzle
+	}
+
+	/// Looks up a mode by its name, ignoring case.
+	pub fn from_name(name: &str) -> Option<GameMode> {
+		GameMode::ALL
+			.into_iter()
+			.find(|mode| mode.name().eq_ignore_ascii_case(name))
+	}
+}
+//----------------------------------//
@@ -0,0 +1,258 @@
+//! Openers are drawn like puzzle boards, with the placement of every tetromino of the first bag
+//! written with its letter. The placements hold for every order of the bag: a placement becomes
+//! the target once the placements under it are done, and a tetromino without one is kept in the
+//! hold.
+
+use crate::{
+	app::{
+		App,
+		PLAYFIELD_PADDING,
+	},
+	tetromino::{
+		Tetromino,
+		TetrominoKind,
+	},
+};
+
+/// Openers the trainer can be started with.
+pub const OPENERS: [Opener; 3] = [
+	Opener {
+		name: "TKI",
+		description: "T first, then a T-spin double",
+		board: &[
+			"......ZJJJ",
+			"LLL..ZZSSJ",
+			"LT...ZSSOO",
+			"TTT.IIIIOO",
+		],
+	},
+	Opener {
+		name: "PCO",
+		description: "Hold the T for a perfect clear",
+		board: &[
+			"..S.....ZI",
+			"..SS...ZZI",
+			"OOJS...ZLI",
+			"OOJJJ.LLLI",
+		],
+	},
+	Opener {
+		name: "DT",
+		description: "Hold the T for a DT cannon",
+		board: &[
+			".Z........",
+			"ZZSS..JOO.",
+			"ZSS...JOOL",
+			"IIII.JJLLL",
+		],
+	},
+];
+
+//----------[ Structs ]----------//
+/// The placements of the first bag of an opener.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Opener {
+	pub name: &'static str,
+	pub description: &'static str,
+	/// Rows of the board from top to bottom.
+	pub board: &'static [&'static str],
+}
+
+/// An opener being trained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenerState {
+	pub opener: &'static Opener,
+	/// Is a wrong placement undone right away?
+	pub auto_undo: bool,
+	/// Wrong placements so far.
+	pub mistakes: u32,
+	/// Was the last placement wrong?
+	pub last_wrong: bool,
+}
+//-------------------------------//
+
+impl OpenerState {
+	/// Starts training an opener.
+	pub fn new(opener: &'static Opener, auto_undo: bool) -> Self {
+		Self {
+			opener,
+			auto_undo,
+			mistakes: 0,
+			last_wrong: false,
+		}
+	}
+}
+
+impl Opener {
+	/// Looks up an opener by its name, ignoring case.
+	pub fn from_name(name: &str) -> Option<&'static Opener> {
+		OPENERS
+			.iter()
+			.find(|opener| opener.name.eq_ignore_ascii_case(name))
+	}
+
+	/// Returns the cells of the placement of a tetromino type as `(column, row)`, the bottom row
+	/// being 0.
+	pub fn placement(&self, kind: TetrominoKind) -> Option<Vec<(usize, usize)>> {
+		let cells: Vec<(usize, usize)> = self
+			.board
+			.iter()
+			.rev()
+			.enumerate()
+			.flat_map(|(row, line)| {
+				line.chars()
+					.enumerate()
+					.filter(|&(_, cell)| cell == kind.letter())
+					.map(move |(column, _)| (column, row))
+			})
+			.collect();
+		(!cells.is_empty()).then_some(cells)
+	}
+
+	/// Returns the tetromino types placed by the opener.
+	pub fn kinds(&self) -> Vec<TetrominoKind> {
+		TetrominoKind::ALL
+			.into_iter()
+			.filter(|&kind| self.placement(kind).is_some())
+			.collect()
+	}
+}
+
+/// Returns the rotation and playfield position a tetromino has to be at to cover the given
+/// playfield cells.
+pub fn find_position(
+	tetromino: &Tetromino,
+	cells: &[(usize, usize)],
+) -> Option<(usize, usize, usize)> {
+	let mut cells = cells.to_vec();
+	cells.sort();
+	(0..4).find_map(|rotation| {
+		let mut shape: Vec<(usize, usize)> = (0..4)
+			.flat_map(|y| (0..4).map(move |x| (x, y)))
+			.filter(|&(x, y)| tetromino.rotations[rotation][y][x])
+			.collect();
+		shape.sort();
+		let (first_x, first_y) = shape[0];
+		let x = cells[0].0.checked_sub(first_x)?;
+		let y = cells[0].1.checked_sub(first_y)?;
+		let moved: Vec<(usize, usize)> = shape.iter().map(|&(cx, cy)| (x + cx, y + cy)).collect();
+		(moved == cells).then_some((rotation, x, y))
+	})
+}
+
+impl App {
+	//----------[ Targets ]----------//
+	/// Returns the playfield cells of an opener placement.
+	fn opener_cells(&self, opener: &Opener, kind: TetrominoKind) -> Option<Vec<(usize, usize)>> {
+		opener.placement(kind).map(|cells| {
+			cells
+				.into_iter()
+				.map(|(column, row)| (PLAYFIELD_PADDING + column, self.playfield.len() - 1 - row))
+				.collect()
+		})
+	}
+
+	/// Returns the playfield cells the current tetromino should be placed on, or `None` when it
+	/// has to be held.
+	pub fn opener_target(&self) -> Option<Vec<(usize, usize)>> {
+		let state = self.opener.as_ref()?;
+		let kind = self.current_tetromino.kind()?;
+		let cells = self.opener_cells(state.opener, kind)?;
+		let free = cells.iter().all(|&(x, y)| !self.playfield[y][x].landed);
+		// Every cell above the placement has to be empty to drop the tetromino in.
+		let open = cells.iter().all(|&(x, y)| {
+			(0..y).all(|above| !self.playfield[above][x].landed || cells.contains(&(x, above)))
+		});
+		// Placements below this one have to be done first, or they could not be dropped in.
+		let pending: Vec<(usize, usize)> = state
+			.opener
+			.kinds()
+			.into_iter()
+			.filter(|&other| other != kind)
+			.filter_map(|other| self.opener_cells(state.opener, other))
+			.flatten()
+			.filter(|&(x, y)| !self.playfield[y][x].landed)
+			.collect();
+		let unblocking = pending
+			.iter()
+			.all(|&(pending_x, pending_y)| !cells.iter().any(|&(x, y)| x == pending_x && y < pending_y));
+		let supported = cells
+			.iter()
+			.any(|&(x, y)| y + 1 == self.playfield.len() || self.playfield[y + 1][x].landed);
+		(free && open && unblocking && supported).then_some(cells)
+	}
+
+	/// Returns how many tetrominos of the opener have been placed.
+	pub fn opener_progress(&self) -> usize {
+		self.opener.as_ref().map_or(0, |state| {
+			state
+				.opener
+				.kinds()
+				.into_iter()
+				.filter(|&kind| {
+					self.opener_cells(state.opener, kind)
+						.is_some_and(|cells| cells.iter().all(|&(x, y)| self.playfield[y][x].landed))
+				})
+				.count()
+		})
+	}
+
+	/// Has every tetromino of the opener been placed?
+	pub fn opener_complete(&self) -> bool {
+		self.opener
+			.is_some_and(|state| self.opener_progress() == state.opener.kinds().len())
+	}
+	//-------------------------------//
+
+	//----------[ Checks ]----------//
+	/// Checks a locked tetromino against the opener, ending the game once every placement is
+	/// done.
+	pub fn check_opener_placement(
+		&mut self,
+		kind: Option<TetrominoKind>,
+		cells: &[(usize, usize)],
+	) {
+		let Some(state) = self.opener else {
+			return;
+		};
+		let mut cells = cells.to_vec();
+		cells.sort();
+		let target = kind
+			.and_then(|kind| self.opener_cells(state.opener, kind))
+			.map(|mut target| {
+				target.sort();
+				target
+			});
+		let wrong = target.as_deref() != Some(cells.as_slice());
+
+		if let Some(state) = &mut self.opener {
+			state.last_wrong = wrong;
+			if wrong {
+				state.mistakes += 1;
+			}
+		}
+		self.status_message = match (wrong, state.auto_undo) {
+			(true, true) => Some(String::from("Wrong placement, undone")),
+			(true, false) => Some(String::from("Wrong placement, [^Z] undo")),
+			(false, _) => None,
+		};
+		if !wrong && self.opener_complete() {
+			self.end_game();
+		}
+	}
+
+	/// Undoes the last placement if it was wrong and the trainer undoes mistakes. The wrong
+	/// placement is dropped rather than kept for redo.
+	pub fn auto_undo_opener(&mut self) {
+		if !self
+			.opener
+			.is_some_and(|state| state.auto_undo && state.last_wrong)
+		{
+			return;
+		}
+		if let Some(snapshot) = self.history.undo.pop() {
+			self.restore(snapshot);
+		}
+	}
+	//------------------------------//
+}
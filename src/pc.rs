@@ -0,0 +1,379 @@
+use std::{
+	cmp::Reverse,
+	collections::HashSet,
+};
+
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+	app::{
+		App,
+		PlayFieldCell,
+		BOARD_WIDTH,
+		PLAYFIELD_PADDING,
+	},
+	puzzle::{
+		Goal,
+		Puzzle,
+	},
+	tetromino::{
+		Tetromino,
+		TetrominoKind,
+	},
+};
+
+/// Rows cleared by a perfect clear of the trainer.
+pub const PC_HEIGHT: usize = 4;
+/// Tetrominos in the queue of a trainer board.
+pub const PC_QUEUE: usize = 4;
+/// Random boards tried for a queue before drawing a new one.
+const BOARD_ATTEMPTS: usize = 50;
+/// Queues drawn before giving up on a board.
+const QUEUE_ATTEMPTS: usize = 100;
+
+//----------[ Structs ]----------//
+/// A tetromino placed by the solver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placement {
+	pub kind: TetrominoKind,
+	/// Cells of the tetromino as `(column, row)` on the board it started from, the bottom row
+	/// being 0.
+	pub cells: Vec<(usize, usize)>,
+}
+
+/// Progress of the perfect clear trainer.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PcTrainer {
+	/// Tetrominos left in the current bag.
+	pub bag: Vec<TetrominoKind>,
+	/// A solution of the current board.
+	pub solution: Vec<Placement>,
+	pub solved: u32,
+	pub failed: u32,
+}
+//-------------------------------//
+
+//----------[ Solver ]----------//
+/// Returns the cells of a tetromino rotation as `(column, row)` offsets, the bottom row being 0.
+fn shape_cells(tetromino: &Tetromino, rotation: usize) -> Vec<(usize, usize)> {
+	(0..4)
+		.flat_map(|y| (0..4).map(move |x| (x, y)))
+		.filter(|&(x, y)| tetromino.rotations[rotation][y][x])
+		.map(|(x, y)| (x, 3 - y))
+		.collect()
+}
+
+/// Drops a tetromino rotation straight down at the given column offset, returning the cells it
+/// lands on if they stay below the top of the board.
+fn drop_cells(
+	rows: &[u16],
+	width: usize,
+	shape: &[(usize, usize)],
+	x: i32,
+) -> Option<Vec<(usize, usize)>> {
+	let columns: Vec<i32> = shape.iter().map(|&(cx, _)| x + cx as i32).collect();
+	if columns.iter().any(|&column| column < 0 || column >= width as i32) {
+		return None;
+	}
+	let bottom = shape.iter().map(|&(_, cy)| cy).min()?;
+	let fits = |y: i32| {
+		shape.iter().all(|&(cx, cy)| {
+			let row = y + cy as i32 - bottom as i32;
+			row >= 0
+				&& rows
+					.get(row as usize)
+					.is_none_or(|mask| mask & (1 << (x + cx as i32)) == 0)
+		})
+	};
+	// Fall from above the board until the next row down is blocked.
+	let mut y = rows.len() as i32;
+	while fits(y - 1) {
+		y -= 1;
+	}
+	let cells: Vec<(usize, usize)> = shape
+		.iter()
+		.map(|&(cx, cy)| ((x + cx as i32) as usize, (y + cy as i32 - bottom as i32) as usize))
+		.collect();
+	cells.iter().all(|&(_, row)| row < rows.len()).then_some(cells)
+}
+
+/// Finds a way to clear every row of the board with the queue by hard dropping, using the hold.
+/// The rows are bit masks from the bottom up, one bit per column.
+pub fn solve(
+	rows: &[u16],
+	width: usize,
+	queue: &[TetrominoKind],
+	hold: Option<TetrominoKind>,
+) -> Option<Vec<Placement>> {
+	let board: Vec<(u16, usize)> = rows.iter().copied().zip(0..).collect();
+	search(&board, width, queue, hold, &mut HashSet::new())
+}
+
+/// Searches for a perfect clear, the rows keeping the index they had on the starting board.
+fn search(
+	board: &[(u16, usize)],
+	width: usize,
+	queue: &[TetrominoKind],
+	hold: Option<TetrominoKind>,
+	seen: &mut HashSet<(Vec<u16>, usize, Option<TetrominoKind>)>,
+) -> Option<Vec<Placement>> {
+	if board.is_empty() {
+		return Some(vec![]);
+	}
+	let full = (1u16 << width) - 1;
+	let empty_cells: u32 = board.iter().map(|&(mask, _)| (full & !mask).count_ones()).sum();
+	let pieces = queue.len() + hold.is_some() as usize;
+	if empty_cells as usize > pieces * 4 {
+		return None;
+	}
+	let rows: Vec<u16> = board.iter().map(|&(mask, _)| mask).collect();
+	if !seen.insert((rows.clone(), queue.len(), hold)) {
+		return None;
+	}
+	let (&current, rest) = queue.split_first()?;
+
+	// Play the current tetromino, swap it with the held one, or hold it and play the next one.
+	let mut options = vec![(current, hold, rest)];
+	match hold {
+		Some(held) if held != current => options.push((held, Some(current), rest)),
+		None if !rest.is_empty() => options.push((rest[0], Some(current), &rest[1..])),
+		_ => {}
+	}
+
+	for (kind, hold, queue) in options {
+		let tetromino = kind.shape();
+		let mut tried: Vec<Vec<(usize, usize)>> = vec![];
+		for rotation in 0..4 {
+			let shape = shape_cells(&tetromino, rotation);
+			for x in -3..width as i32 {
+				let Some(cells) = drop_cells(&rows, width, &shape, x) else {
+					continue;
+				};
+				if tried.contains(&cells) {
+					continue;
+				}
+				tried.push(cells.clone());
+
+				let mut next = board.to_vec();
+				for &(column, row) in &cells {
+					next[row].0 |= 1 << column;
+				}
+				let placement = Placement {
+					kind,
+					cells: cells.iter().map(|&(column, row)| (column, board[row].1)).collect(),
+				};
+				next.retain(|&(mask, _)| mask != full);
+				if let Some(mut solution) = search(&next, width, queue, hold, seen) {
+					solution.insert(0, placement);
+					return Some(solution);
+				}
+			}
+		}
+	}
+	None
+}
+//------------------------------//
+
+//----------[ Generator ]----------//
+/// Draws the next tetromino of the bag, starting a new bag once it is empty.
+pub fn draw_from_bag(rng: &mut ChaCha8Rng, bag: &mut Vec<TetrominoKind>) -> TetrominoKind {
+	if bag.is_empty() {
+		*bag = TetrominoKind::ALL.to_vec();
+		bag.shuffle(rng);
+	}
+	bag.remove(0)
+}
+
+/// Randomly covers the board with tetrominos.
+fn random_tiling(rng: &mut ChaCha8Rng, width: usize) -> Option<Vec<Placement>> {
+	fn fill(
+		rng: &mut ChaCha8Rng,
+		rows: &mut [u16],
+		width: usize,
+		tiles: &mut Vec<Placement>,
+		budget: &mut u32,
+	) -> bool {
+		let full = (1u16 << width) - 1;
+		let Some(row) = rows.iter().position(|&mask| mask != full) else {
+			return true;
+		};
+		if *budget == 0 {
+			return false;
+		}
+		*budget -= 1;
+		let column = (0..width).find(|&column| rows[row] & (1 << column) == 0).unwrap();
+
+		let mut candidates: Vec<(TetrominoKind, usize)> = TetrominoKind::ALL
+			.into_iter()
+			.flat_map(|kind| (0..4).map(move |rotation| (kind, rotation)))
+			.collect();
+		candidates.shuffle(rng);
+		for (kind, rotation) in candidates {
+			let shape = shape_cells(&kind.shape(), rotation);
+			// Anchor the lowest, leftmost cell of the shape on the first empty cell.
+			let &(anchor_x, anchor_y) = shape.iter().min_by_key(|&&(cx, cy)| (cy, cx)).unwrap();
+			let cells: Option<Vec<(usize, usize)>> = shape
+				.iter()
+				.map(|&(cx, cy)| Some(((column + cx).checked_sub(anchor_x)?, row + cy - anchor_y)))
+				.collect();
+			let Some(cells) = cells else {
+				continue;
+			};
+			let blocked = |&(x, y): &(usize, usize)| {
+				x >= width || y >= rows.len() || rows[y] & (1 << x) != 0
+			};
+			if cells.iter().any(blocked) {
+				continue;
+			}
+			cells.iter().for_each(|&(x, y)| rows[y] |= 1 << x);
+			tiles.push(Placement {
+				kind,
+				cells: cells.clone(),
+			});
+			if fill(rng, rows, width, tiles, budget) {
+				return true;
+			}
+			tiles.pop();
+			cells.iter().for_each(|&(x, y)| rows[y] &= !(1 << x));
+		}
+		false
+	}
+
+	let mut rows = vec![0u16; PC_HEIGHT];
+	let mut tiles = vec![];
+	fill(rng, &mut rows, width, &mut tiles, &mut 2000).then_some(tiles)
+}
+
+/// Generates a board that can be perfect cleared with the next tetrominos of the bag, along with
+/// a solution, unless none is found in time.
+pub fn generate(
+	rng: &mut ChaCha8Rng,
+	bag: &mut Vec<TetrominoKind>,
+) -> Option<(Puzzle, Vec<Placement>)> {
+	for _ in 0..QUEUE_ATTEMPTS {
+		let queue: Vec<TetrominoKind> = (0..PC_QUEUE).map(|_| draw_from_bag(rng, bag)).collect();
+		for _ in 0..BOARD_ATTEMPTS {
+			let Some(mut tiles) = random_tiling(rng, BOARD_WIDTH) else {
+				continue;
+			};
+			// Take the tetrominos of the queue out of the tiling, highest first.
+			tiles.sort_by_key(|tile| Reverse(tile.cells.iter().map(|&(_, y)| y).max()));
+			let mut taken = vec![];
+			for kind in &queue {
+				if let Some(index) = tiles.iter().position(|tile| tile.kind == *kind) {
+					taken.push(tiles.remove(index));
+				}
+			}
+			if taken.len() < queue.len() {
+				continue;
+			}
+
+			let mut rows = vec![0u16; PC_HEIGHT];
+			tiles
+				.iter()
+				.flat_map(|tile| &tile.cells)
+				.for_each(|&(x, y)| rows[y] |= 1 << x);
+			if rows.contains(&((1 << BOARD_WIDTH) - 1)) {
+				continue;
+			}
+			if let Some(solution) = solve(&rows, BOARD_WIDTH, &queue, None) {
+				let board = rows
+					.iter()
+					.rev()
+					.map(|&mask| {
+						(0..BOARD_WIDTH)
+							.map(|x| {
+								if mask & (1 << x) != 0 {
+									PlayFieldCell::filled(None)
+								} else {
+									PlayFieldCell::default()
+								}
+							})
+							.collect()
+					})
+					.collect();
+				let puzzle = Puzzle {
+					id: String::from("perfect-clear"),
+					name: String::from("Perfect clear"),
+					goal: Some(Goal::PerfectClear),
+					queue,
+					hold: None,
+					board,
+				};
+				return Some((puzzle, solution));
+			}
+		}
+	}
+	None
+}
+//---------------------------------//
+
+impl App {
+	/// Starts a new board of the perfect clear trainer.
+	pub fn next_pc_board(&mut self) {
+		let Some(mut trainer) = self.pc.take() else {
+			return;
+		};
+		let Some((puzzle, solution)) = generate(&mut self.rng, &mut trainer.bag) else {
+			self.pc = Some(trainer);
+			self.status_message = Some(String::from("Could not generate a board, [n] tries again"));
+			return;
+		};
+		trainer.solution = solution;
+		self.pc = Some(trainer);
+		self.start_pc_board(puzzle);
+	}
+
+	/// Plays the current board of the perfect clear trainer again.
+	pub fn retry_pc_board(&mut self) {
+		if let Some(state) = self.puzzle.take() {
+			self.start_pc_board(state.puzzle);
+		}
+	}
+
+	/// Sets up a board of the perfect clear trainer.
+	fn start_pc_board(&mut self, puzzle: Puzzle) {
+		self.clear_falling();
+		self.swap_tetromino = Tetromino::EMPTY;
+		self.tetromino_queue.clear();
+		self.game_over = false;
+		self.load_puzzle(puzzle);
+	}
+
+	/// Counts the outcome of the current board and shows the solution on the board it started
+	/// from after a failure.
+	pub fn finish_pc_board(&mut self, solved: bool) {
+		let Some(trainer) = &mut self.pc else {
+			return;
+		};
+		if solved {
+			trainer.solved += 1;
+			return;
+		}
+		trainer.failed += 1;
+		let solution = trainer.solution.clone();
+		if let Some(state) = self.puzzle.clone() {
+			self.clear_falling();
+			self.apply_setup(&state.puzzle);
+		}
+		let bottom = self.playfield.len() - 1;
+		for placement in solution {
+			for (column, row) in placement.cells {
+				self.playfield[bottom - row][PLAYFIELD_PADDING + column] =
+					PlayFieldCell::filled(Some(placement.kind));
+			}
+		}
+	}
+
+	/// Returns the tetrominos of the solution of the current board, in the order they are placed.
+	pub fn pc_solution_string(&self) -> String {
+		self.pc.as_ref().map_or(String::new(), |trainer| {
+			trainer
+				.solution
+				.iter()
+				.map(|placement| placement.kind.letter())
+				.collect()
+		})
+	}
+}
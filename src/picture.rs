@@ -0,0 +1,351 @@
+//! The board is drawn in software as a picture of indexed colors, with a square of
+//! [`CELL_SIZE`] pixels for every cell, in the colors the terminal draws the tetrominos with.
+//! Pictures are saved as PNG screenshots, of a game being played or of a replay, and the boards of
+//! a replay as an animated GIF.
+
+use std::{
+	borrow::Cow,
+	path::{
+		Path,
+		PathBuf,
+	},
+};
+
+use chrono::Local;
+use ratatui::style::Color;
+
+use crate::{
+	app::{
+		App,
+		PLAYFIELD_PADDING,
+		TICK_RATE,
+	},
+	config::Config,
+	replay::{
+		self,
+		Replay,
+	},
+	storage,
+	tetromino::TetrominoKind,
+};
+
+/// Pixels on each side of a cell.
+pub const CELL_SIZE: usize = 16;
+/// Pixels of the border around the board.
+const BORDER_SIZE: usize = 4;
+/// Centiseconds the last board of an animation is shown before it loops.
+const LAST_FRAME_DELAY: u16 = 200;
+/// Name of the directory in the data directory that screenshots are saved in.
+const SCREENSHOTS_DIRECTORY: &str = "screenshots";
+
+const BACKGROUND: [u8; 3] = [16, 16, 16];
+const GRID: [u8; 3] = [40, 40, 40];
+/// Palette index of the background of empty cells.
+const BACKGROUND_INDEX: u8 = 0;
+/// Palette index of the lines between cells.
+const GRID_INDEX: u8 = 1;
+/// Palette index of the border, in the accent color.
+const BORDER_INDEX: u8 = 2;
+/// Percents of its color every shade of a cell is drawn with, like `██`, `▓▓` and `░░`.
+const SHADES: [u32; 3] = [100, 60, 30];
+
+//----------[ Structs ]----------//
+/// A picture drawn with the colors of a palette.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Picture {
+	pub width: usize,
+	pub height: usize,
+	/// Index in the palette of every pixel, row by row.
+	pub pixels: Vec<u8>,
+}
+//-------------------------------//
+
+//----------[ Colors ]----------//
+/// Returns the red, green and blue of a terminal color, as the default xterm palette shows it.
+pub fn rgb(color: Color) -> [u8; 3] {
+	const BASIC: [[u8; 3]; 16] = [
+		[0, 0, 0],
+		[205, 0, 0],
+		[0, 205, 0],
+		[205, 205, 0],
+		[0, 0, 238],
+		[205, 0, 205],
+		[0, 205, 205],
+		[229, 229, 229],
+		[127, 127, 127],
+		[255, 0, 0],
+		[0, 255, 0],
+		[255, 255, 0],
+		[92, 92, 255],
+		[255, 0, 255],
+		[0, 255, 255],
+		[255, 255, 255],
+	];
+	const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+	match color {
+		Color::Reset | Color::White => BASIC[15],
+		Color::Black => BASIC[0],
+		Color::Red => BASIC[1],
+		Color::Green => BASIC[2],
+		Color::Yellow => BASIC[3],
+		Color::Blue => BASIC[4],
+		Color::Magenta => BASIC[5],
+		Color::Cyan => BASIC[6],
+		Color::Gray => BASIC[7],
+		Color::DarkGray => BASIC[8],
+		Color::LightRed => BASIC[9],
+		Color::LightGreen => BASIC[10],
+		Color::LightYellow => BASIC[11],
+		Color::LightBlue => BASIC[12],
+		Color::LightMagenta => BASIC[13],
+		Color::LightCyan => BASIC[14],
+		Color::Rgb(red, green, blue) => [red, green, blue],
+		Color::Indexed(index @ 0..=15) => BASIC[index as usize],
+		Color::Indexed(index @ 16..=231) => {
+			let index = index as usize - 16;
+			[CUBE[index / 36], CUBE[index / 6 % 6], CUBE[index % 6]]
+		}
+		Color::Indexed(index) => [8 + 10 * (index - 232); 3],
+	}
+}
+
+/// Mixes a color with the background, keeping some percents of it.
+fn shade(color: [u8; 3], percent: u32) -> [u8; 3] {
+	let mut result = BACKGROUND;
+	for (channel, (&value, &background)) in result.iter_mut().zip(color.iter().zip(&BACKGROUND)) {
+		*channel = ((value as u32 * percent + background as u32 * (100 - percent)) / 100) as u8;
+	}
+	result
+}
+
+/// Returns the colors pictures are drawn with: the background, the grid and the border, then every
+/// shade of the tetrominos and of garbage.
+pub fn palette(config: &Config) -> Vec<[u8; 3]> {
+	let mut palette = vec![BACKGROUND, GRID, rgb(config.theme.accent_color())];
+	let colors = TetrominoKind::ALL
+		.iter()
+		.map(TetrominoKind::color)
+		.chain([Color::Gray]);
+	for color in colors {
+		palette.extend(SHADES.map(|percent| shade(rgb(color), percent)));
+	}
+	palette
+}
+
+/// Returns the palette index of a shade of a tetromino type, or garbage.
+fn cell_index(kind: Option<TetrominoKind>, shade: usize) -> u8 {
+	let color = kind.map_or(TetrominoKind::ALL.len(), |kind| kind as usize);
+	(3 + color * SHADES.len() + shade) as u8
+}
+//------------------------------//
+
+impl Picture {
+	/// Returns a picture filled with one color.
+	pub fn new(width: usize, height: usize, index: u8) -> Self {
+		Self {
+			width,
+			height,
+			pixels: vec![index; width * height],
+		}
+	}
+
+	/// Fills a rectangle with one color.
+	pub fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, index: u8) {
+		for row in y..(y + height).min(self.height) {
+			let start = row * self.width;
+			self.pixels[start + x.min(self.width)..start + (x + width).min(self.width)].fill(index);
+		}
+	}
+
+	/// Encodes the picture as a PNG file.
+	pub fn png(&self, palette: &[[u8; 3]]) -> Result<Vec<u8>, String> {
+		let mut result = vec![];
+		let mut encoder = png::Encoder::new(&mut result, self.width as u32, self.height as u32);
+		encoder.set_color(png::ColorType::Indexed);
+		encoder.set_depth(png::BitDepth::Eight);
+		encoder.set_palette(palette.concat());
+		let mut writer = encoder.write_header().map_err(|error| error.to_string())?;
+		writer
+			.write_image_data(&self.pixels)
+			.map_err(|error| error.to_string())?;
+		writer.finish().map_err(|error| error.to_string())?;
+		Ok(result)
+	}
+}
+
+impl App {
+	/// Draws the visible board with the tetromino, its ghost and the stack as they are shown in the
+	/// terminal.
+	pub fn board_picture(&self) -> Picture {
+		let size = CELL_SIZE * self.cell_scale();
+		let columns = self.board_columns();
+		let rows = self.playfield.len() - PLAYFIELD_PADDING;
+		let mut picture = Picture::new(
+			columns.len() * size + 2 * BORDER_SIZE + 1,
+			rows * size + 2 * BORDER_SIZE + 1,
+			BORDER_INDEX,
+		);
+		picture.fill(
+			BORDER_SIZE,
+			BORDER_SIZE,
+			picture.width - 2 * BORDER_SIZE,
+			picture.height - 2 * BORDER_SIZE,
+			GRID_INDEX,
+		);
+
+		let falling_kind = self.current_tetromino.kind();
+		let target = self
+			.opener_target()
+			.or_else(|| self.finesse_target())
+			.unwrap_or_default();
+		let ghost = if self.config.layout.ghost && self.can_control() {
+			self.ghost_cells()
+		} else {
+			vec![]
+		};
+		let visible_rows = self.playfield.iter().enumerate().skip(PLAYFIELD_PADDING);
+		for (row, (y, cells)) in visible_rows.enumerate() {
+			for (column, x) in columns.clone().enumerate() {
+				let cell = &cells[x];
+				let index = if cell.landed {
+					match self.landed_cell_string(cell) {
+						"██" => cell_index(cell.kind, 0),
+						"▓▓" => cell_index(cell.kind, 1),
+						"░░" => cell_index(cell.kind, 2),
+						_ => BACKGROUND_INDEX,
+					}
+				} else if cell.falling {
+					cell_index(falling_kind, 0)
+				} else if target.contains(&(x, y)) {
+					cell_index(falling_kind, 1)
+				} else if ghost.contains(&(x, y)) {
+					cell_index(falling_kind, 2)
+				} else {
+					BACKGROUND_INDEX
+				};
+				picture.fill(
+					BORDER_SIZE + 1 + column * size,
+					BORDER_SIZE + 1 + row * size,
+					size - 1,
+					size - 1,
+					index,
+				);
+			}
+		}
+		picture
+	}
+
+	/// Saves a PNG screenshot of the board to the screenshots directory, so that boards of every
+	/// mode can be shared.
+	pub fn save_screenshot(&mut self) {
+		let name = format!(
+			"{}_{}.png",
+			Local::now().format("%Y-%m-%d_%H-%M-%S"),
+			self.mode.name().to_lowercase().replace(' ', "-")
+		);
+		let result = storage::path(SCREENSHOTS_DIRECTORY)
+			.map_err(|error| error.to_string())
+			.and_then(|directory| {
+				let output = directory.join(name);
+				let contents = self.board_picture().png(&palette(&self.config))?;
+				write_export(&output, &contents)?;
+				Ok(output)
+			});
+		self.status_message = Some(match result {
+			Ok(output) => format!("Screenshot saved as {}", output.display()),
+			Err(error) => format!("Could not save the screenshot: {}", error),
+		});
+	}
+}
+
+/// Returns the path of an export next to a replay, unless another path is given.
+fn output_path(path: &Path, output: Option<&Path>, extension: &str) -> PathBuf {
+	output.map_or_else(|| path.with_extension(extension), Path::to_path_buf)
+}
+
+/// Writes an exported file.
+fn write_export(output: &Path, contents: &[u8]) -> Result<(), String> {
+	storage::write_path(output, contents).map_err(|error| format!("{}: {}", output.display(), error))
+}
+
+/// Plays a replay back until the given number of pieces are locked, or to its end, and returns a
+/// picture of the board.
+pub fn replay_picture(replay: &Replay, config: &Config, pieces: Option<u32>) -> Picture {
+	let mut game = replay.game_with_config(config);
+	let mut next_input = 0;
+	while !replay.finished(&game, next_input)
+		&& pieces.is_none_or(|pieces| game.stats.pieces < pieces)
+	{
+		next_input = replay.step(&mut game, next_input);
+	}
+	game.board_picture()
+}
+
+/// Plays a replay back and returns an animated GIF of the board, with a frame whenever it changes.
+pub fn replay_gif(replay: &Replay, config: &Config) -> Result<Vec<u8>, String> {
+	let mut game = replay.game_with_config(config);
+	let first = game.board_picture();
+	let palette = palette(config);
+	let mut result = vec![];
+	let mut encoder = gif::Encoder::new(
+		&mut result,
+		first.width as u16,
+		first.height as u16,
+		&palette.concat(),
+	)
+	.map_err(|error| error.to_string())?;
+	encoder
+		.set_repeat(gif::Repeat::Infinite)
+		.map_err(|error| error.to_string())?;
+
+	let mut write_frame = |picture: &Picture, delay: u16| {
+		let frame = gif::Frame {
+			width: picture.width as u16,
+			height: picture.height as u16,
+			buffer: Cow::Borrowed(&picture.pixels),
+			delay,
+			..gif::Frame::default()
+		};
+		encoder.write_frame(&frame).map_err(|error| error.to_string())
+	};
+	// Every board is written once the next one is known, so that it is shown until then.
+	let (mut shown, mut shown_at) = (first, 0);
+	let mut next_input = 0;
+	while !replay.finished(&game, next_input) {
+		next_input = replay.step(&mut game, next_input);
+		let picture = game.board_picture();
+		if picture != shown {
+			let ticks = (game.replay_ticks - shown_at) as u64;
+			write_frame(&shown, (ticks * TICK_RATE / 10).min(u16::MAX as u64) as u16)?;
+			(shown, shown_at) = (picture, game.replay_ticks);
+		}
+	}
+	write_frame(&shown, LAST_FRAME_DELAY)?;
+	drop(encoder);
+	Ok(result)
+}
+
+/// Exports a PNG of the board of a replay file after some pieces, or at its end, next to it unless
+/// another path is given, returning the path of the picture.
+pub fn export_png(
+	path: &Path,
+	output: Option<&Path>,
+	pieces: Option<u32>,
+	config: &Config,
+) -> Result<PathBuf, String> {
+	let replay = replay::load(path)?;
+	let output = output_path(path, output, "png");
+	let contents = replay_picture(&replay, config, pieces).png(&palette(config))?;
+	write_export(&output, &contents)?;
+	Ok(output)
+}
+
+/// Exports an animated GIF of a replay file, next to it unless another path is given, returning the
+/// path of the animation.
+pub fn export_gif(path: &Path, output: Option<&Path>, config: &Config) -> Result<PathBuf, String> {
+	let replay = replay::load(path)?;
+	let output = output_path(path, output, "gif");
+	let contents = replay_gif(&replay, config)?;
+	write_export(&output, &contents)?;
+	Ok(output)
+}
@@ -0,0 +1,360 @@
+//! Puzzles are plain text files. Blank lines and lines starting with `#` are ignored until the
+//! board starts.
+//!
+//! ```text
+//! # A T-spin double setup.
+//! name: T-spin Double
+//! goal: tsd
+//! queue: TIO
+//! hold: L
+//! board:
+//! XXX...XXXX
+//! XX...XXXXX
+//! XXXX.XXXXX
+//! ```
+//!
+//! - `name` is shown while playing, it defaults to the file name.
+//! - `goal` is one of `lines <n>`, `perfect-clear`, `tsd` or `survive <n>`.
+//! - `queue` is the fixed tetromino sequence as letters. The puzzle fails once it runs out. When
+//!   it is left out the tetrominos are drawn from random bags.
+//! - `hold` is the tetromino in the hold at the start, if any.
+//! - `board` must come last. Every following line is a row of the board, the last one being the
+//!   bottom row. Rows are as wide as the board, `.` is an empty cell, `X` is garbage and any of
+//!   `IJLOSTZ` is a block colored like that tetromino.
+//!
+//! Puzzle packs are directories of `.txt` files, played in the order of their file names. Sandbox
+//! setups are saved in the same format without a goal.
+
+use std::{
+	fs,
+	io,
+	path::{
+		Path,
+		PathBuf,
+	},
+};
+
+use crate::{
+	app::{
+		PlayFieldCell,
+		BOARD_WIDTH,
+	},
+	storage,
+	tetromino::TetrominoKind,
+};
+
+/// Puzzles shipped with tetrs, played before the ones in the pack directory.
+const BUILTIN_PUZZLES: [(&str, &str); 3] = [
+	("01-first-lines", include_str!("../puzzles/01-first-lines.txt")),
+	("02-t-spin-double", include_str!("../puzzles/02-t-spin-double.txt")),
+	("03-perfect-clear", include_str!("../puzzles/03-perfect-clear.txt")),
+];
+
+//----------[ Structs ]----------//
+/// What has to be done to solve a puzzle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+	/// Clear the given number of lines.
+	Lines(u32),
+	/// Clear every block from the board.
+	PerfectClear,
+	/// Clear two lines with a T-spin.
+	Tsd,
+	/// Lock the given number of tetrominos without topping out.
+	Survive(u32),
+}
+
+/// A puzzle as described by a level file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Puzzle {
+	/// Identifier used for progress tracking, the file name without extension.
+	pub id: String,
+	pub name: String,
+	/// Goal of the puzzle, `None` for a sandbox setup.
+	pub goal: Option<Goal>,
+	/// Fixed tetromino sequence, random bags are used when empty.
+	pub queue: Vec<TetrominoKind>,
+	pub hold: Option<TetrominoKind>,
+	/// Rows of the board from top to bottom.
+	pub board: Vec<Vec<PlayFieldCell>>,
+}
+
+/// Whether a puzzle is still being played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PuzzleStatus {
+	#[default]
+	Playing,
+	Solved,
+	Failed,
+}
+
+/// A puzzle being played.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PuzzleState {
+	pub puzzle: Puzzle,
+	/// Tetrominos locked so far.
+	pub pieces: u32,
+	/// Lines cleared so far.
+	pub lines: u32,
+	pub status: PuzzleStatus,
+}
+//-------------------------------//
+
+impl Goal {
+	/// Returns a short description of the goal.
+	pub fn description(&self) -> String {
+		match self {
+			Goal::Lines(lines) => format!("Clear {} lines", lines),
+			Goal::PerfectClear => String::from("Perfect clear"),
+			Goal::Tsd => String::from("T-spin double"),
+			Goal::Survive(pieces) => format!("Survive {} pieces", pieces),
+		}
+	}
+}
+
+//----------[ Parsing ]----------//
+/// Parses the goal field of a puzzle file.
+fn parse_goal(value: &str) -> Result<Goal, String> {
+	let mut words = value.split_whitespace();
+	let goal = words.next().unwrap_or_default().to_lowercase();
+	let count = words.next().map(|count| {
+		count
+			.parse::<u32>()
+			.map_err(|_| format!("invalid count '{}'", count))
+	});
+	match (goal.as_str(), count) {
+		("lines", Some(count)) => Ok(Goal::Lines(count?)),
+		("survive", Some(count)) => Ok(Goal::Survive(count?)),
+		("perfect-clear", None) => Ok(Goal::PerfectClear),
+		("tsd", None) => Ok(Goal::Tsd),
+		_ => Err(format!(
+			"invalid goal '{}', expected 'lines <n>', 'perfect-clear', 'tsd' or 'survive <n>'",
+			value
+		)),
+	}
+}
+
+/// Parses a sequence of tetromino letters.
+fn parse_tetrominos(value: &str) -> Result<Vec<TetrominoKind>, String> {
+	value
+		.chars()
+		.filter(|letter| !letter.is_whitespace())
+		.map(|letter| {
+			TetrominoKind::from_letter(letter).ok_or_else(|| format!("invalid tetromino '{}'", letter))
+		})
+		.collect()
+}
+
+/// Parses the contents of a puzzle file.
+pub fn parse(id: &str, contents: &str) -> Result<Puzzle, String> {
+	let puzzle = parse_setup(id, contents)?;
+	match puzzle.goal {
+		Some(_) => Ok(puzzle),
+		None => Err(String::from("missing goal")),
+	}
+}
+
+/// Parses the contents of a puzzle file that may leave out the goal.
+pub fn parse_setup(id: &str, contents: &str) -> Result<Puzzle, String> {
+	let mut puzzle = Puzzle {
+		id: id.to_string(),
+		name: id.to_string(),
+		goal: None,
+		queue: vec![],
+		hold: None,
+		board: vec![],
+	};
+	let mut in_board = false;
+
+	for (index, line) in contents.lines().enumerate() {
+		let error = |message: String| format!("line {}: {}", index + 1, message);
+		let line = line.trim_end();
+
+		if in_board {
+			if line.chars().count() != BOARD_WIDTH {
+				return Err(error(format!("board rows must be {} cells wide", BOARD_WIDTH)));
+			}
+			let row = line
+				.chars()
+				.map(|cell| {
+					PlayFieldCell::from_char(cell)
+						.ok_or_else(|| error(format!("invalid board cell '{}'", cell)))
+				})
+				.collect::<Result<Vec<PlayFieldCell>, String>>()?;
+			puzzle.board.push(row);
+			continue;
+		}
+
+		if line.trim().is_empty() || line.starts_with('#') {
+			continue;
+		}
+		let (key, value) = line
+			.split_once(':')
+			.ok_or_else(|| error(String::from("expected 'key: value'")))?;
+		let value = value.trim();
+		match key.trim().to_lowercase().as_str() {
+			"name" => puzzle.name = value.to_string(),
+			"goal" => puzzle.goal = Some(parse_goal(value).map_err(error)?),
+			"queue" => puzzle.queue = parse_tetrominos(value).map_err(error)?,
+			"hold" => {
+				puzzle.hold = match parse_tetrominos(value).map_err(error)?.as_slice() {
+					[] => None,
+					[hold] => Some(*hold),
+					_ => return Err(error(String::from("only one tetromino can be held"))),
+				}
+			}
+			"board" => in_board = true,
+			key => return Err(error(format!("unknown key '{}'", key))),
+		}
+	}
+
+	Ok(puzzle)
+}
+
+/// Writes a puzzle in the file format read by [`parse_setup`].
+pub fn to_file_string(puzzle: &Puzzle) -> String {
+	let mut result = format!("name: {}\n", puzzle.name);
+	if let Some(goal) = puzzle.goal {
+		result += &format!(
+			"goal: {}\n",
+			match goal {
+				Goal::Lines(lines) => format!("lines {}", lines),
+				Goal::PerfectClear => String::from("perfect-clear"),
+				Goal::Tsd => String::from("tsd"),
+				Goal::Survive(pieces) => format!("survive {}", pieces),
+			}
+		);
+	}
+	if !puzzle.queue.is_empty() {
+		result += &format!(
+			"queue: {}\n",
+			puzzle.queue.iter().map(|kind| kind.letter()).collect::<String>()
+		);
+	}
+	if let Some(hold) = puzzle.hold {
+		result += &format!("hold: {}\n", hold.letter());
+	}
+	result += "board:\n";
+	for row in &puzzle.board {
+		result.extend(row.iter().map(|cell| cell.to_char()));
+		result.push('\n');
+	}
+	result
+}
+
+/// Loads a puzzle file, using its file name as the identifier.
+pub fn load(path: &Path) -> Result<Puzzle, String> {
+	load_with(path, parse)
+}
+
+/// Loads a puzzle file that may leave out the goal.
+pub fn load_setup(path: &Path) -> Result<Puzzle, String> {
+	load_with(path, parse_setup)
+}
+
+/// Reads a puzzle file and parses it with the given parser.
+fn load_with(path: &Path, parser: fn(&str, &str) -> Result<Puzzle, String>) -> Result<Puzzle, String> {
+	let id = path
+		.file_stem()
+		.map_or(String::from("puzzle"), |stem| stem.to_string_lossy().to_string());
+	let contents = fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+	parser(&id, &contents).map_err(|error| format!("{}: {}", path.display(), error))
+}
+//-------------------------------//
+
+//----------[ Pack ]----------//
+/// Returns the directory holding the player's own puzzles.
+pub fn pack_directory() -> Option<PathBuf> {
+	storage::data_directory().map(|directory| directory.join("puzzles"))
+}
+
+/// Loads the built-in puzzles followed by the ones in the pack directory, returning the puzzles
+/// that could not be loaded as errors.
+pub fn load_pack() -> (Vec<Puzzle>, Vec<String>) {
+	let mut puzzles: Vec<Puzzle> = BUILTIN_PUZZLES
+		.iter()
+		.map(|(id, contents)| parse(id, contents).expect("built-in puzzles are valid"))
+		.collect();
+	let mut errors = vec![];
+
+	let mut paths: Vec<PathBuf> = pack_directory()
+		.and_then(|directory| fs::read_dir(directory).ok())
+		.map(|entries| {
+			entries
+				.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+				.filter(|path| path.extension().is_some_and(|extension| extension == "txt"))
+				.collect()
+		})
+		.unwrap_or_default();
+	paths.sort();
+
+	for path in paths {
+		match load(&path) {
+			Ok(puzzle) => puzzles.push(puzzle),
+			Err(error) => errors.push(error),
+		}
+	}
+	(puzzles, errors)
+}
+//----------------------------//
+
+//----------[ Progress ]----------//
+/// Name of the file listing solved puzzles in the data directory.
+const PROGRESS_FILE: &str = "puzzle_progress";
+
+/// Loads the identifiers of every solved puzzle.
+pub fn load_progress() -> Vec<String> {
+	storage::read(PROGRESS_FILE)
+		.ok()
+		.flatten()
+		.map(|contents| contents.lines().map(String::from).collect())
+		.unwrap_or_default()
+}
+
+/// Returns the first unsolved puzzle of the pack, or else the first one.
+pub fn next_unsolved() -> Option<Puzzle> {
+	let (puzzles, _) = load_pack();
+	let solved = load_progress();
+	puzzles
+		.iter()
+		.find(|puzzle| !solved.contains(&puzzle.id))
+		.or(puzzles.first())
+		.cloned()
+}
+
+/// Marks a puzzle as solved.
+pub fn record_solved(id: &str) -> io::Result<()> {
+	let mut solved = load_progress();
+	if solved.iter().any(|solved_id| solved_id == id) {
+		return Ok(());
+	}
+	solved.push(id.to_string());
+	storage::write(PROGRESS_FILE, &(solved.join("\n") + "\n"))
+}
+
+/// Prints every puzzle of the pack along with whether it has been solved.
+pub fn print_pack() {
+	let (puzzles, errors) = load_pack();
+	let solved = load_progress();
+	for puzzle in &puzzles {
+		println!(
+			"[{}] {:<24}{:<24}{}",
+			if solved.contains(&puzzle.id) { "x" } else { " " },
+			puzzle.id,
+			puzzle.name,
+			puzzle.goal.map_or(String::new(), |goal| goal.description())
+		);
+	}
+	println!(
+		"\n{}/{} solved, add your own puzzles to {}",
+		puzzles.iter().filter(|puzzle| solved.contains(&puzzle.id)).count(),
+		puzzles.len(),
+		pack_directory().map_or(String::from("the puzzles data directory"), |path| path
+			.display()
+			.to_string())
+	);
+	for error in errors {
+		eprintln!("{}", error);
+	}
+}
+//--------------------------------//
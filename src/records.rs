@@ -0,0 +1,240 @@
+use std::io;
+
+use chrono::{
+	Duration,
+	Local,
+	NaiveDateTime,
+};
+
+use crate::{
+	app::{
+		format_ticks,
+		App,
+	},
+	mode::GameMode,
+	stats::GameStats,
+	storage,
+};
+
+/// Format of the date and time of a record.
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+//----------[ Structs ]----------//
+/// A finished game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameRecord {
+	/// Local date and time the game ended.
+	pub date: NaiveDateTime,
+	pub mode: GameMode,
+	pub seed: u64,
+	/// Length of the game in ticks.
+	pub ticks: u32,
+	pub score: u32,
+	pub level: u32,
+	pub lines: u32,
+	pub pieces: u32,
+	pub attack: u32,
+	pub max_combo: u32,
+}
+
+/// Personal bests and averages of a mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModeSummary {
+	pub mode: GameMode,
+	pub games: usize,
+	pub best_score: u32,
+	pub best_lines: u32,
+	pub best_pps: f64,
+	pub average_score: f64,
+	pub average_pps: f64,
+	pub max_combo: u32,
+}
+//-------------------------------//
+
+impl GameRecord {
+	/// Returns the tetrominos locked per second.
+	pub fn pieces_per_second(&self) -> f64 {
+		GameStats {
+			pieces: self.pieces,
+			..GameStats::default()
+		}
+		.pieces_per_second(self.ticks)
+	}
+}
+
+impl App {
+	/// Returns the record of the game, ending now.
+	pub fn game_record(&self) -> GameRecord {
+		GameRecord {
+			date: Local::now().naive_local(),
+			mode: self.mode,
+			seed: self.seed,
+			ticks: self.elapsed_ticks,
+			score: self.score,
+			level: self.level,
+			lines: self.stats.lines,
+			pieces: self.stats.pieces,
+			attack: self.stats.attack,
+			max_combo: self.stats.max_combo,
+		}
+	}
+}
+
+//----------[ Records ]----------//
+/// Name of the file recording every finished game in the data directory.
+const RECORDS_FILE: &str = "games";
+
+/// Parses a line of the records file, formatted as
+/// `date mode seed ticks score level lines pieces attack max_combo`.
+pub fn parse_record(line: &str) -> Option<GameRecord> {
+	let mut fields = line.split_whitespace();
+	Some(GameRecord {
+		date: NaiveDateTime::parse_from_str(fields.next()?, DATE_FORMAT).ok()?,
+		mode: GameMode::from_name(fields.next()?)?,
+		seed: fields.next()?.parse().ok()?,
+		ticks: fields.next()?.parse().ok()?,
+		score: fields.next()?.parse().ok()?,
+		level: fields.next()?.parse().ok()?,
+		lines: fields.next()?.parse().ok()?,
+		pieces: fields.next()?.parse().ok()?,
+		attack: fields.next()?.parse().ok()?,
+		max_combo: fields.next()?.parse().ok()?,
+	})
+}
+
+/// Formats a record as a line of the records file.
+pub fn record_line(record: &GameRecord) -> String {
+	format!(
+		"{} {} {} {} {} {} {} {} {} {}",
+		record.date.format(DATE_FORMAT),
+		record.mode.name(),
+		record.seed,
+		record.ticks,
+		record.score,
+		record.level,
+		record.lines,
+		record.pieces,
+		record.attack,
+		record.max_combo
+	)
+}
+
+/// Loads every recorded game, skipping malformed lines.
+pub fn load_records() -> Vec<GameRecord> {
+	storage::read(RECORDS_FILE)
+		.ok()
+		.flatten()
+		.map(|contents| contents.lines().filter_map(parse_record).collect())
+		.unwrap_or_default()
+}
+
+/// Does the game score more than every recorded game of its mode?
+pub fn is_personal_best(records: &[GameRecord], record: &GameRecord) -> bool {
+	record.score > 0
+		&& records
+			.iter()
+			.filter(|other| other.mode == record.mode)
+			.all(|other| record.score > other.score)
+}
+
+/// Appends a game to the records file.
+pub fn record_game(record: &GameRecord) -> io::Result<()> {
+	storage::append_line(RECORDS_FILE, &record_line(record))
+}
+//-------------------------------//
+
+//----------[ Summary ]----------//
+/// Returns the average of a value over some records, or `None` without records.
+fn average(records: &[&GameRecord], value: impl Fn(&GameRecord) -> f64) -> Option<f64> {
+	(!records.is_empty())
+		.then(|| records.iter().map(|record| value(record)).sum::<f64>() / records.len() as f64)
+}
+
+/// Summarizes the records of every played mode, in menu order.
+pub fn summarize(records: &[GameRecord]) -> Vec<ModeSummary> {
+	GameMode::ALL
+		.into_iter()
+		.filter_map(|mode| {
+			let games: Vec<&GameRecord> =
+				records.iter().filter(|record| record.mode == mode).collect();
+			Some(ModeSummary {
+				mode,
+				games: games.len(),
+				best_score: games.iter().map(|record| record.score).max()?,
+				best_lines: games.iter().map(|record| record.lines).max()?,
+				best_pps: games
+					.iter()
+					.map(|record| record.pieces_per_second())
+					.fold(0.0, f64::max),
+				average_score: average(&games, |record| record.score as f64)?,
+				average_pps: average(&games, GameRecord::pieces_per_second)?,
+				max_combo: games.iter().map(|record| record.max_combo).max()?,
+			})
+		})
+		.collect()
+}
+
+/// Prints the bests and averages of every played mode, followed by how the last week compares to
+/// the one before.
+pub fn print_stats() {
+	let records = load_records();
+	if records.is_empty() {
+		println!("No games finished yet.");
+		return;
+	}
+
+	println!(
+		"{:<14}{:>6}{:>12}{:>12}{:>8}{:>10}{:>10}{:>8}",
+		"Mode", "Games", "Best score", "Avg score", "Lines", "Best PPS", "Avg PPS", "Combo"
+	);
+	for summary in summarize(&records) {
+		println!(
+			"{:<14}{:>6}{:>12}{:>12.0}{:>8}{:>10.2}{:>10.2}{:>8}",
+			summary.mode.name(),
+			summary.games,
+			summary.best_score,
+			summary.average_score,
+			summary.best_lines,
+			summary.best_pps,
+			summary.average_pps,
+			summary.max_combo
+		);
+	}
+
+	let now = Local::now().naive_local();
+	let week = |weeks_ago: i32| {
+		let end = now - Duration::weeks(weeks_ago as i64);
+		let start = end - Duration::weeks(1);
+		move |record: &&GameRecord| record.date > start && record.date <= end
+	};
+	println!("\nLast 7 days against the 7 before:");
+	for mode in GameMode::ALL {
+		let games: Vec<&GameRecord> = records.iter().filter(|record| record.mode == mode).collect();
+		let this_week: Vec<&GameRecord> = games.iter().copied().filter(week(0)).collect();
+		let last_week: Vec<&GameRecord> = games.iter().copied().filter(week(1)).collect();
+		if this_week.is_empty() {
+			continue;
+		}
+		let trend = |value: fn(&GameRecord) -> f64, precision: usize| {
+			let current = average(&this_week, value).unwrap_or_default();
+			match average(&last_week, value) {
+				Some(previous) => format!(
+					"{:.precision$} ({:+.precision$})",
+					current,
+					current - previous,
+					precision = precision
+				),
+				None => format!("{:.precision$}", current, precision = precision),
+			}
+		};
+		println!(
+			"{:<14}{} games, avg score {}, avg PPS {}, time played {}",
+			mode.name(),
+			this_week.len(),
+			trend(|record| record.score as f64, 0),
+			trend(GameRecord::pieces_per_second, 2),
+			format_ticks(this_week.iter().map(|record| record.ticks).sum())
+		);
+	}
+}
+//-------------------------------//
@@ -0,0 +1,644 @@
+//! Replays are the inputs of a game with the tick they happened on. Since the tetrominos come from
+//! a seeded generator and the game only changes on ticks and inputs, playing the inputs back on a
+//! game started from the same seed and options repeats it exactly.
+//!
+//! Replays are saved as plain text files in the `replays` directory of the data directory.
+//!
+//! ```text
+//! tetrs-replay: 1
+//! version: 0.1.0
+//! rules: 1
+//! date: 2026-03-02T18:30:00
+//! mode: Marathon
+//! ruleset: start 1
+//! seed: 42
+//! level: 1
+//! fade: 166
+//! practice: false
+//! key-releases: true
+//! handling: 167 33 20
+//! result: 2000 12000 40 5
+//! inputs:
+//! 0 press left
+//! 6 release left
+//! 31 press hard-drop
+//! 95 handling 100 0 0
+//! ```
+//!
+//! - `tetrs-replay` is the version of the format and must come first. Files of other versions are
+//!   not read.
+//! - `version` is the version of tetrs that recorded the game, `date` the local time it started.
+//! - `rules` is the version of the game rules the inputs were played under. Replays without it
+//!   were recorded under the first one.
+//! - `ruleset` is the settings the game is ranked under on the leaderboard.
+//! - `level` is the starting level, `fade` the ticks locked tetrominos take to fade away and
+//!   `practice` whether undo was allowed.
+//! - `key-releases` tells whether the terminal reported key releases, which held shift keys need.
+//! - `handling` is DAS, ARR and SDF at the start of the game.
+//! - `result` is the ticks, score, lines and level the game ended with, if it ended.
+//! - `inputs` must come last. Every following line is the tick of an input, counted from the start
+//!   of the game without turning back on undo, followed by
+//!   `press <action>`, `release <action>`, `undo`, `redo` or `handling <das> <arr> <sdf>` for
+//!   handling changed during the game. Actions are `left`, `right`, `das-left`, `das-right`,
+//!   `rotate-cw`, `rotate-ccw`, `soft-drop`, `hard-drop` and `hold`.
+
+use std::{
+	fs,
+	path::{
+		Path,
+		PathBuf,
+	},
+};
+
+use chrono::{
+	Local,
+	NaiveDateTime,
+	SubsecRound,
+};
+
+use crate::{
+	app::{
+		format_ticks,
+		App,
+	},
+	config::{
+		Action,
+		Config,
+		Handling,
+	},
+	mode::GameMode,
+	storage,
+};
+
+/// Version of the replay format, raised whenever older replays could not be played back.
+pub const REPLAY_VERSION: u32 = 1;
+
+/// Version of the game rules, raised whenever a change makes the same inputs play out differently.
+pub const RULES_VERSION: u32 = 2;
+
+/// Ticks a replay is played back past its claimed time before it is given up on.
+const VERIFY_MARGIN_TICKS: u32 = 100;
+
+/// Name of the directory of replays in the data directory.
+const REPLAYS_DIRECTORY: &str = "replays";
+
+/// Format of the date and time a replay started.
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Actions that change the game, with their names in replay files.
+const ACTIONS: [(&str, Action); 9] = [
+	("left", Action::Left),
+	("right", Action::Right),
+	("das-left", Action::DasLeft),
+	("das-right", Action::DasRight),
+	("rotate-cw", Action::RotateCw),
+	("rotate-ccw", Action::RotateCcw),
+	("soft-drop", Action::SoftDrop),
+	("hard-drop", Action::HardDrop),
+	("hold", Action::Hold),
+];
+
+//----------[ Structs ]----------//
+/// An input that changes the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayInput {
+	/// A key bound to an action was pressed.
+	Press(Action),
+	/// A held shift key was let go of.
+	Release(Action),
+	Undo,
+	Redo,
+	/// The handling was changed in the settings.
+	Handling(Handling),
+}
+
+/// How a game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayResult {
+	pub ticks: u32,
+	pub score: u32,
+	pub lines: u32,
+	pub level: u32,
+}
+
+/// A recorded game.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Replay {
+	/// Version of tetrs that recorded the game.
+	pub version: String,
+	/// Version of the game rules the game was played under.
+	pub rules: u32,
+	/// Local date and time the game started.
+	pub date: NaiveDateTime,
+	pub mode: GameMode,
+	pub ruleset: String,
+	pub seed: u64,
+	/// Level the game started on.
+	pub level: u32,
+	pub fade_ticks: u32,
+	pub practice: bool,
+	pub key_releases: bool,
+	/// Handling at the start of the game.
+	pub handling: Handling,
+	pub result: Option<ReplayResult>,
+	/// Inputs with the tick they happened on, in order.
+	pub inputs: Vec<(u32, ReplayInput)>,
+}
+//-------------------------------//
+
+impl ReplayInput {
+	/// Parses an input of a replay file.
+	pub fn parse(value: &str) -> Result<Self, String> {
+		let words: Vec<&str> = value.split_whitespace().collect();
+		let action = |name: &str| {
+			ACTIONS
+				.iter()
+				.find(|&&(other, _)| other == name)
+				.map(|&(_, action)| action)
+				.ok_or_else(|| format!("invalid action '{}'", name))
+		};
+		match words.as_slice() {
+			["press", name] => Ok(ReplayInput::Press(action(name)?)),
+			["release", name] => Ok(ReplayInput::Release(action(name)?)),
+			["undo"] => Ok(ReplayInput::Undo),
+			["redo"] => Ok(ReplayInput::Redo),
+			["handling", values @ ..] => Ok(ReplayInput::Handling(parse_handling(values)?)),
+			_ => Err(format!("invalid input '{}'", value)),
+		}
+	}
+}
+
+impl std::fmt::Display for ReplayInput {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let name = |action: &Action| {
+			ACTIONS
+				.iter()
+				.find(|&(_, other)| other == action)
+				.map_or("none", |&(name, _)| name)
+		};
+		match self {
+			ReplayInput::Press(action) => write!(f, "press {}", name(action)),
+			ReplayInput::Release(action) => write!(f, "release {}", name(action)),
+			ReplayInput::Undo => write!(f, "undo"),
+			ReplayInput::Redo => write!(f, "redo"),
+			ReplayInput::Handling(handling) => write!(f, "handling {}", handling_string(handling)),
+		}
+	}
+}
+
+//----------[ Parsing ]----------//
+/// Parses DAS, ARR and SDF.
+fn parse_handling(values: &[&str]) -> Result<Handling, String> {
+	match values
+		.iter()
+		.map(|value| value.parse::<u32>())
+		.collect::<Result<Vec<u32>, _>>()
+		.as_deref()
+	{
+		Ok(&[das, arr, sdf]) => Ok(Handling { das, arr, sdf }),
+		_ => Err(format!("invalid handling '{}', expected '<das> <arr> <sdf>'", values.join(" "))),
+	}
+}
+
+/// Writes DAS, ARR and SDF.
+fn handling_string(handling: &Handling) -> String {
+	format!("{} {} {}", handling.das, handling.arr, handling.sdf)
+}
+
+/// Parses a value of a replay file.
+fn parse_value<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+	value
+		.parse()
+		.map_err(|_| format!("invalid value '{}'", value))
+}
+
+impl Replay {
+	/// Parses the contents of a replay file.
+	pub fn parse(contents: &str) -> Result<Self, String> {
+		let mut replay = Replay {
+			rules: 1,
+			..Replay::default()
+		};
+		let mut lines = contents.lines().enumerate();
+		let mut in_inputs = false;
+
+		match lines.next().and_then(|(_, line)| line.split_once(':')) {
+			Some(("tetrs-replay", version)) if parse_value(version.trim()) == Ok(REPLAY_VERSION) => {}
+			Some(("tetrs-replay", version)) => {
+				return Err(format!(
+					"unsupported replay version {}, expected {}",
+					version.trim(),
+					REPLAY_VERSION
+				));
+			}
+			_ => return Err(String::from("not a replay file")),
+		}
+
+		for (index, line) in lines {
+			let error = |message: String| format!("line {}: {}", index + 1, message);
+			if line.trim().is_empty() {
+				continue;
+			}
+
+			if in_inputs {
+				let (tick, input) = line
+					.trim()
+					.split_once(' ')
+					.ok_or_else(|| error(String::from("expected '<tick> <input>'")))?;
+				let tick = parse_value(tick).map_err(error)?;
+				if replay.inputs.last().is_some_and(|&(last, _)| tick < last) {
+					return Err(error(String::from("inputs must be in order")));
+				}
+				replay
+					.inputs
+					.push((tick, ReplayInput::parse(input).map_err(error)?));
+				continue;
+			}
+
+			let (key, value) = line
+				.split_once(':')
+				.ok_or_else(|| error(String::from("expected 'key: value'")))?;
+			let value = value.trim();
+			match key.trim() {
+				"version" => replay.version = value.to_string(),
+				"rules" => replay.rules = parse_value(value).map_err(error)?,
+				"date" => {
+					replay.date = NaiveDateTime::parse_from_str(value, DATE_FORMAT)
+						.map_err(|_| error(format!("invalid date '{}'", value)))?;
+				}
+				"mode" => {
+					replay.mode = GameMode::from_name(value)
+						.ok_or_else(|| error(format!("invalid mode '{}'", value)))?;
+				}
+				"ruleset" => replay.ruleset = value.to_string(),
+				"seed" => replay.seed = parse_value(value).map_err(error)?,
+				"level" => replay.level = parse_value(value).map_err(error)?,
+				"fade" => replay.fade_ticks = parse_value(value).map_err(error)?,
+				"practice" => replay.practice = parse_value(value).map_err(error)?,
+				"key-releases" => replay.key_releases = parse_value(value).map_err(error)?,
+				"handling" => {
+					let values: Vec<&str> = value.split_whitespace().collect();
+					replay.handling = parse_handling(&values).map_err(error)?;
+				}
+				"result" => {
+					let values = value
+						.split_whitespace()
+						.map(parse_value)
+						.collect::<Result<Vec<u32>, String>>()
+						.map_err(error)?;
+					let &[ticks, score, lines, level] = values.as_slice() else {
+						return Err(error(String::from(
+							"expected 'result: <ticks> <score> <lines> <level>'",
+						)));
+					};
+					replay.result = Some(ReplayResult {
+						ticks,
+						score,
+						lines,
+						level,
+					});
+				}
+				"inputs" => in_inputs = true,
+				key => return Err(error(format!("unknown key '{}'", key))),
+			}
+		}
+
+		Ok(replay)
+	}
+
+	/// Writes the replay in the file format read by [`Replay::parse`].
+	pub fn to_file_string(&self) -> String {
+		let mut result = format!(
+			"tetrs-replay: {}\nversion: {}\nrules: {}\ndate: {}\nmode: {}\nruleset: {}\nseed: \
+			 {}\nlevel: {}\nfade: {}\npractice: {}\nkey-releases: {}\nhandling: {}\n",
+			REPLAY_VERSION,
+			self.version,
+			self.rules,
+			self.date.format(DATE_FORMAT),
+			self.mode.name(),
+			self.ruleset,
+			self.seed,
+			self.level,
+			self.fade_ticks,
+			self.practice,
+			self.key_releases,
+			handling_string(&self.handling)
+		);
+		if let Some(end) = self.result {
+			result += &format!(
+				"result: {} {} {} {}\n",
+				end.ticks, end.score, end.lines, end.level
+			);
+		}
+		result += "inputs:\n";
+		for (tick, input) in &self.inputs {
+			result += &format!("{} {}\n", tick, input);
+		}
+		result
+	}
+
+	/// Returns the name of the file the replay is saved as.
+	pub fn file_name(&self) -> String {
+		format!(
+			"{}_{}.replay",
+			self.date.format("%Y-%m-%d_%H-%M-%S"),
+			self.mode.name().to_lowercase().replace(' ', "-")
+		)
+	}
+
+	/// Returns the handling after the last change during the game.
+	pub fn last_handling(&self) -> Handling {
+		self.inputs
+			.iter()
+			.rev()
+			.find_map(|(_, input)| match input {
+				ReplayInput::Handling(handling) => Some(*handling),
+				_ => None,
+			})
+			.unwrap_or(self.handling)
+	}
+
+	/// Has the game, played back up to the given input, reached the end of the replay?
+	pub fn finished(&self, game: &App, next_input: usize) -> bool {
+		match self.inputs.get(next_input) {
+			Some(&(tick, _)) => game.game_over && tick != game.replay_ticks,
+			// Without a result, the replay ends with its last input.
+			None => {
+				game.game_over
+					|| self
+						.result
+						.is_none_or(|end| game.elapsed_ticks >= end.ticks)
+			}
+		}
+	}
+
+	/// Plays the inputs from the given one on that happened on the current tick of the game and
+	/// advances it by one tick, returning the index of the next input.
+	pub fn step(&self, game: &mut App, mut next_input: usize) -> usize {
+		while let Some(&(tick, input)) = self.inputs.get(next_input) {
+			if tick != game.replay_ticks {
+				break;
+			}
+			game.play_input(input);
+			next_input += 1;
+		}
+		game.advance();
+		next_input
+	}
+
+	/// Plays the replay back without a terminal until the game is over, returning the result it
+	/// ends with. Modes without replays are not played, nor games going on well past their claimed
+	/// time.
+	pub fn played_result(&self) -> Result<ReplayResult, String> {
+		if !self.mode.has_replays() {
+			return Err(format!("{} games have no replays", self.mode.name()));
+		}
+		let claimed = self.result.map(|result| result.ticks);
+		let mut game = self.game();
+		let mut next_input = 0;
+		while !(game.game_over && self.finished(&game, next_input)) {
+			// Once every input is played, undo can no longer turn the time back.
+			if let Some(claimed) = claimed.filter(|&claimed| {
+				next_input == self.inputs.len()
+					&& game.elapsed_ticks > claimed.saturating_add(VERIFY_MARGIN_TICKS)
+			}) {
+				return Err(format!(
+					"the game goes on past its claimed time of {}",
+					format_ticks(claimed)
+				));
+			}
+			next_input = self.step(&mut game, next_input);
+		}
+		Ok(ReplayResult {
+			ticks: game.elapsed_ticks,
+			score: game.score,
+			lines: game.stats.lines,
+			level: game.level,
+		})
+	}
+
+	/// Checks that the replay can stand for a legitimate result before it is played back: a game of
+	/// a mode with replays, recorded under the current rules, without practice or undo.
+	pub fn check(&self) -> Result<(), String> {
+		if !self.mode.has_replays() {
+			return Err(format!("{} games have no replays", self.mode.name()));
+		}
+		if self.rules != RULES_VERSION {
+			return Err(format!(
+				"recorded under rules version {}, expected {}",
+				self.rules, RULES_VERSION
+			));
+		}
+		if self.practice {
+			return Err(String::from("the game was played in practice"));
+		}
+		if self
+			.inputs
+			.iter()
+			.any(|(_, input)| matches!(input, ReplayInput::Undo | ReplayInput::Redo))
+		{
+			return Err(String::from("the game undoes placements"));
+		}
+		Ok(())
+	}
+
+	/// Checks that the replay stands for a legitimate result and claims the result it was played
+	/// back to.
+	pub fn verify(&self, result: &ReplayResult) -> Result<(), String> {
+		self.check()?;
+		let claimed = self
+			.result
+			.ok_or_else(|| String::from("the replay claims no result"))?;
+		let mismatches: Vec<String> = [
+			("time", format_ticks(claimed.ticks), format_ticks(result.ticks)),
+			("score", claimed.score.to_string(), result.score.to_string()),
+			("lines", claimed.lines.to_string(), result.lines.to_string()),
+			("level", claimed.level.to_string(), result.level.to_string()),
+		]
+		.into_iter()
+		.filter(|(_, claimed, played)| claimed != played)
+		.map(|(name, claimed, played)| {
+			format!("{} {} claimed, {} played back", name, claimed, played)
+		})
+		.collect();
+		if mismatches.is_empty() {
+			Ok(())
+		} else {
+			Err(mismatches.join(", "))
+		}
+	}
+
+	/// Starts the recorded game over with the options of a config, but the recorded handling.
+	pub fn game_with_config(&self, config: &Config) -> App {
+		let mut game = self.game();
+		game.config = Config {
+			handling: self.handling,
+			..config.clone()
+		};
+		game
+	}
+
+	/// Starts the recorded game over, before any input.
+	pub fn game(&self) -> App {
+		let mut game = App::with_seed(self.mode, self.seed);
+		if self.mode != GameMode::Master {
+			game.level = self.level;
+			game.start_level = self.level;
+		}
+		game.fade_ticks = self.fade_ticks;
+		game.practice = self.practice;
+		game.key_releases = self.key_releases;
+		game.config.handling = self.handling;
+		game
+	}
+}
+//-------------------------------//
+
+/// Returns the directory the replays are saved in.
+pub fn replays_directory() -> Option<PathBuf> {
+	storage::data_directory().map(|directory| directory.join(REPLAYS_DIRECTORY))
+}
+
+/// Loads a replay file.
+pub fn load(path: &Path) -> Result<Replay, String> {
+	let contents = fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+	Replay::parse(&contents).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+/// Verifies a replay file and prints the result it plays back to, returning whether it matches the
+/// claimed one.
+pub fn print_verification(path: &Path) -> bool {
+	let replay = match load(path) {
+		Ok(replay) => replay,
+		Err(error) => {
+			eprintln!("Invalid replay {}", error);
+			return false;
+		}
+	};
+	let result = match replay.check().and_then(|()| replay.played_result()) {
+		Ok(result) => result,
+		Err(error) => {
+			eprintln!("Verification failed: {}", error);
+			return false;
+		}
+	};
+	println!("Score: {}", result.score);
+	println!("Lines: {}", result.lines);
+	println!("Time: {}", format_ticks(result.ticks));
+	match replay.verify(&result) {
+		Ok(()) => {
+			println!("\nThe replay matches its claimed result.");
+			true
+		}
+		Err(error) => {
+			eprintln!("\nVerification failed: {}", error);
+			false
+		}
+	}
+}
+
+/// Loads the newest saved replays, newest first, keeping the ones that could not be loaded as
+/// errors.
+pub fn load_replays(count: usize) -> Vec<Result<Replay, String>> {
+	let mut paths: Vec<PathBuf> = replays_directory()
+		.and_then(|directory| fs::read_dir(directory).ok())
+		.map(|entries| {
+			entries
+				.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+				.filter(|path| path.extension().is_some_and(|extension| extension == "replay"))
+				.collect()
+		})
+		.unwrap_or_default();
+	// File names start with the date, so they sort by age.
+	paths.sort();
+	paths.iter().rev().take(count).map(|path| load(path)).collect()
+}
+
+impl App {
+	/// Starts recording the game from its current state.
+	pub fn start_replay(&mut self) {
+		self.replay = Replay {
+			version: env!("CARGO_PKG_VERSION").to_string(),
+			rules: RULES_VERSION,
+			date: Local::now().naive_local().trunc_subsecs(0),
+			mode: self.mode,
+			ruleset: self.ruleset(),
+			seed: self.seed,
+			level: self.start_level,
+			fade_ticks: self.fade_ticks,
+			practice: self.practice,
+			key_releases: self.key_releases,
+			handling: self.config.handling,
+			result: None,
+			inputs: vec![],
+		};
+	}
+
+	/// Records an input and applies it to the game. Inputs are ignored during the countdown of a
+	/// resumed game.
+	pub fn input(&mut self, input: ReplayInput) {
+		if self.countdown > 0 {
+			return;
+		}
+		self.replay.inputs.push((self.replay_ticks, input));
+		self.play_input(input);
+	}
+
+	/// Applies an input to the game.
+	pub fn play_input(&mut self, input: ReplayInput) {
+		match input {
+			ReplayInput::Press(action) => match action {
+				Action::Left => self.press_shift(-1),
+				Action::Right => self.press_shift(1),
+				Action::DasLeft => self.das_tetromino(-1),
+				Action::DasRight => self.das_tetromino(1),
+				Action::RotateCw => self.rotate_tetromino(true),
+				Action::RotateCcw => self.rotate_tetromino(false),
+				Action::SoftDrop => self.soft_drop(),
+				Action::HardDrop => self.hard_drop(),
+				Action::Hold => self.hold_tetromino(),
+				_ => {}
+			},
+			ReplayInput::Release(Action::Left) => self.release_shift(-1),
+			ReplayInput::Release(Action::Right) => self.release_shift(1),
+			ReplayInput::Release(_) => {}
+			ReplayInput::Undo => {
+				self.undo();
+			}
+			ReplayInput::Redo => {
+				self.redo();
+			}
+			ReplayInput::Handling(handling) => self.config.handling = handling,
+		}
+	}
+
+	/// Records a change of the handling made in the settings.
+	pub fn record_handling(&mut self) {
+		if self.config.handling != self.replay.last_handling() {
+			self.input(ReplayInput::Handling(self.config.handling));
+		}
+	}
+
+	/// Saves the replay of the finished game to the replays directory. Recorded games save theirs
+	/// when they end, and the results screen can save it again, such as after a failure.
+	pub fn save_replay(&mut self) {
+		if !self.mode.has_replays() {
+			self.status_message = Some(String::from("Replays are not kept for this mode"));
+			return;
+		}
+		self.replay.result = Some(ReplayResult {
+			ticks: self.elapsed_ticks,
+			score: self.score,
+			lines: self.stats.lines,
+			level: self.level,
+		});
+		let name = self.replay.file_name();
+		let result = storage::path(REPLAYS_DIRECTORY).and_then(|directory| {
+			storage::write_path(&directory.join(&name), self.replay.to_file_string())
+		});
+		self.status_message = Some(match result {
+			Ok(()) => format!("Replay saved as {}", name),
+			Err(error) => format!("Could not save the replay: {}", error),
+		});
+	}
+}
@@ -0,0 +1,341 @@
+use crate::{
+	app::{
+		format_ticks,
+		App,
+		TICK_RATE,
+	},
+	config::Action,
+	daily::{
+		self,
+		DailyChallenge,
+		DailyResult,
+	},
+	mode::GameMode,
+	opener::OpenerState,
+	puzzle::{
+		self,
+		Puzzle,
+		PuzzleStatus,
+	},
+	replay::ReplayInput,
+	settings::SettingsMenu,
+};
+
+/// Seconds counted down before a paused game goes on.
+const COUNTDOWN_SECONDS: u64 = 3;
+/// Items of the title screen.
+const TITLE_ITEMS: [&str; 5] = ["Play", "Settings", "Leaderboard", "Replays", "Quit"];
+/// Items of the pause menu.
+const PAUSE_ITEMS: [&str; 4] = ["Resume", "Restart", "Settings", "Quit to title"];
+/// Items of the results screen.
+const RESULTS_ITEMS: [&str; 4] = ["Retry", "Retry same seed", "Save replay", "Title"];
+
+//----------[ Structs ]----------//
+/// What the application shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Screen {
+	Title,
+	/// Picks the mode of a new game.
+	ModeSelect,
+	/// The settings menu, over the screen it was opened from.
+	Settings,
+	/// A game is being played.
+	#[default]
+	Playing,
+	Paused,
+	/// Summary of a finished game.
+	Results,
+	/// A replay is played back.
+	Watching,
+	/// The boards of every mode.
+	Leaderboard,
+	Replays,
+}
+//-------------------------------//
+
+impl Screen {
+	/// Is the screen a list of items to pick from?
+	pub fn is_menu(&self) -> bool {
+		matches!(
+			self,
+			Screen::Title | Screen::ModeSelect | Screen::Results | Screen::Leaderboard | Screen::Replays
+		)
+	}
+
+	/// Returns the title of the screen.
+	pub fn title(&self) -> &'static str {
+		match self {
+			Screen::Title => "┤ TETRS ├",
+			Screen::ModeSelect => "┤ MODE ├",
+			Screen::Settings => "┤ SETTINGS ├",
+			Screen::Playing => "┤ TETRS ├",
+			Screen::Paused => "┤ PAUSED ├",
+			Screen::Results => "┤ RESULTS ├",
+			Screen::Watching => "┤ REPLAY ├",
+			Screen::Leaderboard => "┤ LEADERBOARD ├",
+			Screen::Replays => "┤ REPLAYS ├",
+		}
+	}
+}
+
+/// Returns the modes that have a leaderboard, in menu order.
+fn leaderboard_modes() -> Vec<GameMode> {
+	GameMode::ALL
+		.into_iter()
+		.filter(GameMode::has_leaderboard)
+		.collect()
+}
+
+impl App {
+	//----------[ Screens ]----------//
+	/// Shows a screen, selecting its first item.
+	pub fn show_screen(&mut self, screen: Screen) {
+		self.screen = screen;
+		self.menu_selected = 0;
+	}
+
+	/// Starts a new game of a mode with the options of the config, keeping the options, the
+	/// leaderboard and what is known about the terminal. The Puzzle mode plays the given puzzle, or
+	/// else the first unsolved one of the pack, the Sandbox mode starts from the given setup, and the
+	/// tetrominos come from the given seed, or else a random one. Master and Daily games always start
+	/// at the first level, so that everyone plays them alike.
+	pub fn start_game(&mut self, mode: GameMode, puzzle: Option<Puzzle>, seed: Option<u64>) {
+		let mut game = match (mode, seed) {
+			(GameMode::Daily, _) => {
+				let challenge = DailyChallenge::today();
+				let mut game = App::with_seed(mode, challenge.seed);
+				game.daily = Some(challenge);
+				game
+			}
+			(_, Some(seed)) => App::with_seed(mode, seed),
+			(_, None) => App::with_mode(mode),
+		};
+		let options = &self.config.game;
+		if !matches!(mode, GameMode::Master | GameMode::Daily) {
+			game.level = options.level;
+			game.start_level = game.level;
+		}
+		game.fade_ticks = (options.fade as u64 * 1000 / TICK_RATE) as u32;
+		game.practice = options.practice;
+		game.record_results = !options.practice;
+		if mode == GameMode::Puzzle {
+			if let Some(puzzle) = puzzle.or_else(puzzle::next_unsolved) {
+				game.load_puzzle(puzzle);
+			}
+		} else if let (GameMode::Sandbox, Some(setup)) = (mode, &puzzle) {
+			game.clear_falling();
+			game.apply_setup(setup);
+			game.spawn_next_tetromino();
+		}
+		if mode == GameMode::Opener {
+			game.opener = Some(OpenerState::new(self.config.opener(), options.auto_undo));
+		}
+
+		game.config = std::mem::take(&mut self.config);
+		game.file_config = std::mem::take(&mut self.file_config);
+		game.leaderboard = std::mem::take(&mut self.leaderboard);
+		game.key_releases = self.key_releases;
+		game.status_message = self.status_message.take();
+		game.fumen_export = self.fumen_export.take();
+		// The scored attempt of the day is used up as soon as it starts, even if it is abandoned.
+		if let Some(challenge) = game
+			.daily
+			.filter(|challenge| game.record_results && !challenge.practice)
+		{
+			let result = daily::record_result(&DailyResult {
+				date: challenge.date,
+				score: 0,
+				level: game.level,
+				ticks: 0,
+			});
+			game.report_save_error(result);
+		}
+		game.start_replay();
+		*self = game;
+	}
+
+	/// Starts the game over in the same mode, on the same puzzle.
+	pub fn restart(&mut self) {
+		let puzzle = self.puzzle.as_ref().map(|state| state.puzzle.clone());
+		self.start_game(self.mode, puzzle, None);
+	}
+
+	/// Starts the game over with the same tetrominos.
+	pub fn restart_same_seed(&mut self) {
+		let puzzle = self.puzzle.as_ref().map(|state| state.puzzle.clone());
+		self.start_game(self.mode, puzzle, Some(self.seed));
+	}
+
+	/// Shows the results once a game that keeps records is over.
+	pub fn show_results(&mut self) {
+		if self.game_over && self.record_results && self.mode.keeps_records() {
+			self.show_screen(Screen::Results);
+		}
+	}
+
+	/// Opens the settings menu over the current screen.
+	pub fn open_settings(&mut self) {
+		self.settings = SettingsMenu {
+			back: self.screen,
+			..SettingsMenu::default()
+		};
+		self.screen = Screen::Settings;
+	}
+
+	/// Pauses the game being played, which also stops a countdown.
+	pub fn pause(&mut self) {
+		if self.screen == Screen::Playing && !self.game_over {
+			self.show_screen(Screen::Paused);
+			self.countdown = 0;
+			// Keys let go of while paused are never reported as released.
+			if let Some(held) = self.held_shift {
+				let action = if held.direction < 0 {
+					Action::Left
+				} else {
+					Action::Right
+				};
+				self.input(ReplayInput::Release(action));
+			}
+		}
+	}
+
+	/// Goes back to the paused game after a countdown.
+	pub fn resume(&mut self) {
+		if self.screen == Screen::Paused {
+			self.screen = Screen::Playing;
+			self.countdown = (COUNTDOWN_SECONDS * 1000 / TICK_RATE) as u32;
+		}
+	}
+
+	/// Pauses or resumes the game.
+	pub fn toggle_pause(&mut self) {
+		match self.screen {
+			Screen::Paused => self.resume(),
+			_ => self.pause(),
+		}
+	}
+
+	/// Returns the second of the countdown that is shown, if one is going.
+	pub fn countdown_seconds(&self) -> Option<u64> {
+		(self.countdown > 0).then(|| (self.countdown as u64 * TICK_RATE).div_ceil(1000))
+	}
+
+	/// Goes back to the screen before, quitting from the title screen.
+	pub fn back(&mut self) {
+		match self.screen {
+			Screen::Title => self.quit(),
+			Screen::Settings => self.close_settings(),
+			Screen::Paused => self.resume(),
+			Screen::Watching => self.close_viewer(),
+			_ => self.show_screen(Screen::Title),
+		}
+	}
+	//-------------------------------//
+
+	//----------[ Menus ]----------//
+	/// Returns the items of the current screen.
+	pub fn menu_items(&self) -> Vec<String> {
+		let items: Vec<&str> = match self.screen {
+			Screen::Title => TITLE_ITEMS.to_vec(),
+			Screen::ModeSelect => GameMode::ALL.map(|mode| mode.name()).to_vec(),
+			Screen::Paused => PAUSE_ITEMS.to_vec(),
+			Screen::Results => RESULTS_ITEMS.to_vec(),
+			Screen::Leaderboard => leaderboard_modes()
+				.into_iter()
+				.map(|mode| mode.name())
+				.collect(),
+			Screen::Replays => return self.replay_names(),
+			_ => vec![],
+		};
+		items.into_iter().map(String::from).collect()
+	}
+
+	/// Selects the item some rows up (-1) or down (1), wrapping around.
+	pub fn select_menu_item(&mut self, delta: i32) {
+		let count = self.menu_items().len() as i32;
+		if count > 0 {
+			self.menu_selected = (self.menu_selected as i32 + delta).rem_euclid(count) as usize;
+		}
+	}
+
+	/// Does what the selected item of the current screen stands for.
+	pub fn activate_menu_item(&mut self) {
+		let selected = self.menu_selected;
+		let item = self.menu_items().get(selected).cloned();
+		match (self.screen, item.as_deref()) {
+			(Screen::Title, Some("Play")) => self.show_screen(Screen::ModeSelect),
+			(Screen::Title, Some("Settings")) => self.open_settings(),
+			(Screen::Title, Some("Leaderboard")) => self.show_screen(Screen::Leaderboard),
+			(Screen::Title, Some("Replays")) => self.open_replays(),
+			(Screen::Title, Some("Quit")) => self.quit(),
+			(Screen::ModeSelect, Some(_)) => self.start_game(GameMode::ALL[selected], None, None),
+			(Screen::Paused, Some("Resume")) => self.resume(),
+			(Screen::Paused, Some("Restart")) => self.restart(),
+			(Screen::Paused, Some("Settings")) => self.open_settings(),
+			(Screen::Paused, Some("Quit to title")) => self.show_screen(Screen::Title),
+			(Screen::Results, Some("Retry")) => self.restart(),
+			(Screen::Results, Some("Retry same seed")) => self.restart_same_seed(),
+			(Screen::Results, Some("Save replay")) => self.save_replay(),
+			(Screen::Results, Some("Title")) => self.show_screen(Screen::Title),
+			(Screen::Replays, Some(_)) => {
+				if let Some(Ok(replay)) = self.replay_list.get(selected).cloned() {
+					self.watch_replay(replay);
+				}
+			}
+			_ => {}
+		}
+	}
+
+	/// Returns what is shown below the items of the current screen.
+	pub fn menu_details(&self) -> String {
+		match self.screen {
+			Screen::Title => String::from("[Up/Down] select [Enter] pick\n[Esc] quit"),
+			Screen::ModeSelect => GameMode::ALL[self.menu_selected].description().to_string(),
+			Screen::Results => self.results_string(),
+			Screen::Leaderboard => leaderboard_modes()
+				.get(self.menu_selected)
+				.map_or(String::new(), |&mode| self.leaderboard.mode_string(mode)),
+			Screen::Paused => String::from("[Esc] resume"),
+			Screen::Replays => self.replay_details(),
+			_ => String::new(),
+		}
+	}
+
+	/// Returns the summary of the finished game.
+	pub fn results_string(&self) -> String {
+		let outcome = if self
+			.puzzle
+			.as_ref()
+			.is_some_and(|state| state.status == PuzzleStatus::Solved)
+		{
+			"SOLVED"
+		} else {
+			"GAME OVER"
+		};
+		let mut result = format!(
+			"{}{}\n{}\nScore: {}\nLines: {}\nLevel: {}\nTime: {}\nPPS: {:.2} APM: {:.1}\nPieces: \
+			 {}\n{}\n{}\n\n[r] retry [R] same seed",
+			outcome,
+			if self.personal_best {
+				" - NEW PERSONAL BEST"
+			} else {
+				""
+			},
+			self.mode.name(),
+			self.score,
+			self.stats.lines,
+			self.level,
+			format_ticks(self.elapsed_ticks),
+			self.stats.pieces_per_second(self.elapsed_ticks),
+			self.stats.attack_per_minute(self.elapsed_ticks),
+			self.stats.pieces,
+			self.stats.distribution_string(),
+			self.stats.clears_string()
+		);
+		if let Some(message) = &self.status_message {
+			result += &format!("\n{}", message);
+		}
+		result
+	}
+	//-----------------------------//
+}
@@ -0,0 +1,262 @@
+use std::ops::RangeInclusive;
+
+use crossterm::event::KeyCode;
+
+use crate::{
+	app::App,
+	config::{
+		Action,
+		Border,
+		Config,
+		Key,
+		ACCENT_RANGE,
+		ARR_RANGE,
+		DAS_RANGE,
+		PREVIEW_RANGE,
+		SDF_RANGE,
+	},
+	screen::Screen,
+};
+
+/// Milliseconds DAS and ARR change by per step.
+const HANDLING_STEP: i32 = 10;
+
+//----------[ Structs ]----------//
+/// An option of the settings menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+	Das,
+	Arr,
+	Sdf,
+	/// The key bound to an action.
+	Key(Action),
+	Accent,
+	Border,
+	Ghost,
+	Preview,
+	Buttons,
+	/// Returns every option to its default.
+	Defaults,
+}
+
+/// State of the settings menu.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SettingsMenu {
+	/// Index of the selected option in [`SettingsMenu::settings`].
+	pub selected: usize,
+	/// Is the next pressed key bound to the selected action?
+	pub binding: bool,
+	/// Screen the menu was opened from.
+	pub back: Screen,
+}
+//-------------------------------//
+
+/// Steps a value by a delta, keeping it in range.
+fn step(value: u32, delta: i32, range: RangeInclusive<u32>) -> u32 {
+	(value as i64 + delta as i64).clamp(*range.start() as i64, *range.end() as i64) as u32
+}
+
+impl Setting {
+	/// Returns the name of the option.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Setting::Das => "DAS",
+			Setting::Arr => "ARR",
+			Setting::Sdf => "SDF",
+			Setting::Key(action) => action.name(),
+			Setting::Accent => "Accent color",
+			Setting::Border => "Border",
+			Setting::Ghost => "Ghost",
+			Setting::Preview => "Preview",
+			Setting::Buttons => "Buttons",
+			Setting::Defaults => "Reset to defaults",
+		}
+	}
+
+	/// Returns the value of the option.
+	pub fn value(&self, config: &Config) -> String {
+		let on_off = |value: bool| String::from(if value { "on" } else { "off" });
+		match self {
+			Setting::Das => format!("{} ms", config.handling.das),
+			Setting::Arr => format!("{} ms", config.handling.arr),
+			Setting::Sdf if config.handling.sdf == 0 => String::from("instant"),
+			Setting::Sdf => format!("{}x", config.handling.sdf),
+			Setting::Key(action) => config
+				.controls
+				.bindings()
+				.into_iter()
+				.find(|&(bound, _)| bound == *action)
+				.map_or(String::new(), |(_, keys)| {
+					keys.iter()
+						.map(Key::to_string)
+						.collect::<Vec<String>>()
+						.join(" ")
+				}),
+			Setting::Accent => config.theme.accent.to_string(),
+			Setting::Border => format!("{:?}", config.theme.border),
+			Setting::Ghost => on_off(config.layout.ghost),
+			Setting::Preview => config.layout.preview.to_string(),
+			Setting::Buttons => on_off(config.layout.buttons),
+			Setting::Defaults => String::new(),
+		}
+	}
+
+	/// Changes the value of the option by some steps, flipping switches either way.
+	pub fn adjust(&self, config: &mut Config, delta: i32) {
+		let handling = &mut config.handling;
+		match self {
+			Setting::Das => handling.das = step(handling.das, delta * HANDLING_STEP, DAS_RANGE),
+			Setting::Arr => handling.arr = step(handling.arr, delta * HANDLING_STEP, ARR_RANGE),
+			Setting::Sdf => handling.sdf = step(handling.sdf, delta, SDF_RANGE),
+			Setting::Accent => {
+				config.theme.accent = step(config.theme.accent as u32, delta, ACCENT_RANGE) as u8;
+			}
+			Setting::Preview => {
+				config.layout.preview = step(config.layout.preview, delta, PREVIEW_RANGE);
+			}
+			Setting::Border | Setting::Ghost | Setting::Buttons => {
+				self.activate(config);
+			}
+			Setting::Key(_) | Setting::Defaults => {}
+		}
+	}
+
+	/// Copies the value of the option from one config to another.
+	pub fn copy(&self, from: &Config, to: &mut Config) {
+		match self {
+			Setting::Das => to.handling.das = from.handling.das,
+			Setting::Arr => to.handling.arr = from.handling.arr,
+			Setting::Sdf => to.handling.sdf = from.handling.sdf,
+			Setting::Key(_) => to.controls = from.controls.clone(),
+			Setting::Accent => to.theme.accent = from.theme.accent,
+			Setting::Border => to.theme.border = from.theme.border,
+			Setting::Ghost => to.layout.ghost = from.layout.ghost,
+			Setting::Preview => to.layout.preview = from.layout.preview,
+			Setting::Buttons => to.layout.buttons = from.layout.buttons,
+			Setting::Defaults => {}
+		}
+	}
+
+	/// Flips a switch or resets the options, returning whether the option waits for a key instead.
+	pub fn activate(&self, config: &mut Config) -> bool {
+		match self {
+			Setting::Border => {
+				config.theme.border = match config.theme.border {
+					Border::Plain => Border::Rounded,
+					Border::Rounded => Border::Plain,
+				};
+			}
+			Setting::Ghost => config.layout.ghost = !config.layout.ghost,
+			Setting::Buttons => config.layout.buttons = !config.layout.buttons,
+			Setting::Defaults => {
+				*config = Config {
+					game: config.game.clone(),
+					..Config::default()
+				};
+			}
+			Setting::Key(_) => return true,
+			_ => {}
+		}
+		false
+	}
+}
+
+impl SettingsMenu {
+	/// Returns every option, with the name of the group it starts if any.
+	pub fn settings() -> Vec<(Option<&'static str>, Setting)> {
+		let mut settings = vec![
+			(Some("Handling"), Setting::Das),
+			(None, Setting::Arr),
+			(None, Setting::Sdf),
+		];
+		for (index, action) in Action::ALL.into_iter().enumerate() {
+			settings.push(((index == 0).then_some("Controls"), Setting::Key(action)));
+		}
+		settings.extend([
+			(Some("Theme"), Setting::Accent),
+			(None, Setting::Border),
+			(Some("Layout"), Setting::Ghost),
+			(None, Setting::Preview),
+			(None, Setting::Buttons),
+			(Some(""), Setting::Defaults),
+		]);
+		settings
+	}
+
+	/// Returns the selected option.
+	pub fn setting(&self) -> Setting {
+		Self::settings()[self.selected].1
+	}
+}
+
+impl App {
+	/// Closes the settings menu and saves the options of the config file with the changes made in
+	/// the menu, leaving out the ones of the command line.
+	pub fn close_settings(&mut self) {
+		self.screen = self.settings.back;
+		self.record_handling();
+		let result = self.file_config.save();
+		self.report_save_error(result);
+	}
+
+	/// Selects the option some rows up (-1) or down (1), wrapping around.
+	pub fn select_setting(&mut self, delta: i32) {
+		let count = SettingsMenu::settings().len() as i32;
+		self.settings.selected = (self.settings.selected as i32 + delta).rem_euclid(count) as usize;
+	}
+
+	/// Changes the selected option by some steps.
+	pub fn adjust_setting(&mut self, delta: i32) {
+		let setting = self.settings.setting();
+		setting.adjust(&mut self.config, delta);
+		setting.copy(&self.config, &mut self.file_config);
+	}
+
+	/// Flips the selected switch, or waits for a key to bind to the selected action.
+	pub fn activate_setting(&mut self) {
+		let setting = self.settings.setting();
+		self.settings.binding = setting.activate(&mut self.config);
+		if setting == Setting::Defaults {
+			setting.activate(&mut self.file_config);
+		}
+		setting.copy(&self.config, &mut self.file_config);
+	}
+
+	/// Binds a key to the selected action, or cancels on `Esc`.
+	pub fn bind_setting(&mut self, code: KeyCode) {
+		if let (Setting::Key(action), false) = (self.settings.setting(), code == KeyCode::Esc) {
+			self.config.controls.bind(action, Key(code));
+			Setting::Key(action).copy(&self.config, &mut self.file_config);
+		}
+		self.settings.binding = false;
+	}
+
+	/// Returns the settings menu as lines, with the index of the selected line.
+	pub fn settings_string(&self) -> (String, usize) {
+		let menu = self.settings;
+		let mut result = String::new();
+		let mut selected_line = 0;
+		let mut line = 0;
+		for (index, (group, setting)) in SettingsMenu::settings().into_iter().enumerate() {
+			if let Some(group) = group {
+				result += &format!("{}\n", group);
+				line += 1;
+			}
+			let value = if index == menu.selected && menu.binding {
+				String::from("press a key")
+			} else {
+				setting.value(&self.config)
+			};
+			let marker = if index == menu.selected {
+				selected_line = line;
+				">"
+			} else {
+				" "
+			};
+			result += &format!("{} {:<13}{}\n", marker, setting.name(), value);
+			line += 1;
+		}
+		result += "\n[Up/Down] select [Left/Right] change\n[Enter] bind/switch [Esc] save";
+		(result, selected_line)
+	}
+}
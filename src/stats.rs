@@ -0,0 +1,198 @@
+use crate::{
+	app::{
+		format_ticks,
+		App,
+		LockResult,
+		TICK_RATE,
+	},
+	tetromino::TetrominoKind,
+};
+
+/// Lines sent by clearing 0 to 4 lines.
+const LINE_ATTACK: [u32; 5] = [0, 0, 1, 2, 4];
+/// Lines sent by T-spins clearing 0 to 3 lines.
+const T_SPIN_ATTACK: [u32; 4] = [0, 2, 4, 6];
+/// Extra lines sent by a combo, by the clears in a row before the current one.
+const COMBO_ATTACK: [u32; 12] = [0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5];
+/// Extra lines sent by a Tetris or T-spin following another one.
+const BACK_TO_BACK_ATTACK: u32 = 1;
+/// Extra lines sent by a perfect clear.
+const PERFECT_CLEAR_ATTACK: u32 = 10;
+
+//----------[ Structs ]----------//
+/// Statistics of the game so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GameStats {
+	/// Tetrominos locked.
+	pub pieces: u32,
+	/// Inputs that moved, rotated, dropped or held a tetromino.
+	pub keys: u32,
+	pub lines: u32,
+	pub tetrises: u32,
+	pub t_spins: u32,
+	/// Lines sent under the Guideline attack table.
+	pub attack: u32,
+	/// Clears in a row, 0 after a tetromino that cleared nothing.
+	pub combo: u32,
+	/// Longest run of clears in a row.
+	pub max_combo: u32,
+	/// Was the last clear a Tetris or a T-spin?
+	pub back_to_back: bool,
+	/// Tetrominos locked of each type, in the order of [`TetrominoKind::ALL`].
+	pub kinds: [u32; 7],
+	/// Singles, doubles, triples and Tetrises.
+	pub clears: [u32; 4],
+	/// T-spins clearing 0 to 3 lines.
+	pub t_spin_clears: [u32; 4],
+	pub perfect_clears: u32,
+}
+//-------------------------------//
+
+/// Returns the minutes a number of ticks takes.
+fn minutes(ticks: u32) -> f64 {
+	ticks as f64 * TICK_RATE as f64 / 60_000.0
+}
+
+/// Divides, counting nothing divided by nothing as 0.
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+	if denominator > 0.0 {
+		numerator / denominator
+	} else {
+		0.0
+	}
+}
+
+impl GameStats {
+	/// Counts a locked tetromino, returning the lines it sends.
+	pub fn record_lock(&mut self, result: &LockResult) -> u32 {
+		self.pieces += 1;
+		if let Some(kind) = result.tetromino {
+			self.kinds[kind as usize] += 1;
+		}
+		if result.t_spin {
+			self.t_spins += 1;
+			self.t_spin_clears[result.lines.min(3) as usize] += 1;
+		}
+		if result.lines == 0 {
+			self.combo = 0;
+			return 0;
+		}
+
+		self.lines += result.lines;
+		if !result.t_spin {
+			self.clears[result.lines.min(4) as usize - 1] += 1;
+		}
+		if result.lines >= 4 {
+			self.tetrises += 1;
+		}
+		let mut attack = if result.t_spin {
+			T_SPIN_ATTACK[result.lines.min(3) as usize]
+		} else {
+			LINE_ATTACK[result.lines.min(4) as usize]
+		};
+		let difficult = result.t_spin || result.lines >= 4;
+		if difficult && self.back_to_back {
+			attack += BACK_TO_BACK_ATTACK;
+		}
+		self.back_to_back = difficult;
+		attack += COMBO_ATTACK[(self.combo as usize).min(COMBO_ATTACK.len() - 1)];
+		self.combo += 1;
+		self.max_combo = self.max_combo.max(self.combo);
+		if result.perfect_clear {
+			self.perfect_clears += 1;
+			attack += PERFECT_CLEAR_ATTACK;
+		}
+		self.attack += attack;
+		attack
+	}
+
+	/// Returns the tetrominos locked per second over the given ticks.
+	pub fn pieces_per_second(&self, ticks: u32) -> f64 {
+		ratio(self.pieces as f64, minutes(ticks) * 60.0)
+	}
+
+	/// Returns the inputs per locked tetromino.
+	pub fn keys_per_piece(&self) -> f64 {
+		ratio(self.keys as f64, self.pieces as f64)
+	}
+
+	/// Returns the lines sent per minute over the given ticks.
+	pub fn attack_per_minute(&self, ticks: u32) -> f64 {
+		ratio(self.attack as f64, minutes(ticks))
+	}
+
+	/// Returns the share of cleared lines that were cleared by Tetrises.
+	pub fn tetris_rate(&self) -> f64 {
+		ratio(self.tetrises as f64 * 4.0, self.lines as f64)
+	}
+
+	/// Returns the lines sent per line cleared.
+	pub fn efficiency(&self) -> f64 {
+		ratio(self.attack as f64, self.lines as f64)
+	}
+
+	/// Returns the tetrominos locked of each type.
+	pub fn distribution_string(&self) -> String {
+		TetrominoKind::ALL
+			.iter()
+			.zip(self.kinds)
+			.map(|(kind, count)| format!("{}{}", kind.letter(), count))
+			.collect::<Vec<String>>()
+			.join(" ")
+	}
+
+	/// Returns the clears of each type.
+	pub fn clears_string(&self) -> String {
+		let [singles, doubles, triples, tetrises] = self.clears;
+		let [t_spins, t_spin_singles, t_spin_doubles, t_spin_triples] = self.t_spin_clears;
+		format!(
+			"Single {} Double {}\nTriple {} Tetris {}\nT-spin {} TSS {} TSD {} TST {}\nPerfect clear {}",
+			singles,
+			doubles,
+			triples,
+			tetrises,
+			t_spins,
+			t_spin_singles,
+			t_spin_doubles,
+			t_spin_triples,
+			self.perfect_clears
+		)
+	}
+}
+
+impl App {
+	/// Shows or hides the detailed statistics.
+	pub fn toggle_stats(&mut self) {
+		self.show_stats = !self.show_stats;
+	}
+
+	/// Returns the statistics shown below the status.
+	pub fn stats_summary(&self) -> String {
+		format!(
+			"PPS: {:.2} APM: {:.1} [i] stats [l] board",
+			self.stats.pieces_per_second(self.elapsed_ticks),
+			self.stats.attack_per_minute(self.elapsed_ticks)
+		)
+	}
+
+	/// Returns the detailed statistics.
+	pub fn stats_string(&self) -> String {
+		let stats = &self.stats;
+		format!(
+			"Time: {}\nPieces: {} ({:.2} PPS)\nKeys: {} ({:.2} KPP)\nLines: {}\nTetris rate: \
+			 {:.0}%\nT-spins: {}\nAttack: {} ({:.1} APM)\nEfficiency: {:.2}\nFinesse faults: {}",
+			format_ticks(self.elapsed_ticks),
+			stats.pieces,
+			stats.pieces_per_second(self.elapsed_ticks),
+			stats.keys,
+			stats.keys_per_piece(),
+			stats.lines,
+			stats.tetris_rate() * 100.0,
+			stats.t_spins,
+			stats.attack,
+			stats.attack_per_minute(self.elapsed_ticks),
+			stats.efficiency(),
+			self.finesse.faults
+		)
+	}
+}
@@ -0,0 +1,142 @@
+//! Everything kept between games lives in the data directory, `$XDG_DATA_HOME/tetrs` on Linux.
+//! Files are replaced by writing a temporary file next to them and renaming it over the old one,
+//! so an interrupted write never leaves a half written file behind.
+//!
+//! Older versions kept their high score in the home directory. It is moved over on the first start.
+
+use std::{
+	cell::RefCell,
+	fs,
+	io::{
+		self,
+		Write,
+	},
+	path::{
+		Path,
+		PathBuf,
+	},
+};
+
+use crate::app::App;
+
+/// Name of the directory in the data directory of the platform.
+const DATA_DIRECTORY: &str = "tetrs";
+
+/// The high score file of older versions in the home directory.
+const LEGACY_HIGHSCORE_FILE: &str = ".tetrs_highscore";
+
+/// The single high score of older versions, until it is moved onto the leaderboard.
+pub const HIGHSCORE_FILE: &str = "highscore";
+
+thread_local! {
+	/// Data directory used instead of the one of the platform on this thread.
+	static DATA_DIRECTORY_OVERRIDE: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Returns the data directory. Tests have none unless they set one with
+/// [`override_data_directory`], so they never touch the files of the player.
+pub fn data_directory() -> Option<PathBuf> {
+	DATA_DIRECTORY_OVERRIDE
+		.with_borrow(Clone::clone)
+		.or_else(|| match cfg!(test) {
+			true => None,
+			false => dirs::data_dir().map(|path| path.join(DATA_DIRECTORY)),
+		})
+}
+
+/// Uses another data directory on the current thread, or the one of the platform again.
+pub fn override_data_directory(directory: Option<PathBuf>) {
+	DATA_DIRECTORY_OVERRIDE.set(directory);
+}
+
+/// Returns the path of a file in the data directory.
+pub fn path(name: &str) -> io::Result<PathBuf> {
+	data_directory()
+		.map(|directory| directory.join(name))
+		.ok_or_else(|| io::Error::other("No data directory found"))
+}
+
+/// Reads a file of the data directory, which is `None` if it does not exist yet.
+pub fn read(name: &str) -> io::Result<Option<String>> {
+	read_path(&path(name)?)
+}
+
+/// Reads a file, which is `None` if it does not exist.
+pub fn read_path(path: &Path) -> io::Result<Option<String>> {
+	match fs::read_to_string(path) {
+		Ok(contents) => Ok(Some(contents)),
+		Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+		Err(error) => Err(error),
+	}
+}
+
+/// Replaces a file of the data directory.
+pub fn write(name: &str, contents: &str) -> io::Result<()> {
+	write_path(&path(name)?, contents)
+}
+
+/// Replaces a file by writing a temporary file and renaming it, creating its directory first.
+pub fn write_path(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+	let directory = path
+		.parent()
+		.ok_or_else(|| io::Error::other("Invalid path"))?;
+	fs::create_dir_all(directory)?;
+	let mut temporary_name = path.file_name().unwrap_or_default().to_os_string();
+	temporary_name.push(".tmp");
+	let temporary = directory.join(temporary_name);
+	let mut file = fs::File::create(&temporary)?;
+	file.write_all(contents.as_ref())?;
+	file.sync_all()?;
+	fs::rename(&temporary, path)
+}
+
+/// Adds a line to the end of a file of the data directory.
+pub fn append_line(name: &str, line: &str) -> io::Result<()> {
+	let path = path(name)?;
+	if let Some(directory) = path.parent() {
+		fs::create_dir_all(directory)?;
+	}
+	let mut file = fs::OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(path)?;
+	writeln!(file, "{}", line)
+}
+
+/// Moves the high score of older versions from the home directory to the data directory.
+pub fn migrate() -> io::Result<Vec<PathBuf>> {
+	match (dirs::home_dir(), data_directory()) {
+		(Some(home), Some(data)) => migrate_between(&home, &data),
+		_ => Ok(vec![]),
+	}
+}
+
+/// Moves the legacy high score from one directory to another, returning the moved files. A file
+/// that already exists in the new directory is left alone.
+pub fn migrate_between(home: &Path, data: &Path) -> io::Result<Vec<PathBuf>> {
+	let from = home.join(LEGACY_HIGHSCORE_FILE);
+	let to = data.join(HIGHSCORE_FILE);
+	if !from.exists() || to.exists() {
+		return Ok(vec![]);
+	}
+	fs::create_dir_all(data)?;
+	move_file(&from, &to)?;
+	Ok(vec![to])
+}
+
+/// Moves a file, copying it when it cannot be renamed across filesystems.
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+	fs::rename(from, to).or_else(|_| {
+		fs::copy(from, to)?;
+		fs::remove_file(from)
+	})
+}
+
+impl App {
+	/// Shows why something could not be saved.
+	pub fn report_save_error(&mut self, result: io::Result<()>) {
+		if let Err(error) = result {
+			self.status_message = Some(format!("Could not save: {}", error));
+		}
+	}
+}
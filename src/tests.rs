@@ -1,16 +1,106 @@
 #[cfg(test)]
 mod unit_tests {
 	use crate::{
-		app::App,
-		tetromino::TETROMINO_SHAPES,
+		cast::{
+			self,
+			CAST_HEIGHT,
+			CAST_WIDTH,
+		},
+		app::{
+			App,
+			LockResult,
+			Phase,
+			PlayFieldCell,
+			PLAYFIELD_PADDING,
+		},
+		config::{
+			Action,
+			Config,
+			Key,
+			DEFAULT_CONFIG,
+		},
+		finesse::Input,
+		handler::handle_key_events,
+		history::Snapshot,
+		fumen::{
+			self,
+			FumenPage,
+			FumenPiece,
+		},
+		picture::{
+			self,
+			CELL_SIZE,
+		},
+		leaderboard::{
+			Leaderboard,
+			LeaderboardEntry,
+			LEADERBOARD_SIZE,
+		},
+		settings::{
+			Setting,
+			SettingsMenu,
+		},
+		screen::Screen,
+		replay::{
+			self,
+			Replay,
+			ReplayInput,
+			ReplayResult,
+			RULES_VERSION,
+		},
+		records::{
+			self,
+			GameRecord,
+		},
+		stats::GameStats,
+		storage,
+		daily::{
+			self,
+			DailyChallenge,
+		},
+		master::{
+			self,
+			Grade,
+			MasterState,
+		},
+		mode::GameMode,
+		opener::{
+			self,
+			OpenerState,
+			OPENERS,
+		},
+		pc::{
+			self,
+			PC_HEIGHT,
+		},
+		puzzle::{
+			self,
+			Goal,
+			Puzzle,
+			PuzzleStatus,
+		},
+		tetromino::{
+			TetrominoKind,
+			TETROMINO_SHAPES,
+		},
 	};
+	use chrono::NaiveDate;
+	use crossterm::event::{
+		KeyCode,
+		KeyEvent,
+		KeyEventKind,
+		KeyModifiers,
+	};
+	use rand::SeedableRng;
+	use rand_chacha::ChaCha8Rng;
+	use ratatui::layout::Rect;
 
 	#[test]
 	fn test_default_app() {
 		let app = App::default();
 		assert!(app.running);
-		assert_eq!(app.playfield.len(), 24);
-		assert_eq!(app.playfield[0].len(), 14);
+		assert_eq!(app.playfield.len(), 26);
+		assert_eq!(app.playfield[0].len(), 18);
 		// Add more assertions for other fields if needed
 	}
 
@@ -42,20 +132,24 @@ mod unit_tests {
 	#[test]
 	fn test_reset_tetromino() {
 		let mut app = App::default();
-		let initial_tetromino = app.current_tetromino;
+		app.populate_tetromino_queue();
+		let next_tetromino = app.tetromino_queue[0];
 		app.reset_tetromino();
-		assert_ne!(app.current_tetromino, initial_tetromino);
+		assert_eq!(app.current_tetromino, next_tetromino);
 		// Add more assertions as needed
 	}
 
 	#[test]
 	fn test_swap_tetromino() {
 		let mut app = App::default();
+		app.populate_tetromino_queue();
 		let current_tetromino = app.current_tetromino;
-		let swap_tetromino = app.swap_tetromino;
+		let next_tetromino = app.tetromino_queue[0];
 		app.swap_tetromino();
-		assert_eq!(app.current_tetromino, swap_tetromino);
+		assert_eq!(app.current_tetromino, next_tetromino);
 		assert_eq!(app.swap_tetromino, current_tetromino);
+		app.swap_tetromino();
+		assert_eq!(app.current_tetromino, current_tetromino);
 	}
 
 	#[test]
@@ -69,5 +163,1309 @@ mod unit_tests {
 		// Add assertions to check the generated string
 	}
 
+	#[test]
+	fn test_master_level_stops_at_section_end() {
+		assert_eq!(MasterState::level_after_spawn(0), 1);
+		assert_eq!(MasterState::level_after_spawn(99), 99);
+		assert_eq!(MasterState::level_after_spawn(998), 998);
+	}
+
+	#[test]
+	fn test_master_grades() {
+		assert_eq!(Grade::for_score(0).to_string(), "9");
+		assert_eq!(Grade::for_score(12000).to_string(), "1");
+		assert_eq!(Grade::for_score(1_000_000).to_string(), "S9");
+		assert_eq!(Grade::GM.to_string(), "GM");
+		assert!(master::gravity(500) >= 256 * 20);
+	}
+
+	#[test]
+	fn test_master_are_and_line_clear_delay() {
+		let mut app = App::with_mode(GameMode::Master);
+		assert_eq!(app.level, 0);
+		while app.phase != Phase::Falling {
+			app.tick();
+		}
+		assert_eq!(app.level, 1);
+
+		// Fill the bottom row except for where the tetromino will land.
+		let bottom = app.playfield.len() - 1;
+		for x in 4..14 {
+			app.playfield[bottom][x].landed = true;
+		}
+		app.playfield[bottom][app.x + 1].landed = false;
+		app.current_tetromino = TETROMINO_SHAPES[3];
+		app.playfield[bottom][app.x + 2].landed = false;
+		app.current_rotation = 0;
+		app.hard_drop();
+		assert!(matches!(app.phase, Phase::LineClear(_)));
+		assert!(app.score > 0);
+
+		while matches!(app.phase, Phase::LineClear(_)) {
+			app.tick();
+		}
+		assert!(matches!(app.phase, Phase::Are(_)));
+		// Only the upper half of the O tetromino is left.
+		assert_eq!(app.playfield[bottom].iter().filter(|cell| cell.landed).count(), 2);
+	}
+
+	#[test]
+	fn test_fading_stack() {
+		let mut app = App::with_mode(GameMode::Fading);
+		app.fade_ticks = 30;
+		app.land_tetromino();
+		let cell = *app.playfield.iter().flatten().find(|cell| cell.landed).unwrap();
+		assert_eq!(app.landed_cell_string(&cell), "██");
+
+		app.elapsed_ticks = 15;
+		assert_eq!(app.landed_cell_string(&cell), "▓▓");
+		app.elapsed_ticks = 30;
+		assert_eq!(app.landed_cell_string(&cell), "  ");
+
+		// The stack is revealed once the game is over.
+		app.game_over = true;
+		assert_eq!(app.landed_cell_string(&cell), "██");
+	}
+
+	#[test]
+	fn test_invisible_stack() {
+		let mut app = App::with_mode(GameMode::Invisible);
+		app.land_tetromino();
+		let cell = *app.playfield.iter().flatten().find(|cell| cell.landed).unwrap();
+		assert_eq!(app.landed_cell_string(&cell), "  ");
+		assert!(!app.playfield_string().contains('█'));
+	}
+
+	#[test]
+	fn test_big_mode_board() {
+		let mut app = App::with_mode(GameMode::Big);
+		assert_eq!(app.board_columns().len(), 5);
+
+		// The doubled board fills the same area as the standard one.
+		let playfield_string = app.playfield_string();
+		let lines: Vec<&str> = playfield_string.lines().collect();
+		assert_eq!(lines.len(), 22);
+		assert!(lines.iter().all(|line| line.chars().count() == 20));
+
+		// A tetromino can move two logical columns left before hitting the wall.
+		app.current_tetromino = TETROMINO_SHAPES[3];
+		app.move_tetromino(0, 0, app.current_tetromino);
+		let mut moves = 0;
+		while !app.has_landed_cells_at_offset(-1, 0) {
+			app.shift_tetromino(-1);
+			moves += 1;
+		}
+		assert_eq!(moves, 2);
+
+		// Full rows of the narrower board are cleared.
+		let bottom = app.playfield.len() - 1;
+		for x in app.board_columns() {
+			app.playfield[bottom][x].landed = true;
+		}
+		assert_eq!(app.check_for_line_clear(), 1);
+	}
+
+	#[test]
+	fn test_seeded_tetromino_sequence() {
+		let mut first = App::with_seed(GameMode::Daily, 20261019);
+		let mut second = App::with_seed(GameMode::Daily, 20261019);
+		first.populate_tetromino_queue();
+		second.populate_tetromino_queue();
+		assert_eq!(first.current_tetromino, second.current_tetromino);
+		assert_eq!(first.tetromino_queue, second.tetromino_queue);
+	}
+
+	/// A temporary data directory for the current test, removed when dropped.
+	struct TemporaryDataDirectory(std::path::PathBuf);
+
+	impl TemporaryDataDirectory {
+		fn new(name: &str) -> Self {
+			let directory = std::env::temp_dir()
+				.join(format!("tetrs-data-{}-{}", name, std::process::id()));
+			let _ = std::fs::remove_dir_all(&directory);
+			storage::override_data_directory(Some(directory.clone()));
+			Self(directory)
+		}
+	}
+
+	impl Drop for TemporaryDataDirectory {
+		fn drop(&mut self) {
+			storage::override_data_directory(None);
+			let _ = std::fs::remove_dir_all(&self.0);
+		}
+	}
+
+	#[test]
+	fn test_daily_challenge() {
+		let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+		let challenge = DailyChallenge::for_date(date, &[]);
+		assert_eq!(challenge.seed, daily::seed_for_date(date));
+		assert!(!challenge.practice);
+
+		let result = daily::parse_result("2026-10-19 1200 3 4000").unwrap();
+		assert_eq!(result.score, 1200);
+		assert!(DailyChallenge::for_date(date, &[result]).practice);
+		assert!(daily::parse_result("2026-10-19 garbage").is_none());
+
+		// Starting the scored attempt uses it up, so a restart is practice.
+		let _data = TemporaryDataDirectory::new("daily");
+		let mut app = App::new();
+		app.config.game.level = 15;
+		app.start_game(GameMode::Daily, None, None);
+		assert!(!app.daily.unwrap().practice);
+		// Everyone starts the Daily at the first level, whatever their options.
+		assert_eq!((app.level, app.start_level), (1, 1));
+		app.restart();
+		assert!(app.daily.unwrap().practice);
+		app.score = 300;
+		app.end_game();
+		let history = daily::load_history();
+		assert_eq!(history.len(), 1);
+		assert_eq!(history[0].score, 0);
+	}
+
+	#[test]
+	fn test_parse_puzzle() {
+		let puzzle = puzzle::parse(
+			"test",
+			"# comment\nname: Test\ngoal: lines 2\nqueue: TIO\nhold: l\nboard:\nXXXXXXXXX.\n",
+		)
+		.unwrap();
+		assert_eq!(puzzle.name, "Test");
+		assert_eq!(puzzle.goal, Some(Goal::Lines(2)));
+		assert_eq!(
+			puzzle.queue,
+			vec![TetrominoKind::T, TetrominoKind::I, TetrominoKind::O]
+		);
+		assert_eq!(puzzle.hold, Some(TetrominoKind::L));
+		assert_eq!(puzzle.board.len(), 1);
+
+		assert!(puzzle::parse("test", "board:\nXXX\n").is_err());
+		assert!(puzzle::parse("test", "goal: tsd\nqueue: TQ\n").is_err());
+		assert!(puzzle::parse("test", "queue: T\n").is_err());
+	}
+
+	#[test]
+	fn test_puzzle_t_spin_double() {
+		let mut app = App::with_mode(GameMode::Puzzle);
+		app.load_puzzle(
+			puzzle::parse("tsd", include_str!("../puzzles/02-t-spin-double.txt")).unwrap(),
+		);
+		assert_eq!(app.current_tetromino.kind(), Some(TetrominoKind::T));
+		assert!(app.tetromino_queue.is_empty());
+
+		app.rotate_tetromino(true);
+		while !app.has_landed_cells_at_offset(0, 1) {
+			app.move_tetromino(0, 1, app.current_tetromino);
+		}
+		app.rotate_tetromino(true);
+		assert!(app.is_t_spin());
+		app.reset_tetromino();
+
+		let state = app.puzzle.as_ref().unwrap();
+		assert_eq!(state.status, PuzzleStatus::Solved);
+		assert_eq!(state.lines, 2);
+		assert!(app.game_over);
+	}
+
+	#[test]
+	fn test_puzzle_fails_when_queue_runs_out() {
+		let mut app = App::with_mode(GameMode::Puzzle);
+		app.load_puzzle(puzzle::parse("drop", "goal: lines 1\nqueue: O\n").unwrap());
+		app.hard_drop();
+		assert!(app.game_over);
+		assert_eq!(app.puzzle.unwrap().status, PuzzleStatus::Failed);
+	}
+
+	#[test]
+	fn test_sandbox_editing_and_undo() {
+		let mut app = App::with_mode(GameMode::Sandbox);
+		app.playfield_area = Rect::new(10, 5, 22, 24);
+		app.toggle_editing();
+		assert!(app.editing);
+		assert!(!app.can_control());
+
+		// The first board cell is right inside the border, each cell is two columns wide.
+		assert_eq!(app.cell_at(11, 6), Some((4, 4)));
+		assert_eq!(app.cell_at(13, 6), Some((5, 4)));
+		assert_eq!(app.cell_at(10, 6), None);
+
+		app.brush = Some(TetrominoKind::S);
+		app.save_snapshot();
+		app.paint_cell(4, 25, false);
+		assert_eq!(app.playfield[25][4].kind, Some(TetrominoKind::S));
+		assert!(app.undo());
+		assert!(!app.playfield[25][4].landed);
+		assert!(app.redo());
+		assert!(app.playfield[25][4].landed);
+		let (x, y) = app.tetromino_cells()[0];
+		app.paint_cell(x, y, false);
+		assert!(!app.playfield[y][x].landed);
+
+		app.clear_queue();
+		app.push_queue(TetrominoKind::T);
+		app.set_hold(TetrominoKind::I);
+		let setup = app.setup();
+		assert_eq!(setup.queue[1..], [TetrominoKind::T]);
+		assert_eq!(setup.hold, Some(TetrominoKind::I));
+		assert_eq!(setup.board.len(), 1);
+
+		let file = puzzle::to_file_string(&setup);
+		assert_eq!(puzzle::parse_setup("sandbox", &file).unwrap(), setup);
+
+		// Loading a setup brings back exactly what was saved.
+		let mut other = App::with_mode(GameMode::Sandbox);
+		other.clear_falling();
+		other.apply_setup(&setup);
+		other.spawn_next_tetromino();
+		assert_eq!(other.setup(), setup);
+
+		// A setup without hold or queue empties the hold and deals new tetrominos.
+		let empty = Puzzle {
+			queue: vec![],
+			hold: None,
+			..setup
+		};
+		app.clear_falling();
+		app.apply_setup(&empty);
+		assert_eq!(app.swap_tetromino.kind(), None);
+		assert_eq!(app.tetromino_queue.len(), 7);
+	}
+
+	#[test]
+	fn test_sandbox_undo_placement() {
+		let mut app = App::with_mode(GameMode::Sandbox);
+		let tetromino = app.current_tetromino;
+		app.hard_drop();
+		assert!(app.playfield.iter().flatten().any(|cell| cell.landed));
+		assert!(app.undo());
+		assert!(!app.playfield.iter().flatten().any(|cell| cell.landed));
+		assert_eq!(app.current_tetromino, tetromino);
+	}
+
+	#[test]
+	fn test_practice_undo_restores_game_state() {
+		let mut app = App::with_seed(GameMode::Marathon, 7);
+		app.practice = true;
+		app.spawn_next_tetromino();
+		let before = app.snapshot();
+
+		app.score = 500;
+		app.hard_drop();
+		let placed = app.snapshot();
+		assert_ne!(placed.playfield, before.playfield);
+		assert_eq!(app.stats.pieces, 1);
+		app.advance();
+
+		// The undone placement and the time since it no longer count.
+		assert!(app.undo());
+		assert_eq!(app.snapshot().playfield, before.playfield);
+		assert_eq!(app.score, 500);
+		assert_eq!((app.stats.pieces, app.elapsed_ticks), (0, 0));
+		assert_eq!(app.replay_ticks, 1);
+		assert_eq!(app.current_tetromino, before.current_tetromino);
+
+		// Playing on draws the same tetrominos as before the undo.
+		app.hard_drop();
+		let stats = GameStats {
+			keys: placed.stats.keys + 1,
+			..placed.stats
+		};
+		let placed = Snapshot {
+			stats,
+			..placed
+		};
+		assert_eq!(app.snapshot(), placed);
+
+		assert!(app.undo());
+		assert!(app.redo());
+		assert_eq!(app.snapshot(), placed);
+		assert!(!app.redo());
+	}
+
+	/// Moves the current tetromino onto the target of the opener and drops it, or holds it.
+	fn play_opener_step(app: &mut App) {
+		match app.opener_target() {
+			Some(target) => {
+				let (rotation, x, y) =
+					opener::find_position(&app.current_tetromino, &target).unwrap();
+				app.clear_falling();
+				app.current_rotation = rotation;
+				(app.x, app.y) = (x, y);
+				app.move_tetromino(0, 0, app.current_tetromino);
+				app.hard_drop();
+			}
+			None => app.hold_tetromino(),
+		}
+	}
+
+	#[test]
+	fn test_opener_trainer() {
+		for (index, opener) in OPENERS.iter().enumerate() {
+			for kind in opener.kinds() {
+				// Flip the rows so that they count from the top like the playfield, which also has
+				// room to the left of the board.
+				let cells: Vec<(usize, usize)> = opener
+					.placement(kind)
+					.unwrap()
+					.into_iter()
+					.map(|(column, row)| (column + PLAYFIELD_PADDING, 10 - row))
+					.collect();
+				assert!(opener::find_position(&kind.shape(), &cells).is_some());
+			}
+
+			// Every opener can be built from a bag in which its tetrominos come from the bottom up,
+			// after the ones that are held.
+			let mut app = App::with_seed(GameMode::Opener, 1);
+			app.opener = Some(OpenerState::new(&OPENERS[index], false));
+			app.clear_falling();
+			let mut kinds = TetrominoKind::ALL.to_vec();
+			kinds.sort_by_key(|&kind| {
+				opener.placement(kind).map(|cells| {
+					let rows = cells.iter().map(|&(_, row)| row);
+					(rows.clone().min(), rows.max())
+				})
+			});
+			app.tetromino_queue = kinds.iter().map(|kind| kind.shape()).collect();
+			app.spawn_next_tetromino();
+			for _ in 0..10 {
+				play_opener_step(&mut app);
+			}
+			assert!(app.opener_complete(), "{}", opener.name);
+			assert_eq!(app.opener.unwrap().mistakes, 0);
+			assert!(app.game_over);
+		}
+	}
+
+	#[test]
+	fn test_opener_wrong_placement_is_undone() {
+		let mut app = App::with_seed(GameMode::Opener, 3);
+		app.opener = Some(OpenerState::new(&OPENERS[1], true));
+		let tetromino = app.current_tetromino;
+		app.hard_drop();
+		let state = app.opener.unwrap();
+		assert!(state.last_wrong);
+		assert_eq!(state.mistakes, 1);
+		assert!(!app.playfield.iter().flatten().any(|cell| cell.landed));
+		assert_eq!(app.current_tetromino, tetromino);
+		// The rejected placement cannot be brought back.
+		assert!(!app.redo());
+	}
+
+	#[test]
+	fn test_pc_solver() {
+		let rows = [0b0000111111; 4];
+		let solution = pc::solve(&rows, 10, &[TetrominoKind::I; 4], None).unwrap();
+		assert_eq!(solution.len(), 4);
+		let queue = [TetrominoKind::O, TetrominoKind::O, TetrominoKind::S];
+		assert!(pc::solve(&rows, 10, &queue, None).is_none());
+
+		// The hold lets the tetrominos be played out of order.
+		let rows = [0b1111111100; 2];
+		let queue = [TetrominoKind::I, TetrominoKind::O];
+		let solution = pc::solve(&rows, 10, &queue, None).unwrap();
+		assert_eq!(solution[0].kind, TetrominoKind::O);
+
+		// The solution of a generated board fills every one of its empty cells.
+		let mut rng = ChaCha8Rng::seed_from_u64(9);
+		let (puzzle, solution) = pc::generate(&mut rng, &mut vec![]).unwrap();
+		let bottom = puzzle.board.len() - 1;
+		let mut rows: Vec<u16> = (0..PC_HEIGHT)
+			.map(|row| {
+				let cells = &puzzle.board[bottom - row];
+				(0..10).fold(0, |mask, column| mask | ((cells[column].landed as u16) << column))
+			})
+			.collect();
+		assert_eq!(solution.len(), puzzle.queue.len());
+		for placement in &solution {
+			for &(column, row) in &placement.cells {
+				assert_eq!(rows[row] & (1 << column), 0);
+				rows[row] |= 1 << column;
+			}
+		}
+		assert!(rows.iter().all(|&row| row == (1 << 10) - 1));
+	}
+
+	#[test]
+	fn test_pc_trainer() {
+		let mut app = App::with_seed(GameMode::PerfectClear, 5);
+		assert!(!app.pc.as_ref().unwrap().solution.is_empty());
+
+		// Play the first placement of a solution of the board until it is cleared.
+		for _ in 0..10 {
+			if app.game_over {
+				break;
+			}
+			let bottom = app.playfield.len() - 1;
+			let height = PC_HEIGHT - app.puzzle.as_ref().unwrap().lines as usize;
+			let rows: Vec<u16> = (0..height)
+				.map(|row| {
+					(0..10).fold(0, |mask, column| {
+						let landed = app.playfield[bottom - row][PLAYFIELD_PADDING + column].landed;
+						mask | ((landed as u16) << column)
+					})
+				})
+				.collect();
+			let queue: Vec<TetrominoKind> = std::iter::once(&app.current_tetromino)
+				.chain(&app.tetromino_queue)
+				.filter_map(|tetromino| tetromino.kind())
+				.collect();
+			let solution = pc::solve(&rows, 10, &queue, app.swap_tetromino.kind()).unwrap();
+			let placement = solution[0].clone();
+			if Some(placement.kind) != app.current_tetromino.kind() {
+				app.hold_tetromino();
+				continue;
+			}
+			let cells: Vec<(usize, usize)> = placement
+				.cells
+				.iter()
+				.map(|&(column, row)| (PLAYFIELD_PADDING + column, bottom - row))
+				.collect();
+			let (rotation, x, y) = opener::find_position(&app.current_tetromino, &cells).unwrap();
+			app.clear_falling();
+			app.current_rotation = rotation;
+			(app.x, app.y) = (x, y);
+			app.hard_drop();
+		}
+		assert_eq!(app.puzzle.as_ref().unwrap().status, PuzzleStatus::Solved);
+		assert_eq!(app.pc.as_ref().unwrap().solved, 1);
+
+		// A failed board shows the solution.
+		app.next_pc_board();
+		while !app.game_over {
+			app.hard_drop();
+		}
+		if app.puzzle.as_ref().unwrap().status == PuzzleStatus::Failed {
+			let solution_cells =
+				app.playfield.iter().flatten().filter(|cell| cell.kind.is_some()).count();
+			assert_eq!(solution_cells, 4 * app.pc.as_ref().unwrap().solution.len());
+			assert_eq!(app.pc.as_ref().unwrap().failed, 1);
+		}
+	}
+
+	#[test]
+	fn test_optimal_inputs() {
+		let app = App::new();
+		let bottom = app.playfield.len() - 1;
+		let o = TetrominoKind::O.shape();
+		let o_at = |x: usize| {
+			vec![(x, bottom - 1), (x + 1, bottom - 1), (x, bottom), (x + 1, bottom)]
+		};
+		assert_eq!(app.optimal_inputs(&o, &o_at(8)), Some(vec![]));
+		assert_eq!(app.optimal_inputs(&o, &o_at(7)), Some(vec![Input::Left]));
+		assert_eq!(app.optimal_inputs(&o, &o_at(4)), Some(vec![Input::DasLeft]));
+		assert_eq!(app.optimal_inputs(&o, &o_at(12)), Some(vec![Input::DasRight]));
+		assert_eq!(
+			app.optimal_inputs(&o, &o_at(5)),
+			Some(vec![Input::DasLeft, Input::Right])
+		);
+		// Cells under an overhang need a soft drop.
+		let mut app = app;
+		app.playfield[bottom - 2][4].landed = true;
+		assert_eq!(app.optimal_inputs(&o, &o_at(4)), None);
+	}
+
+	/// Presses the keys of the given inputs.
+	fn press_inputs(app: &mut App, inputs: &[Input]) {
+		for input in inputs {
+			match input {
+				Input::Left => app.shift_tetromino(-1),
+				Input::Right => app.shift_tetromino(1),
+				Input::RotateCw => app.rotate_tetromino(true),
+				Input::RotateCcw => app.rotate_tetromino(false),
+				Input::DasLeft => app.das_tetromino(-1),
+				Input::DasRight => app.das_tetromino(1),
+			}
+		}
+	}
+
+	#[test]
+	fn test_finesse_trainer() {
+		let mut app = App::with_seed(GameMode::Finesse, 5);
+		for _ in 0..10 {
+			let target = app.finesse_target().unwrap();
+			let optimal = app.optimal_inputs(&app.current_tetromino, &target).unwrap();
+			press_inputs(&mut app, &optimal);
+			app.hard_drop();
+		}
+		let trainer = app.finesse_trainer.as_ref().unwrap();
+		assert_eq!((trainer.correct, trainer.wrong), (10, 0));
+		assert_eq!(app.finesse.faults, 0);
+		assert!(!app.playfield.iter().flatten().any(|cell| cell.landed));
+		// Picking the targets leaves the tetrominos of the seed alone.
+		let mut sandbox = App::with_seed(GameMode::Sandbox, 5);
+		(0..10).for_each(|_| sandbox.spawn_next_tetromino());
+		assert_eq!(sandbox.rng, app.rng);
+
+		// Wasted inputs are faults, and the placement is asked for again.
+		let tetromino = app.current_tetromino;
+		let target = app.finesse_target().unwrap();
+		let optimal = app.optimal_inputs(&tetromino, &target).unwrap();
+		press_inputs(&mut app, &[Input::Left, Input::Right]);
+		press_inputs(&mut app, &optimal);
+		app.hard_drop();
+		assert_eq!(app.finesse_trainer.as_ref().unwrap().wrong, 1);
+		assert_eq!(app.finesse.faults, 2);
+		assert_eq!(app.finesse.last_fault, Some(optimal));
+		assert_eq!(app.current_tetromino, tetromino);
+		assert_eq!(app.finesse_target(), Some(target));
+		assert!(app.status_message.as_ref().unwrap().starts_with("Too many inputs"));
+	}
+
+	#[test]
+	fn test_game_stats() {
+		let mut stats = GameStats::default();
+		let lock = |lines, t_spin| LockResult {
+			lines,
+			t_spin,
+			..LockResult::default()
+		};
+		// Tetris, back-to-back Tetris, nothing, single, T-spin double.
+		assert_eq!(stats.record_lock(&lock(4, false)), 4);
+		assert_eq!(stats.record_lock(&lock(4, false)), 5);
+		assert_eq!(stats.record_lock(&lock(0, false)), 0);
+		assert_eq!(stats.record_lock(&lock(1, false)), 0);
+		assert_eq!(stats.record_lock(&lock(2, true)), 4);
+		assert_eq!(stats.attack, 13);
+		assert_eq!((stats.pieces, stats.lines, stats.tetrises, stats.t_spins), (5, 11, 2, 1));
+		assert_eq!(stats.tetris_rate(), 8.0 / 11.0);
+		assert_eq!((stats.clears, stats.t_spin_clears), ([1, 0, 0, 2], [0, 0, 1, 0]));
+		// A combo adds to the attack.
+		assert_eq!(stats.combo, 2);
+		assert_eq!(stats.record_lock(&lock(1, false)), 1);
+
+		// 100 ticks are 3 seconds.
+		assert_eq!(stats.pieces_per_second(100), 2.0);
+		assert_eq!(GameStats::default().keys_per_piece(), 0.0);
+
+		let mut app = App::with_seed(GameMode::Marathon, 1);
+		app.shift_tetromino(-1);
+		app.rotate_tetromino(true);
+		app.hard_drop();
+		assert_eq!((app.stats.keys, app.stats.pieces), (3, 1));
+		assert_eq!(app.stats.keys_per_piece(), 3.0);
+	}
+
+	#[test]
+	fn test_game_records() {
+		let line = "2026-03-02T18:30:00 Marathon 42 2000 12000 5 40 100 30 4";
+		let record = records::parse_record(line).unwrap();
+		assert_eq!(record.mode, GameMode::Marathon);
+		assert_eq!((record.seed, record.score, record.max_combo), (42, 12000, 4));
+		assert_eq!(records::record_line(&record), line);
+		assert!(records::parse_record("2026-03-02T18:30:00 Tetris 42").is_none());
+
+		// 2000 ticks are a minute.
+		assert_eq!(record.pieces_per_second(), 100.0 / 60.0);
+		let other = GameRecord {
+			score: 6000,
+			lines: 60,
+			pieces: 50,
+			max_combo: 2,
+			..record
+		};
+		let summaries = records::summarize(&[record, other]);
+		assert_eq!(summaries.len(), 1);
+		let summary = summaries[0];
+		assert_eq!((summary.games, summary.best_score, summary.best_lines), (2, 12000, 60));
+		assert_eq!(summary.average_score, 9000.0);
+		assert_eq!(summary.average_pps, 75.0 / 60.0);
+		assert_eq!(summary.max_combo, 4);
+		assert!(!records::is_personal_best(&[record, other], &other));
+		assert!(records::is_personal_best(&[other], &record));
+
+		let mut app = App::with_seed(GameMode::Big, 7);
+		app.hard_drop();
+		let record = app.game_record();
+		assert_eq!((record.mode, record.seed, record.pieces), (GameMode::Big, 7, 1));
+	}
+
+	#[test]
+	fn test_leaderboard() {
+		let line = "Marathon\tstart 1\tGrace M\t2026-03-02\t12000\t2000\t40\t5\t42";
+		let entry = LeaderboardEntry::parse(line).unwrap();
+		assert_eq!(entry.name, "Grace M");
+		assert_eq!(entry.to_line(), line);
+
+		// Games that scored nothing stay off even an empty board.
+		let mut leaderboard = Leaderboard::default();
+		let empty = LeaderboardEntry {
+			score: 0,
+			..entry.clone()
+		};
+		assert!(!leaderboard.qualifies(&empty));
+		assert!(leaderboard.qualifies(&entry));
+
+		// Every board keeps its best entries, apart from the other rulesets.
+		for score in 1..=12 {
+			let rank = leaderboard.insert(LeaderboardEntry {
+				score: score * 100,
+				..entry.clone()
+			});
+			assert_eq!(rank, Some(1));
+		}
+		leaderboard.insert(LeaderboardEntry {
+			ruleset: String::from("start 5"),
+			..entry.clone()
+		});
+		let board = leaderboard.board(GameMode::Marathon, "start 1");
+		assert_eq!(board.len(), LEADERBOARD_SIZE);
+		assert_eq!((board[0].score, board[9].score), (1200, 300));
+		assert_eq!(leaderboard.entries.len(), LEADERBOARD_SIZE + 1);
+		let low = LeaderboardEntry {
+			score: 200,
+			..entry.clone()
+		};
+		assert!(!leaderboard.qualifies(&low));
+		assert!(leaderboard.qualifies(&entry));
+		assert_eq!(Leaderboard::parse(&leaderboard.to_file_string()), leaderboard);
+
+		// Master games are ranked by level, then time.
+		let master = |level, ticks| LeaderboardEntry {
+			mode: GameMode::Master,
+			level,
+			ticks,
+			..entry.clone()
+		};
+		assert!(!leaderboard.qualifies(&master(0, 100)));
+		leaderboard.insert(master(500, 9000));
+		leaderboard.insert(master(999, 20000));
+		assert_eq!(leaderboard.insert(master(999, 15000)), Some(1));
+		assert_eq!(leaderboard.board(GameMode::Master, "start 1")[2].level, 500);
+
+		// A game making it onto the board asks for a name.
+		let mut app = App::with_seed(GameMode::Marathon, 1);
+		app.leaderboard = leaderboard;
+		app.score = 5000;
+		app.end_game();
+		assert!(app.name_entry.is_none());
+		// Only games that are recorded make it onto the board.
+		app.record_results = true;
+		app.check_leaderboard();
+		app.record_results = false;
+		assert_eq!(app.name_entry.as_deref(), Some("Grace M"));
+		app.pop_name_char();
+		app.push_name_char('!');
+		app.submit_name();
+		assert!(app.show_leaderboard);
+		assert_eq!(app.leaderboard.board(GameMode::Marathon, "start 1")[0].name, "Grace !");
+	}
+
+	#[test]
+	fn test_storage_migration() {
+		let root = std::env::temp_dir().join(format!("tetrs-storage-{}", std::process::id()));
+		let (home, data) = (root.join("home"), root.join("data"));
+		std::fs::create_dir_all(&home).unwrap();
+		std::fs::write(home.join(".tetrs_highscore"), "1200").unwrap();
+
+		// Files are written through a temporary file, creating the directory.
+		storage::write_path(&data.join("daily"), "kept\n").unwrap();
+		assert!(!data.join("daily.tmp").exists());
+		assert_eq!(storage::read_path(&data.join("missing")).unwrap(), None);
+
+		// A high score already in the data directory is left alone.
+		let moved = storage::migrate_between(&home, &data).unwrap();
+		assert_eq!(moved, vec![data.join(storage::HIGHSCORE_FILE)]);
+		assert!(!home.join(".tetrs_highscore").exists());
+		std::fs::write(home.join(".tetrs_highscore"), "900").unwrap();
+		assert!(storage::migrate_between(&home, &data).unwrap().is_empty());
+		assert!(home.join(".tetrs_highscore").exists());
+		let highscore = storage::read_path(&data.join(storage::HIGHSCORE_FILE)).unwrap();
+		assert_eq!(highscore.unwrap(), "1200");
+		std::fs::remove_dir_all(&root).unwrap();
+	}
+
+	#[test]
+	fn test_config() {
+		// The printed default file holds exactly the defaults, and left out values fall back to them.
+		assert_eq!(Config::parse(DEFAULT_CONFIG).unwrap(), Config::default());
+		let written: toml::Table = DEFAULT_CONFIG.parse().unwrap();
+		assert_eq!(written, toml::Table::try_from(Config::default()).unwrap());
+		let saved = toml::to_string(&Config::default()).unwrap();
+		assert_eq!(Config::parse(&saved).unwrap(), Config::default());
+		let config =
+			Config::parse("[game]\nmode = \"master\"\n[controls]\nleft = [\"Left\", \"h\"]\n")
+				.unwrap();
+		assert_eq!(config.game.mode, GameMode::Master);
+		assert_eq!(config.game.level, 1);
+		assert_eq!(config.controls.action(KeyCode::Left), Some(Action::Left));
+		assert_eq!(config.controls.action(KeyCode::Char('h')), Some(Action::Left));
+		assert_eq!(config.controls.action(KeyCode::Char('a')), None);
+		assert_eq!(config.controls.action(KeyCode::Char(' ')), Some(Action::HardDrop));
+
+		// Invalid values name what is wrong with them.
+		let error = Config::parse("[game]\nlevel = 20\n").unwrap_err();
+		assert!(error.contains("game.level") && error.contains("1 to 15"), "{}", error);
+		let error = Config::parse("[game]\nmode = \"Tetris\"\n").unwrap_err();
+		assert!(error.contains("'Tetris'") && error.contains("'Marathon'"), "{}", error);
+		let error = Config::parse("[controls]\nhold = [\"Shift\"]\n").unwrap_err();
+		assert!(error.contains("Invalid key 'Shift'"), "{}", error);
+		assert!(Config::parse("[theme]\nborder = \"Double\"\n").is_err());
+		assert!(Config::parse("[layout]\nbutton = true\n").is_err());
+
+		// Keys are written the way they are read.
+		for name in ["a", "A", "Space", "PageUp", "F5"] {
+			assert_eq!(name.parse::<Key>().unwrap().to_string(), name);
+		}
+		assert_eq!("esc".parse::<Key>().unwrap(), Key(KeyCode::Esc));
+	}
+
+	#[test]
+	fn test_handling() {
+		let mut app = App::with_mode(GameMode::Sandbox);
+		app.key_releases = true;
+		app.config.handling.das = 90;
+		app.config.handling.arr = 30;
+		let start_x = app.x;
+
+		// A held key shifts once, then once every ARR after DAS has charged.
+		app.press_shift(1);
+		assert_eq!(app.x, start_x + 1);
+		app.tick();
+		app.tick();
+		assert_eq!(app.x, start_x + 1);
+		app.tick();
+		assert_eq!(app.x, start_x + 2);
+		app.tick();
+		assert_eq!(app.x, start_x + 3);
+		assert_eq!(app.finesse.inputs, vec![Input::DasRight]);
+		app.release_shift(1);
+		app.tick();
+		assert_eq!(app.x, start_x + 3);
+
+		// Without ARR the tetromino shifts straight to the wall.
+		app.config.handling.arr = 0;
+		app.press_shift(-1);
+		(0..3).for_each(|_| app.tick());
+		assert!(app.has_landed_cells_at_offset(-1, 0));
+
+		// Without a soft drop factor the tetromino drops to the bottom without locking.
+		app.config.handling.sdf = 0;
+		app.soft_drop();
+		assert!(app.has_landed_cells_at_offset(0, 1));
+		assert!(app.is_board_empty());
+
+		// Held keys only repeat soft drop.
+		let mut app = App::with_mode(GameMode::Sandbox);
+		app.screen = Screen::Playing;
+		let repeat = |code| KeyEvent::new_with_kind(code, KeyModifiers::NONE, KeyEventKind::Repeat);
+		handle_key_events(repeat(KeyCode::Char(' ')), &mut app).unwrap();
+		handle_key_events(repeat(KeyCode::Char('e')), &mut app).unwrap();
+		assert_eq!((app.stats.pieces, app.current_rotation), (0, 0));
+		let y = app.y;
+		handle_key_events(repeat(KeyCode::Char('s')), &mut app).unwrap();
+		assert_eq!(app.y, y + 1);
+
+		// Soft drop held through the end of the game leaves the results alone.
+		let mut app = App::with_seed(GameMode::Marathon, 3);
+		app.hard_drop();
+		app.show_screen(Screen::Results);
+		handle_key_events(repeat(KeyCode::Char('s')), &mut app).unwrap();
+		handle_key_events(repeat(KeyCode::Char('R')), &mut app).unwrap();
+		assert_eq!((app.screen, app.stats.pieces), (Screen::Results, 1));
+		// Keys bound to the game do not retry either.
+		handle_key_events(KeyEvent::from(KeyCode::Char('s')), &mut app).unwrap();
+		assert_eq!(app.screen, Screen::Results);
+		handle_key_events(KeyEvent::from(KeyCode::Char('R')), &mut app).unwrap();
+		assert_eq!((app.screen, app.seed, app.stats.pieces), (Screen::Playing, 3, 0));
+	}
+
+	#[test]
+	fn test_settings() {
+		let mut app = App::with_mode(GameMode::Sandbox);
+		let select = |app: &mut App, setting: Setting| {
+			app.settings.selected = SettingsMenu::settings()
+				.iter()
+				.position(|&(_, other)| other == setting)
+				.unwrap();
+		};
+		app.open_settings();
+
+		// Values stay in their range.
+		app.adjust_setting(1);
+		assert_eq!(app.config.handling.das, 177);
+		select(&mut app, Setting::Preview);
+		(0..10).for_each(|_| app.adjust_setting(1));
+		assert_eq!(app.config.layout.preview, 7);
+
+		// A pressed key is bound to the selected action and taken from the other ones.
+		select(&mut app, Setting::Key(Action::Hold));
+		app.activate_setting();
+		assert!(app.settings.binding);
+		app.bind_setting(KeyCode::Char('a'));
+		assert!(!app.settings.binding);
+		assert_eq!(app.config.controls.hold, vec![Key(KeyCode::Char('a'))]);
+		assert!(app.config.controls.left.is_empty());
+		app.activate_setting();
+		app.bind_setting(KeyCode::Esc);
+		assert_eq!(app.config.controls.action(KeyCode::Char('a')), Some(Action::Hold));
+
+		// The ghost shows where the tetromino lands, and the defaults come back on reset.
+		select(&mut app, Setting::Defaults);
+		app.activate_setting();
+		assert_eq!(app.config, Config::default());
+		app.screen = app.settings.back;
+		let ghost = app.ghost_cells();
+		assert!(ghost.iter().all(|&(_, y)| y < app.playfield.len()));
+		assert_eq!(ghost.iter().map(|&(_, y)| y).max(), Some(app.playfield.len() - 1));
+		assert!(app.playfield_string().contains("░░"));
+		app.config.layout.ghost = false;
+		assert!(!app.playfield_string().contains("░░"));
+
+		// Options given on the command line stay out of the saved config file.
+		let _data = TemporaryDataDirectory::new("settings");
+		app.config.theme.accent = 5;
+		app.open_settings();
+		select(&mut app, Setting::Das);
+		app.adjust_setting(-1);
+		app.close_settings();
+		let saved = Config::load().unwrap();
+		assert_eq!(saved.theme.accent, Config::default().theme.accent);
+		assert_eq!(saved.handling.das, app.config.handling.das);
+		assert_ne!(saved.handling.das, Config::default().handling.das);
+	}
+
+	#[test]
+	fn test_screens() {
+		let mut app = App::new();
+		app.show_screen(Screen::Title);
+		app.tick();
+		assert_eq!(app.elapsed_ticks, 0);
+
+		// The items wrap around, and picking a mode starts a game of it.
+		app.select_menu_item(-1);
+		assert_eq!(app.menu_items()[app.menu_selected], "Quit");
+		app.select_menu_item(1);
+		app.activate_menu_item();
+		assert_eq!(app.screen, Screen::ModeSelect);
+		app.activate_menu_item();
+		assert_eq!(app.screen, Screen::Playing);
+		assert_eq!(app.mode, GameMode::ALL[0]);
+
+		// Pausing stops the game.
+		app.toggle_pause();
+		assert_eq!(app.screen, Screen::Paused);
+		assert!(!app.can_control());
+		app.toggle_pause();
+		assert_eq!(app.screen, Screen::Playing);
+
+		// A finished game shows its results, from where it can be retried.
+		app.score = 100;
+		app.game_over = true;
+		app.show_results();
+		assert_eq!(app.screen, Screen::Results);
+		assert!(app.menu_details().contains("Score: 100"));
+		app.activate_menu_item();
+		assert_eq!(app.screen, Screen::Playing);
+		assert_eq!((app.mode, app.score, app.game_over), (GameMode::ALL[0], 0, false));
+		app.back();
+		assert_eq!(app.screen, Screen::Title);
+		app.back();
+		assert!(!app.running);
+	}
+
+	#[test]
+	fn test_pause_menu() {
+		let mut app = App::with_mode(GameMode::Marathon);
+		let tetromino = app.current_tetromino;
+
+		// Losing focus pauses the game, and resuming counts down before it goes on.
+		app.pause();
+		assert_eq!(app.screen, Screen::Paused);
+		app.activate_menu_item();
+		assert_eq!(app.countdown_seconds(), Some(3));
+		assert!(!app.can_control());
+		(0..50).for_each(|_| app.tick());
+		assert_eq!(app.countdown_seconds(), Some(2));
+		assert_eq!(app.elapsed_ticks, 0);
+		assert_eq!(app.current_tetromino, tetromino);
+		(0..50).for_each(|_| app.tick());
+		assert!(app.can_control());
+		app.tick();
+		assert_eq!(app.elapsed_ticks, 1);
+
+		// Pausing again stops the countdown, and back resumes.
+		app.pause();
+		app.back();
+		assert_eq!(app.screen, Screen::Playing);
+		app.pause();
+		app.resume();
+		app.pause();
+		assert_eq!(app.countdown, 0);
+
+		// The other items restart the game or quit to the title screen.
+		app.score = 100;
+		app.select_menu_item(1);
+		app.activate_menu_item();
+		assert_eq!((app.screen, app.score), (Screen::Playing, 0));
+		app.pause();
+		app.select_menu_item(-1);
+		app.activate_menu_item();
+		assert_eq!(app.screen, Screen::Title);
+	}
+
+	#[test]
+	fn test_results() {
+		let mut app = App::with_seed(GameMode::Marathon, 3);
+		let queue = app.tetromino_queue.clone();
+		app.hard_drop();
+		app.show_screen(Screen::Results);
+		assert_eq!(app.stats.kinds.iter().sum::<u32>(), 1);
+		assert!(app.results_string().contains(&app.stats.distribution_string()));
+
+		// The same seed deals the same tetrominos again, unlike a plain retry.
+		app.restart_same_seed();
+		assert_eq!((app.screen, app.seed, app.stats.pieces), (Screen::Playing, 3, 0));
+		assert_eq!(app.tetromino_queue, queue);
+		app.restart();
+		assert_ne!(app.seed, 3);
+	}
+
+	#[test]
+	fn test_replay() {
+		let mut app = App::new();
+		app.key_releases = true;
+		app.start_game(GameMode::Marathon, None, None);
+
+		// Inputs are recorded with their tick, except during the countdown.
+		app.input(ReplayInput::Press(Action::Left));
+		(0..20).for_each(|_| app.tick());
+		app.input(ReplayInput::Release(Action::Left));
+		app.input(ReplayInput::Press(Action::RotateCw));
+		app.input(ReplayInput::Press(Action::HardDrop));
+		app.pause();
+		app.config.handling.arr = 0;
+		app.record_handling();
+		app.resume();
+		app.input(ReplayInput::Press(Action::Hold));
+		(0..200).for_each(|_| app.tick());
+		app.input(ReplayInput::Press(Action::Right));
+		(0..40).for_each(|_| app.tick());
+		app.input(ReplayInput::Press(Action::HardDrop));
+		assert_eq!(app.replay.inputs[1], (20, ReplayInput::Release(Action::Left)));
+		assert_eq!(app.replay.inputs.len(), 7);
+		assert_eq!(app.replay.last_handling().arr, 0);
+
+		// The file format reads back what it writes.
+		let contents = app.replay.to_file_string();
+		assert!(contents.starts_with("tetrs-replay: 1\n"));
+		assert!(contents.contains("\n20 release left\n"));
+		let replay = Replay::parse(&contents).unwrap();
+		assert_eq!(replay, app.replay);
+		assert!(Replay::parse(&contents.replace("tetrs-replay: 1", "tetrs-replay: 2"))
+			.unwrap_err()
+			.starts_with("unsupported replay version 2"));
+		assert_eq!(
+			Replay::parse(&(contents.clone() + "200 press sideways")).unwrap_err(),
+			"line 21: invalid action 'sideways'"
+		);
+
+		// Playing the inputs back on the same seed repeats the game.
+		let mut game = replay.game();
+		let mut inputs = replay.inputs.iter().peekable();
+		while game.elapsed_ticks < app.elapsed_ticks {
+			while let Some((_, input)) = inputs.next_if(|&&(tick, _)| tick == game.elapsed_ticks) {
+				game.play_input(*input);
+			}
+			game.tick();
+		}
+		inputs.for_each(|(_, input)| game.play_input(*input));
+		assert_eq!(game.playfield_string(), app.playfield_string());
+		assert_eq!((game.score, game.stats), (app.score, app.stats));
+	}
+
+	#[test]
+	fn test_replay_viewer() {
+		let mut app = App::new();
+		app.start_game(GameMode::Marathon, None, None);
+		for piece in 0..12 {
+			app.input(ReplayInput::Press(if piece % 2 == 0 {
+				Action::DasLeft
+			} else {
+				Action::RotateCw
+			}));
+			(0..15).for_each(|_| app.tick());
+			app.input(ReplayInput::Press(Action::HardDrop));
+		}
+		(0..10).for_each(|_| app.tick());
+		app.replay.result = Some(ReplayResult {
+			ticks: app.elapsed_ticks,
+			score: app.score,
+			lines: app.stats.lines,
+			level: app.level,
+		});
+
+		// Playing back at 8x gets through 8 ticks per tick and stops at the end.
+		let mut viewer = App::new();
+		viewer.replay_list = vec![Ok(app.replay.clone())];
+		viewer.watch_replay(app.replay.clone());
+		assert_eq!(viewer.screen, Screen::Watching);
+		viewer.change_speed(10);
+		viewer.tick();
+		assert_eq!(viewer.elapsed_ticks, 8);
+		while viewer.elapsed_ticks < app.elapsed_ticks {
+			viewer.tick();
+		}
+		assert_eq!(viewer.playfield_string(), app.playfield_string());
+		assert_eq!(viewer.viewer.as_ref().unwrap().snapshots.len(), 2);
+		// The snapshots leave out the replays listed around the game.
+		let snapshots = &viewer.viewer.as_ref().unwrap().snapshots;
+		assert!(snapshots.iter().all(|(_, snapshot)| snapshot.replay_list.is_empty()));
+
+		// Seeking goes back through the snapshots and forward through the game.
+		viewer.seek_piece(3);
+		assert_eq!(viewer.stats.pieces, 3);
+		assert_eq!(viewer.elapsed_ticks, 46);
+		viewer.seek_pieces(8);
+		assert_eq!(viewer.stats.pieces, 11);
+		viewer.seek_piece(0);
+		assert_eq!((viewer.stats.pieces, viewer.elapsed_ticks), (0, 0));
+		assert_eq!(viewer.replay_list.len(), 1);
+		viewer.step_frame();
+		assert!(!viewer.viewer.as_ref().unwrap().playing);
+		assert_eq!(viewer.elapsed_ticks, 1);
+		viewer.seek_piece(100);
+		assert!(viewer.replay_finished());
+		assert_eq!((viewer.stats, viewer.elapsed_ticks), (app.stats, app.elapsed_ticks));
+
+		// Leaving gives the handling of the player back.
+		viewer.config.handling.das = 0;
+		viewer.viewer.as_mut().unwrap().handling.das = 100;
+		viewer.back();
+		assert_eq!((viewer.screen, viewer.config.handling.das), (Screen::Replays, 100));
+	}
+
+	#[test]
+	fn test_replay_verification() {
+		let _data = TemporaryDataDirectory::new("verification");
+		let mut app = App::new();
+		app.start_game(GameMode::Marathon, None, Some(7));
+		while !app.game_over {
+			(0..5).for_each(|_| app.tick());
+			app.input(ReplayInput::Press(Action::HardDrop));
+		}
+		app.replay.result = Some(ReplayResult {
+			ticks: app.elapsed_ticks,
+			score: app.score,
+			lines: app.stats.lines,
+			level: app.level,
+		});
+		let replay = Replay::parse(&app.replay.to_file_string()).unwrap();
+		assert_eq!(replay.rules, RULES_VERSION);
+		// Recorded games save their replay once they end.
+		let saved = replay::replays_directory().unwrap().join(replay.file_name());
+		assert_eq!(replay::load(&saved).unwrap(), replay);
+
+		// Playing back without a terminal ends with the claimed result.
+		let result = replay.played_result().unwrap();
+		assert_eq!(Some(result), replay.result);
+		assert_eq!(replay.verify(&result), Ok(()));
+
+		// A tampered result or other rules fail.
+		let mut tampered = replay.clone();
+		tampered.result.as_mut().unwrap().score += 100;
+		assert!(tampered.verify(&result).unwrap_err().starts_with("score"));
+		let contents = app.replay.to_file_string();
+		let rules = format!("rules: {}\n", RULES_VERSION);
+		tampered = Replay::parse(&contents.replace(&rules, "rules: 1\n")).unwrap();
+		assert!(tampered.verify(&result).is_err());
+		tampered.result = None;
+		tampered.rules = RULES_VERSION;
+		assert_eq!(tampered.verify(&result).unwrap_err(), "the replay claims no result");
+		// Replays from before the rules were versioned were recorded under the first rules.
+		let old = Replay::parse(&contents.replace(&rules, "")).unwrap();
+		assert_eq!(old.rules, 1);
+
+		// Modes without replays are not played back, nor games outlasting their claimed time.
+		let sandbox = Replay {
+			mode: GameMode::Sandbox,
+			..replay.clone()
+		};
+		assert!(sandbox.played_result().is_err());
+		assert!(sandbox.verify(&result).is_err());
+		// Practice games and undone placements do not stand for a result.
+		let practice = Replay {
+			practice: true,
+			..replay.clone()
+		};
+		assert_eq!(practice.check().unwrap_err(), "the game was played in practice");
+		assert!(practice.verify(&result).is_err());
+		let mut undone = replay.clone();
+		undone.inputs.push((0, ReplayInput::Undo));
+		assert_eq!(undone.check().unwrap_err(), "the game undoes placements");
+		assert_eq!(replay.check(), Ok(()));
+		let mut early = replay.clone();
+		early.result.as_mut().unwrap().ticks = 0;
+		early.inputs.clear();
+		let error = early.played_result().unwrap_err();
+		assert!(error.contains("claimed time"), "{}", error);
+	}
+
+	#[test]
+	fn test_cast() {
+		let mut app = App::new();
+		app.start_game(GameMode::Marathon, None, Some(3));
+		(0..10).for_each(|_| app.tick());
+		app.input(ReplayInput::Press(Action::DasLeft));
+		app.input(ReplayInput::Press(Action::HardDrop));
+		(0..20).for_each(|_| app.tick());
+		app.replay.result = Some(ReplayResult {
+			ticks: app.elapsed_ticks,
+			score: app.score,
+			lines: app.stats.lines,
+			level: app.level,
+		});
+
+		let contents = cast::cast(&app.replay, &app.config).unwrap();
+		let mut lines = contents.lines();
+		assert!(lines.next().unwrap().starts_with(&format!(
+			"{{\"version\": 2, \"width\": {}, \"height\": {}",
+			CAST_WIDTH, CAST_HEIGHT
+		)));
+		// Frames are only written when the screen changes, in order. The first one draws every row
+		// and the others only the cells that changed.
+		let frames: Vec<&str> = lines.collect();
+		assert!(frames.len() > 2 && frames.len() <= 31);
+		assert!(frames[0].starts_with("[0.000, \"o\", \"\\u001b[2J"));
+		assert!(frames.last().unwrap().starts_with("[0.900, "));
+		assert_eq!(frames[0].matches("\\r\\n").count(), CAST_HEIGHT as usize - 1);
+		assert!(frames[1..]
+			.iter()
+			.all(|frame| !frame.contains("\\r\\n") && frame.len() < frames[0].len()));
+		// The accent color of the theme is kept.
+		assert!(frames[0].contains(&format!("38;5;{}m", app.config.theme.accent)));
+	}
+
+	#[test]
+	fn test_picture() {
+		let mut app = App::new();
+		app.start_game(GameMode::Marathon, None, Some(3));
+		app.input(ReplayInput::Press(Action::DasLeft));
+		app.input(ReplayInput::Press(Action::HardDrop));
+		(0..10).for_each(|_| app.tick());
+		app.input(ReplayInput::Press(Action::HardDrop));
+		(0..10).for_each(|_| app.tick());
+		app.replay.result = Some(ReplayResult {
+			ticks: app.elapsed_ticks,
+			score: app.score,
+			lines: app.stats.lines,
+			level: app.level,
+		});
+
+		// Every cell is a square of the color of its tetromino, inside the border.
+		let picture = app.board_picture();
+		assert_eq!(picture.width, 10 * CELL_SIZE + 9);
+		assert_eq!(picture.height, 22 * CELL_SIZE + 9);
+		let palette = picture::palette(&app.config);
+		let first = app.playfield[app.playfield.len() - 1][app.board_columns().start];
+		let pixel = picture.pixels[(picture.height - 6) * picture.width + 6];
+		assert_eq!(palette[pixel as usize], picture::rgb(first.color()));
+		let accent = picture::rgb(app.config.theme.accent_color());
+		assert_eq!(palette[picture.pixels[0] as usize], accent);
+
+		// A replay is pictured after some pieces, or at its end.
+		let replay = &app.replay;
+		assert_eq!(picture::replay_picture(replay, &app.config, None), picture);
+		assert_ne!(picture::replay_picture(replay, &app.config, Some(1)), picture);
+		assert!(picture.png(&palette).unwrap().starts_with(b"\x89PNG"));
+		let gif = picture::replay_gif(replay, &app.config).unwrap();
+		assert!(gif.starts_with(b"GIF89a"));
+
+		// Boards of modes without replays are pictured while they are played.
+		let _data = TemporaryDataDirectory::new("picture");
+		let mut sandbox = App::with_mode(GameMode::Sandbox);
+		sandbox.screen = Screen::Playing;
+		let control_p = KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL);
+		handle_key_events(control_p, &mut sandbox).unwrap();
+		let message = sandbox.status_message.clone().unwrap();
+		let saved = message.strip_prefix("Screenshot saved as ").unwrap();
+		let contents = std::fs::read(saved).unwrap();
+		assert_eq!(contents, sandbox.board_picture().png(&palette).unwrap());
+	}
+
+	#[test]
+	fn test_fumen() {
+		// An empty page, and a T at spawn on the bottom row.
+		let pages = fumen::parse("https://harddrop.com/fumen/?v115@vhAAgH").unwrap();
+		assert_eq!(pages, vec![FumenPage::default()]);
+		assert_eq!(fumen::encode(&pages), "v115@vhAAgH");
+		let t = FumenPiece {
+			kind: TetrominoKind::T,
+			rotation: 0,
+			x: 4,
+			y: 0,
+		};
+		let page = FumenPage {
+			piece: Some(t),
+			..FumenPage::default()
+		};
+		assert_eq!(fumen::encode(&[page]), "v115@vhAVQJ");
+		assert!(fumen::parse("v114@vhAAgH").is_err());
+		assert!(fumen::parse("v115@vh").is_err());
+
+		// Garbage, comments, rotations and unchanged fields come back as they were written.
+		let mut first = FumenPage {
+			piece: Some(t),
+			comment: String::from("Hello, world! 100% é"),
+			..FumenPage::default()
+		};
+		first.field[22][0] = PlayFieldCell::filled(None);
+		first.field[22][9] = PlayFieldCell::filled(Some(TetrominoKind::J));
+		first.field[23][3] = PlayFieldCell::filled(None);
+		let mut second = FumenPage {
+			piece: Some(FumenPiece {
+				kind: TetrominoKind::I,
+				rotation: 1,
+				x: 0,
+				y: 2,
+			}),
+			comment: first.comment.clone(),
+			lock: false,
+			..first.clone()
+		};
+		second.field[21][3] = PlayFieldCell::filled(Some(TetrominoKind::T));
+		let third = FumenPage {
+			field: second.field.clone(),
+			comment: String::from("Done"),
+			..FumenPage::default()
+		};
+		let pages = vec![first, second, third];
+		let data = fumen::encode(&pages);
+		assert_eq!(fumen::parse(&data).unwrap(), pages);
+
+		// The first board becomes a sandbox setup, with the pieces as its queue.
+		let setup = fumen::setup(&pages);
+		assert_eq!(setup.name, "Hello, world! 100% é");
+		assert_eq!(setup.queue, vec![TetrominoKind::T, TetrominoKind::I]);
+		assert_eq!(setup.board.len(), 1);
+		let mut app = App::new();
+		app.start_game(GameMode::Sandbox, Some(setup), None);
+		let bottom = &app.playfield[app.playfield.len() - 1];
+		assert_eq!(bottom[PLAYFIELD_PADDING].kind, None);
+		assert!(bottom[PLAYFIELD_PADDING].landed);
+		assert_eq!(app.current_tetromino.kind(), Some(TetrominoKind::T));
+		let page = app.fumen_page().unwrap();
+		assert_eq!(page.field[..23], pages[0].field[..23]);
+		assert_eq!(page.piece.map(|piece| piece.kind), Some(TetrominoKind::T));
+
+		// A replay becomes a page for every placement and one for the end.
+		app.start_game(GameMode::Marathon, None, Some(3));
+		app.input(ReplayInput::Press(Action::DasLeft));
+		app.input(ReplayInput::Press(Action::HardDrop));
+		(0..10).for_each(|_| app.tick());
+		app.input(ReplayInput::Press(Action::HardDrop));
+		(0..10).for_each(|_| app.tick());
+		let pages = fumen::replay_pages(&app.replay).unwrap();
+		assert_eq!(pages.len(), 3);
+		assert_eq!(pages[2].field, app.fumen_field().unwrap());
+		// Each piece is where the next board has it.
+		let piece = pages[0].piece.unwrap();
+		for (x, y) in piece.cells() {
+			assert!(pages[1].field[22 - y as usize][x as usize].landed);
+		}
+		let data = fumen::encode(&pages);
+		assert_eq!(fumen::parse(&data).unwrap(), pages);
+	}
+
 	// Add more test functions for other methods as needed
 }
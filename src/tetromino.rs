@@ -1,9 +1,81 @@
+use ratatui::style::Color;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 
 pub struct Tetromino {
 	pub rotations: [[[bool; 4]; 4]; 4],
 }
 
+/// The seven tetromino types, in the order of [`TETROMINO_SHAPES`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TetrominoKind {
+	I,
+	J,
+	L,
+	O,
+	S,
+	T,
+	Z,
+}
+
+impl TetrominoKind {
+	/// Every tetromino type, in the order of [`TETROMINO_SHAPES`].
+	pub const ALL: [TetrominoKind; 7] = [
+		TetrominoKind::I,
+		TetrominoKind::J,
+		TetrominoKind::L,
+		TetrominoKind::O,
+		TetrominoKind::S,
+		TetrominoKind::T,
+		TetrominoKind::Z,
+	];
+
+	/// Returns the shape of this tetromino type.
+	pub fn shape(&self) -> Tetromino {
+		TETROMINO_SHAPES[*self as usize]
+	}
+
+	/// Returns the letter naming this tetromino type.
+	pub fn letter(&self) -> char {
+		"IJLOSTZ".as_bytes()[*self as usize] as char
+	}
+
+	/// Returns the guideline color of this tetromino type.
+	pub fn color(&self) -> Color {
+		match self {
+			TetrominoKind::I => Color::Cyan,
+			TetrominoKind::J => Color::Blue,
+			TetrominoKind::L => Color::Indexed(208),
+			TetrominoKind::O => Color::Yellow,
+			TetrominoKind::S => Color::Green,
+			TetrominoKind::T => Color::Magenta,
+			TetrominoKind::Z => Color::Red,
+		}
+	}
+
+	/// Looks up a tetromino type by its letter, ignoring case.
+	pub fn from_letter(letter: char) -> Option<TetrominoKind> {
+		"IJLOSTZ"
+			.find(letter.to_ascii_uppercase())
+			.map(|index| TetrominoKind::ALL[index])
+	}
+}
+
+impl Tetromino {
+	/// The shape without any cells, held when nothing is in the hold.
+	pub const EMPTY: Tetromino = Tetromino {
+		rotations: [[[false; 4]; 4]; 4],
+	};
+
+	/// Returns the type of this tetromino, or `None` for the empty hold.
+	pub fn kind(&self) -> Option<TetrominoKind> {
+		TETROMINO_SHAPES
+			.iter()
+			.position(|shape| shape == self)
+			.map(|index| TetrominoKind::ALL[index])
+	}
+}
+
 /// pre-defined tetromino shapes
 pub const TETROMINO_SHAPES: [Tetromino; 7] = [
 	Tetromino {
@@ -8,10 +8,14 @@ use crate::{
 };
 use crossterm::{
 	event::{
+		DisableFocusChange,
 		DisableMouseCapture,
+		EnableFocusChange,
 		EnableMouseCapture,
+		KeyboardEnhancementFlags,
+		PopKeyboardEnhancementFlags,
+		PushKeyboardEnhancementFlags,
 	},
-	style::Color,
 	terminal::{
 		self,
 		EnterAlternateScreen,
@@ -19,7 +23,8 @@ use crossterm::{
 	},
 };
 use ratatui::{
-	backend::Backend, widgets, Terminal
+	backend::Backend,
+	Terminal,
 };
 use std::{
 	io,
@@ -36,12 +41,18 @@ pub struct Tui<B: Backend> {
 	terminal: Terminal<B>,
 	/// Terminal event handler.
 	pub events: EventHandler,
+	/// Does the terminal report key releases?
+	pub key_releases: bool,
 }
 
 impl<B: Backend> Tui<B> {
 	/// Constructs a new instance of [`Tui`].
 	pub fn new(terminal: Terminal<B>, events: EventHandler) -> Self {
-		Self { terminal, events }
+		Self {
+			terminal,
+			events,
+			key_releases: false,
+		}
 	}
 
 	/// Initializes the terminal interface.
@@ -49,13 +60,27 @@ impl<B: Backend> Tui<B> {
 	/// It enables the raw mode and sets terminal properties.
 	pub fn init(&mut self) -> AppResult<()> {
 		terminal::enable_raw_mode()?;
-		crossterm::execute!(io::stderr(), EnterAlternateScreen, EnableMouseCapture)?;
+		crossterm::execute!(
+			io::stderr(),
+			EnterAlternateScreen,
+			EnableMouseCapture,
+			EnableFocusChange
+		)?;
+		// Held keys can only be told apart from repeated presses when releases are reported.
+		self.key_releases = terminal::supports_keyboard_enhancement().unwrap_or(false);
+		if self.key_releases {
+			crossterm::execute!(
+				io::stderr(),
+				PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+			)?;
+		}
 
 		// Define a custom panic hook to reset the terminal properties.
 		// This way, you won't have your terminal messed up if an unexpected error happens.
+		let key_releases = self.key_releases;
 		let panic_hook = panic::take_hook();
 		panic::set_hook(Box::new(move |panic| {
-			Self::reset().expect("failed to reset the terminal");
+			Self::reset(key_releases).expect("failed to reset the terminal");
 			panic_hook(panic);
 		}));
 
@@ -68,9 +93,8 @@ impl<B: Backend> Tui<B> {
 	///
 	/// [`Draw`]: ratatui::Terminal::draw
 	/// [`rendering`]: crate::ui:render
-	pub fn draw(&mut self, app: &mut App, accent_color: Color, border_type: widgets::BorderType) -> AppResult<()> {
-		self.terminal
-			.draw(|frame| ui::render(app, frame, accent_color.into(), border_type))?;
+	pub fn draw(&mut self, app: &mut App) -> AppResult<()> {
+		self.terminal.draw(|frame| ui::render(app, frame))?;
 		Ok(())
 	}
 
@@ -78,9 +102,17 @@ impl<B: Backend> Tui<B> {
 	///
 	/// This function is also used for the panic hook to revert
 	/// the terminal properties if unexpected errors occur.
-	fn reset() -> AppResult<()> {
+	fn reset(key_releases: bool) -> AppResult<()> {
+		if key_releases {
+			crossterm::execute!(io::stderr(), PopKeyboardEnhancementFlags)?;
+		}
 		terminal::disable_raw_mode()?;
-		crossterm::execute!(io::stderr(), LeaveAlternateScreen, DisableMouseCapture)?;
+		crossterm::execute!(
+			io::stderr(),
+			LeaveAlternateScreen,
+			DisableMouseCapture,
+			DisableFocusChange
+		)?;
 		Ok(())
 	}
 
@@ -88,7 +120,7 @@ impl<B: Backend> Tui<B> {
 	///
 	/// It disables the raw mode and reverts back the terminal properties.
 	pub fn exit(&mut self) -> AppResult<()> {
-		Self::reset()?;
+		Self::reset(self.key_releases)?;
 		self.terminal.show_cursor()?;
 		Ok(())
 	}
@@ -2,44 +2,130 @@ use ratatui::{
 	prelude::*,
 	widgets::{
 		Block,
-		BorderType,
 		Borders,
+		Clear,
 		Paragraph,
+		Wrap,
 	},
 };
 
 use crate::{
-	app::App,
-	clap::clap_parse,
+	app::{
+		format_ticks,
+		App,
+	},
+	finesse::inputs_string,
+	master::MAX_LEVEL,
+	mode::GameMode,
+	puzzle::PuzzleStatus,
+	screen::Screen,
 };
 
+//----------[ helpers ]----------//
+/// Returns a rect of a size centered on an area of the frame.
+fn calculate_centered_rect(r: Rect, x: u16, y: u16, frame: &mut Frame) -> Rect {
+	let popup_layout = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([
+			Constraint::Length((frame.size().height / 2).saturating_sub(y / 2)),
+			Constraint::Length(y),
+			Constraint::Length((frame.size().height / 2).saturating_sub(y / 2)),
+		])
+		.split(r);
+
+	Layout::default()
+		.direction(Direction::Horizontal)
+		.constraints([
+			Constraint::Length((frame.size().width / 2).saturating_sub(x / 2)),
+			Constraint::Length(x),
+			Constraint::Length((frame.size().width / 2).saturating_sub(x / 2)),
+		])
+		.split(popup_layout[1])[1]
+}
+//-------------------------------//
+
 /// Renders the user interface widgets.
-pub fn render(app: &mut App, frame: &mut Frame, accent_color: Color, border_type: BorderType) {
-	let binding = clap_parse();
-
-	let control_buttons: bool = *binding.get_one("ControlButtons").unwrap();
-
-	//----------[ helpers ]----------//
-	fn calculate_centered_rect(r: Rect, x: u16, y: u16, frame: &mut Frame) -> Rect {
-		let popup_layout = Layout::default()
-			.direction(Direction::Vertical)
-			.constraints([
-				Constraint::Length(frame.size().height / 2 - y / 2),
-				Constraint::Length(y),
-				Constraint::Length(frame.size().height / 2 - y / 2),
-			])
-			.split(r);
-
-		Layout::default()
-			.direction(Direction::Horizontal)
-			.constraints([
-				Constraint::Length(frame.size().width / 2 - x / 2),
-				Constraint::Length(x),
-				Constraint::Length(frame.size().width / 2 - x / 2),
-			])
-			.split(popup_layout[1])[1]
+pub fn render(app: &mut App, frame: &mut Frame) {
+	match app.screen {
+		Screen::Playing | Screen::Paused | Screen::Watching => render_game(app, frame),
+		Screen::Settings if matches!(app.settings.back, Screen::Playing | Screen::Paused) => {
+			render_game(app, frame);
+		}
+		Screen::Settings => {
+			let area = calculate_centered_rect(frame.size(), 44, 32, frame);
+			render_settings(app, frame, area);
+		}
+		_ => {
+			let area = calculate_centered_rect(frame.size(), 40, 28, frame);
+			render_menu(app, frame, area);
+		}
 	}
-	//-------------------------------//
+}
+
+/// Renders the items of the current screen in an area, with details below.
+fn render_menu(app: &mut App, frame: &mut Frame, area: Rect) {
+	let accent_color = app.config.theme.accent_color();
+	let border_type = app.config.theme.border.border_type();
+
+	let items = app.menu_items();
+	let mut text = String::new();
+	app.menu_buttons = vec![];
+	for (index, item) in items.iter().enumerate() {
+		let marker = if index == app.menu_selected { ">" } else { " " };
+		text += &format!("{} {}\n", marker, item);
+		app.menu_buttons.push(Rect {
+			x: area.x + 1,
+			y: area.y + 1 + index as u16,
+			width: area.width.saturating_sub(2),
+			height: 1,
+		});
+	}
+	text += &format!("\n{}", app.menu_details());
+
+	frame.render_widget(Clear, area);
+	frame.render_widget(
+		Paragraph::new(text)
+			.block(
+				Block::bordered()
+					.title(app.screen.title())
+					.title_alignment(Alignment::Center)
+					.border_type(border_type)
+					.border_style(Style::default().fg(accent_color)),
+			)
+			.style(Style::default().fg(Color::White))
+			.wrap(Wrap { trim: false }),
+		area,
+	);
+}
+
+/// Renders the settings menu in an area, scrolled to the selected option.
+fn render_settings(app: &App, frame: &mut Frame, area: Rect) {
+	let accent_color = app.config.theme.accent_color();
+	let border_type = app.config.theme.border.border_type();
+
+	let (text, selected_line) = app.settings_string();
+	let scroll = (selected_line as u16).saturating_sub(area.height.saturating_sub(3));
+	frame.render_widget(Clear, area);
+	frame.render_widget(
+		Paragraph::new(text)
+			.block(
+				Block::bordered()
+					.title(Screen::Settings.title())
+					.title_alignment(Alignment::Center)
+					.border_type(border_type)
+					.border_style(Style::default().fg(accent_color)),
+			)
+			.style(Style::default().fg(Color::White))
+			.scroll((scroll, 0)),
+		area,
+	);
+}
+
+/// Renders the game, with its overlays.
+fn render_game(app: &mut App, frame: &mut Frame) {
+	let accent_color = app.config.theme.accent_color();
+	let border_type = app.config.theme.border.border_type();
+	let control_buttons = app.config.layout.buttons;
 
 	//----------[ layouts ]----------//
 	let main_layout = Layout::default()
@@ -117,19 +203,148 @@ pub fn render(app: &mut App, frame: &mut Frame, accent_color: Color, border_type
 	app.buttons.push(side_layout[0]);
 	app.buttons.push(side_layout[1]);
 
+	app.playfield_area = playfield_split[0];
+
 	app.check_for_highscore();
 	//-------------------------------//
 
 	//----------[ render widgets ]----------//
 	{
+		// The board is hidden while paused, so that the game cannot be planned ahead.
+		let hidden = app.screen == Screen::Paused
+			|| (app.screen == Screen::Settings && app.settings.back == Screen::Paused);
+
 		// Render the main interface.
-		frame.render_widget(
-			Paragraph::new(format!(
-				"Score: {}\nHigh Score:{}\nLevel:{}\n{}",
+		let status = match app.mode {
+			GameMode::Master => format!(
+				"Grade: {}\nScore: {}\nNext: {}\nLevel: {:03}/{:03}\nTime: {}",
+				app.master.grade,
 				app.score,
-				app.high_score,
+				app.master
+					.grade
+					.next_requirement()
+					.map_or(String::from("??????"), |score| score.to_string()),
 				app.level,
-				if app.paused {
+				(app.level / 100 * 100 + 100).min(MAX_LEVEL),
+				format_ticks(app.elapsed_ticks)
+			),
+			GameMode::Daily => format!(
+				"Daily {}{}\nScore: {}\nLevel:{}",
+				app.daily
+					.map_or(String::new(), |challenge| challenge.date.to_string()),
+				if app.daily.is_some_and(|challenge| challenge.practice) {
+					" (practice)"
+				} else {
+					""
+				},
+				app.score,
+				app.level
+			),
+			GameMode::Puzzle => match &app.puzzle {
+				Some(state) => format!(
+					"{}\nGoal: {}\nPieces: {}\nLines: {}",
+					state.puzzle.name,
+					state
+						.puzzle
+						.goal
+						.map_or(String::new(), |goal| goal.description()),
+					state.pieces,
+					state.lines
+				),
+				None => String::from("No puzzle loaded"),
+			},
+			GameMode::Sandbox if app.editing => format!(
+				"Sandbox - Editing\n[Left/Right click] paint/erase\nBrush: {} [1-7, 8 garbage]\n[ijlostz] queue [IJLOSTZ] hold\n[Bksp] \
+				 unqueue [Del] clear queue [c] clear board\n[Tab] play",
+				app.brush.map_or('X', |kind| kind.letter())
+			),
+			GameMode::Sandbox => String::from(
+				"Sandbox\n[Tab] edit [^Z/^Y] undo/redo\n[^S/^L] save/load setup\n[^E] export fumen [^P] \
+				 screenshot",
+			),
+			GameMode::Opener => match &app.opener {
+				Some(state) => format!(
+					"{}\n{}\nPlaced: {}/{}\nMistakes: {}\n{}",
+					state.opener.name,
+					state.opener.description,
+					app.opener_progress(),
+					state.opener.kinds().len(),
+					state.mistakes,
+					if app.game_over || app.opener_target().is_some() {
+						"[^Z/^Y] undo/redo"
+					} else {
+						"Hold this tetromino"
+					}
+				),
+				None => String::from("No opener loaded"),
+			},
+			GameMode::PerfectClear => match &app.pc {
+				Some(trainer) => format!(
+					"Perfect clear trainer\nSolved: {} Failed: {}\n[r] retry [n] next board{}",
+					trainer.solved,
+					trainer.failed,
+					if app
+						.puzzle
+						.as_ref()
+						.is_some_and(|state| state.status == PuzzleStatus::Failed)
+					{
+						format!("\nSolution: {}", app.pc_solution_string())
+					} else {
+						String::new()
+					}
+				),
+				None => String::from("No board loaded"),
+			},
+			GameMode::Finesse => match &app.finesse_trainer {
+				Some(trainer) => format!(
+					"Finesse trainer\nCorrect: {} Wrong: {}\n[a/d] tap [A/D] DAS",
+					trainer.correct, trainer.wrong
+				),
+				None => String::from("No trainer loaded"),
+			},
+			_ => format!(
+				"Score: {}\nHigh Score:{}\nLevel:{}",
+				app.score, app.high_score, app.level
+			),
+		};
+		let status = if app.mode == GameMode::Finesse {
+			status
+		} else {
+			match &app.finesse.last_fault {
+				Some(optimal) => format!(
+					"{}\nFinesse faults: {} (optimal: {})",
+					status,
+					app.finesse.faults,
+					inputs_string(optimal)
+				),
+				None => format!("{}\nFinesse faults: {}", status, app.finesse.faults),
+			}
+		};
+		let status = format!("{}\n{}", status, app.stats_summary());
+		let status = if app.practice {
+			format!("{}\nPractice [^Z/^Y] undo/redo", status)
+		} else {
+			status
+		};
+		let status = match &app.status_message {
+			Some(message) => format!("{}\n{}", status, message),
+			None => status,
+		};
+		frame.render_widget(
+			Paragraph::new(format!(
+				"{}\n{}",
+				status,
+				if app
+					.puzzle
+					.as_ref()
+					.is_some_and(|state| state.status == PuzzleStatus::Solved)
+				{
+					"SOLVED"
+				} else if app.opener_complete() {
+					"OPENER COMPLETE"
+				} else if app.game_over {
+					"GAME OVER"
+				} else if hidden {
 					"PAUSED"
 				} else {
 					""
@@ -137,7 +352,11 @@ pub fn render(app: &mut App, frame: &mut Frame, accent_color: Color, border_type
 			))
 			.block(
 				Block::bordered()
-					.title("┤ TETRS ├")
+					.title(if app.viewer.is_some() {
+						Screen::Watching.title()
+					} else {
+						Screen::Playing.title()
+					})
 					.title_alignment(Alignment::Center)
 					.border_type(border_type),
 			)
@@ -150,7 +369,11 @@ pub fn render(app: &mut App, frame: &mut Frame, accent_color: Color, border_type
 		{
 			// Render the playfield.
 			frame.render_widget(
-				Paragraph::new(app.playfield_string())
+				Paragraph::new(if hidden {
+					vec![]
+				} else {
+					app.playfield_lines()
+				})
 					.block(
 						Block::default()
 							.borders(Borders::ALL)
@@ -165,7 +388,11 @@ pub fn render(app: &mut App, frame: &mut Frame, accent_color: Color, border_type
 			{
 				//preview
 				frame.render_widget(
-					Paragraph::new(app.tetromino_queue_string())
+					Paragraph::new(if hidden {
+						String::new()
+					} else {
+						app.tetromino_queue_string()
+					})
 						.block(
 							Block::default()
 								.borders(Borders::ALL)
@@ -179,7 +406,11 @@ pub fn render(app: &mut App, frame: &mut Frame, accent_color: Color, border_type
 				);
 				//swap
 				frame.render_widget(
-					Paragraph::new(app.tetromino_string(app.swap_tetromino))
+					Paragraph::new(if hidden {
+						String::new()
+					} else {
+						app.tetromino_string(app.swap_tetromino)
+					})
 						.block(
 							Block::default()
 								.borders(Borders::ALL)
@@ -193,6 +424,95 @@ pub fn render(app: &mut App, frame: &mut Frame, accent_color: Color, border_type
 				);
 			}
 			//-------------------------------//
+			//----------[ Overlays ]----------//
+			let overlay = if let Some(name) = &app.name_entry {
+				Some((
+					"┤ NEW HIGH SCORE ├",
+					format!("Name: {}_\n\n[Enter] save\n[Esc] skip", name),
+				))
+			} else if app.show_leaderboard {
+				Some(("┤ LEADERBOARD ├", app.leaderboard_string()))
+			} else if app.show_stats {
+				Some(("┤ STATS ├", app.stats_string()))
+			} else {
+				None
+			};
+			if let Some((title, text)) = overlay {
+				frame.render_widget(Clear, playfield_split[0]);
+				frame.render_widget(
+					Paragraph::new(text)
+						.block(
+							Block::bordered()
+								.title(title)
+								.title_alignment(Alignment::Center)
+								.border_type(border_type)
+								.border_style(Style::default().fg(accent_color)),
+						)
+						.style(Style::default().fg(Color::White)),
+					playfield_split[0],
+				);
+			}
+			if app.screen == Screen::Paused {
+				render_menu(app, frame, playfield_split[0]);
+			}
+			if let Some(seconds) = app.countdown_seconds() {
+				let area = Rect {
+					x: playfield_split[0].x + (playfield_split[0].width / 2).saturating_sub(3),
+					y: playfield_split[0].y + (playfield_split[0].height / 2).saturating_sub(1),
+					width: 7,
+					height: 3,
+				};
+				frame.render_widget(Clear, area);
+				frame.render_widget(
+					Paragraph::new(seconds.to_string())
+						.block(
+							Block::bordered()
+								.border_type(border_type)
+								.border_style(Style::default().fg(accent_color)),
+						)
+						.style(Style::default().fg(Color::White))
+						.centered(),
+					area,
+				);
+			}
+			// The transport bar of a replay is shown below the game.
+			if app.viewer.is_some() {
+				let area = Rect {
+					x: main_layout[0].x + 1,
+					y: main_layout[0].bottom().saturating_sub(6),
+					width: main_layout[0].width.saturating_sub(2),
+					height: 5.min(main_layout[0].height),
+				};
+				frame.render_widget(Clear, area);
+				frame.render_widget(
+					Paragraph::new(app.viewer_string())
+						.block(
+							Block::bordered()
+								.border_type(border_type)
+								.border_style(Style::default().fg(accent_color)),
+						)
+						.style(Style::default().fg(Color::White))
+						.wrap(Wrap { trim: false }),
+					area,
+				);
+			}
+			// The settings are shown beside the game when there is room, so that changes can be
+			// seen as they are made.
+			if app.screen == Screen::Settings {
+				let beside = Rect {
+					x: main_layout[0].x + 1,
+					y: centered.y,
+					width: centered.x.saturating_sub(main_layout[0].x + 2),
+					height: centered.height,
+				};
+				let area = if beside.width >= 40 {
+					beside
+				} else {
+					playfield_split[0]
+				};
+				render_settings(app, frame, area);
+			}
+			//--------------------------------//
 		}
 		//---------------------------------//
 		//----------[ Control Buttons ]----------//
@@ -0,0 +1,280 @@
+use crate::{
+	app::{
+		format_ticks,
+		App,
+	},
+	config::{
+		Config,
+		Handling,
+	},
+	replay::{
+		self,
+		Replay,
+		ReplayInput,
+	},
+	screen::Screen,
+};
+
+/// Playback speeds, as ticks of the replay per tick of the terminal.
+pub const SPEEDS: [f64; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+/// Index of the normal speed in [`SPEEDS`].
+const NORMAL_SPEED: usize = 2;
+/// Pieces between two snapshots that seeking starts from.
+pub const SNAPSHOT_INTERVAL: u32 = 10;
+/// Replays listed on the replays screen.
+const REPLAY_LIST_SIZE: usize = 15;
+/// Inputs shown below the game.
+const SHOWN_INPUTS: usize = 4;
+
+//----------[ Structs ]----------//
+/// State of the playback of a replay.
+#[derive(Debug, Clone)]
+pub struct ReplayViewer {
+	pub replay: Replay,
+	/// Index of the next input to play.
+	pub next_input: usize,
+	pub playing: bool,
+	/// Index of the speed in [`SPEEDS`].
+	pub speed: usize,
+	/// Ticks of the replay owed to the terminal at slow speeds.
+	pub progress: f64,
+	/// Copies of the game about every [`SNAPSHOT_INTERVAL`] pieces, with the index of their next
+	/// input, in order.
+	pub snapshots: Vec<(usize, App)>,
+	/// Handling of the player, given back when the viewer closes.
+	pub handling: Handling,
+}
+//-------------------------------//
+
+impl App {
+	//----------[ Replays screen ]----------//
+	/// Shows the replays screen with the newest saved replays.
+	pub fn open_replays(&mut self) {
+		self.replay_list = replay::load_replays(REPLAY_LIST_SIZE);
+		self.show_screen(Screen::Replays);
+	}
+
+	/// Returns the names of the listed replays.
+	pub fn replay_names(&self) -> Vec<String> {
+		self.replay_list
+			.iter()
+			.map(|replay| match replay {
+				Ok(replay) => format!("{} {}", replay.date.format("%Y-%m-%d %H:%M"), replay.mode.name()),
+				Err(_) => String::from("Invalid replay"),
+			})
+			.collect()
+	}
+
+	/// Returns the details of the selected replay.
+	pub fn replay_details(&self) -> String {
+		let details = match self.replay_list.get(self.menu_selected) {
+			None => String::from("No replays saved yet"),
+			Some(Err(error)) => error.clone(),
+			Some(Ok(replay)) => {
+				let result = replay.result.map_or(String::from("Unfinished"), |end| {
+					format!(
+						"Score: {}\nLines: {}\nLevel: {}\nTime: {}",
+						end.score,
+						end.lines,
+						end.level,
+						format_ticks(end.ticks)
+					)
+				});
+				format!("{}\n{}\nInputs: {}", replay.ruleset, result, replay.inputs.len())
+			}
+		};
+		format!("{}\n\n[Enter] watch [Esc] back", details)
+	}
+	//--------------------------------------//
+
+	//----------[ Playback ]----------//
+	/// Plays a replay back from its start, keeping the options and the leaderboard.
+	pub fn watch_replay(&mut self, replay: Replay) {
+		let mut game = replay.game();
+		let handling = self.config.handling;
+		game.config = Config {
+			handling: replay.handling,
+			..std::mem::take(&mut self.config)
+		};
+		game.file_config = std::mem::take(&mut self.file_config);
+		game.leaderboard = std::mem::take(&mut self.leaderboard);
+		game.replay_list = std::mem::take(&mut self.replay_list);
+		game.screen = Screen::Watching;
+		game.viewer = Some(Box::new(ReplayViewer {
+			replay,
+			next_input: 0,
+			playing: true,
+			speed: NORMAL_SPEED,
+			progress: 0.0,
+			snapshots: vec![],
+			handling,
+		}));
+		game.take_snapshot();
+		*self = game;
+	}
+
+	/// Stops the playback and goes back to the replays screen.
+	pub fn close_viewer(&mut self) {
+		if let Some(viewer) = self.viewer.take() {
+			self.config.handling = viewer.handling;
+		}
+		self.open_replays();
+	}
+
+	/// Keeps a copy of the game to seek from, without the replays and the leaderboard around it.
+	fn take_snapshot(&mut self) {
+		let Some(mut viewer) = self.viewer.take() else {
+			return;
+		};
+		let replay_list = std::mem::take(&mut self.replay_list);
+		let leaderboard = std::mem::take(&mut self.leaderboard);
+		viewer.snapshots.push((viewer.next_input, self.clone()));
+		self.replay_list = replay_list;
+		self.leaderboard = leaderboard;
+		self.viewer = Some(viewer);
+	}
+
+	/// Has the replay been played to its end?
+	pub fn replay_finished(&self) -> bool {
+		self.viewer
+			.as_ref()
+			.is_none_or(|viewer| viewer.replay.finished(self, viewer.next_input))
+	}
+
+	/// Plays the inputs of the current tick and advances the game by one tick.
+	pub fn step_replay(&mut self) {
+		let Some(mut viewer) = self.viewer.take() else {
+			return;
+		};
+		viewer.next_input = viewer.replay.step(self, viewer.next_input);
+		let snapshot_due = viewer.snapshots.last().is_some_and(|(_, last)| {
+			self.stats.pieces / SNAPSHOT_INTERVAL > last.stats.pieces / SNAPSHOT_INTERVAL
+		});
+		self.viewer = Some(viewer);
+		if snapshot_due {
+			self.take_snapshot();
+		}
+	}
+
+	/// Plays the replay on at its speed, stopping at its end.
+	pub fn tick_viewer(&mut self) {
+		let Some(viewer) = self.viewer.as_mut() else {
+			return;
+		};
+		if !viewer.playing {
+			return;
+		}
+		viewer.progress += SPEEDS[viewer.speed];
+		while self.viewer.as_ref().is_some_and(|viewer| viewer.progress >= 1.0) {
+			if self.replay_finished() {
+				self.set_playing(false);
+				return;
+			}
+			self.step_replay();
+			if let Some(viewer) = self.viewer.as_mut() {
+				viewer.progress -= 1.0;
+			}
+		}
+	}
+
+	/// Plays or pauses the replay.
+	fn set_playing(&mut self, playing: bool) {
+		if let Some(viewer) = self.viewer.as_mut() {
+			viewer.playing = playing;
+			viewer.progress = 0.0;
+		}
+	}
+
+	/// Pauses a playing replay, or plays a paused one.
+	pub fn toggle_playback(&mut self) {
+		let playing = self.viewer.as_ref().is_some_and(|viewer| viewer.playing);
+		self.set_playing(!playing && !self.replay_finished());
+	}
+
+	/// Pauses the replay and advances it by a single tick.
+	pub fn step_frame(&mut self) {
+		self.set_playing(false);
+		if !self.replay_finished() {
+			self.step_replay();
+		}
+	}
+
+	/// Plays the replay slower (-1) or faster (1).
+	pub fn change_speed(&mut self, delta: i32) {
+		if let Some(viewer) = self.viewer.as_mut() {
+			viewer.speed = (viewer.speed as i32 + delta).clamp(0, SPEEDS.len() as i32 - 1) as usize;
+		}
+	}
+
+	/// Seeks to the moment the given number of pieces were locked, or the end of the replay.
+	pub fn seek_piece(&mut self, piece: u32) {
+		let Some(mut viewer) = self.viewer.take() else {
+			return;
+		};
+		// Start over from the last snapshot up to the piece, unless the game is already closer.
+		let index = viewer
+			.snapshots
+			.iter()
+			.rposition(|(_, snapshot)| snapshot.stats.pieces <= piece)
+			.unwrap_or(0);
+		let snapshot_pieces = viewer.snapshots[index].1.stats.pieces;
+		if piece < self.stats.pieces || snapshot_pieces > self.stats.pieces {
+			let (next_input, mut snapshot) = viewer.snapshots[index].clone();
+			viewer.next_input = next_input;
+			snapshot.replay_list = std::mem::take(&mut self.replay_list);
+			snapshot.leaderboard = std::mem::take(&mut self.leaderboard);
+			*self = snapshot;
+		}
+		viewer.progress = 0.0;
+		self.viewer = Some(viewer);
+		while self.stats.pieces < piece && !self.replay_finished() {
+			self.step_replay();
+		}
+	}
+
+	/// Seeks some pieces back (negative) or forward (positive).
+	pub fn seek_pieces(&mut self, delta: i32) {
+		let piece = (self.stats.pieces as i64 + delta as i64).max(0) as u32;
+		self.seek_piece(piece);
+	}
+
+	/// Returns the transport bar of the replay.
+	pub fn viewer_string(&self) -> String {
+		let Some(viewer) = &self.viewer else {
+			return String::new();
+		};
+		let state = if self.replay_finished() {
+			"END"
+		} else if viewer.playing {
+			"PLAYING"
+		} else {
+			"PAUSED"
+		};
+		let total = viewer
+			.replay
+			.result
+			.map_or(String::from("?"), |end| format_ticks(end.ticks));
+		let inputs = viewer.replay.inputs[..viewer.next_input]
+			.iter()
+			.rev()
+			.take(SHOWN_INPUTS)
+			.rev()
+			.map(|(_, input)| match input {
+				ReplayInput::Handling(_) => String::from("handling"),
+				input => input.to_string(),
+			})
+			.collect::<Vec<String>>()
+			.join(", ");
+		format!(
+			"{} {}x  Piece {}  {} / {}\nInputs: {}\n[Space] play/pause [Up/Down] speed [.] step [[/]] piece \
+			 [{{/}}] 10 pieces [Home] start [Esc] back",
+			state,
+			SPEEDS[viewer.speed],
+			self.stats.pieces,
+			format_ticks(self.elapsed_ticks),
+			total,
+			inputs
+		)
+	}
+	//--------------------------------//
+}